@@ -1,3 +1,132 @@
+use crate::book::lichess_book::LiChessOpeningBook;
+use crate::book::opening_book::OpeningBook;
+use crate::core::position::Position;
+use crate::core::r#move::Move;
+use crate::uci::{config, uci_util};
+use crate::utils::{fen, util};
+use log::info;
+
 pub mod opening_book;
 
 pub mod lichess_book;
+
+/// Looks up the position in the lichess opening book. Returns None when the position is beyond the
+/// book depth, which is the number of plies from the start of the game that are played from the
+/// book, or when the book has no move for it.
+#[allow(dead_code)]
+pub fn probe(position: &Position) -> Option<Move> {
+    probe_opening_book(&LiChessOpeningBook::new(), position, config::get_book_depth())
+}
+
+pub fn probe_opening_book(
+    opening_book: &dyn OpeningBook,
+    position: &Position,
+    book_depth: usize,
+) -> Option<Move> {
+    if position.game_ply() >= book_depth {
+        info!(
+            "Not using the opening book because {} plies have been played and the book depth is {book_depth}",
+            position.game_ply()
+        );
+        return None;
+    }
+    info!("getting opening book move for position: {}", fen::write(position));
+    match opening_book.get_opening_move(position) {
+        Ok(raw_move) => {
+            // a corrupt or mismatched book could suggest a move that can't be played here
+            let legal_move = util::find_generated_move(position.legal_moves(), &raw_move);
+            if legal_move.is_none() {
+                uci_util::send_to_gui(&format!(
+                    "info string ignoring illegal opening book move {raw_move}"
+                ));
+            }
+            legal_move
+        }
+        Err(error) => {
+            info!("Failed to retrieve opening book move: {error}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::opening_book::ErrorKind;
+    use crate::core::r#move;
+    use crate::core::r#move::RawMove;
+
+    const BOOK_DEPTH: usize = 10;
+
+    struct FixedOpeningBook {
+        move_string: &'static str,
+    }
+
+    impl OpeningBook for FixedOpeningBook {
+        fn get_opening_move(&self, _position: &Position) -> Result<RawMove, ErrorKind> {
+            util::parse_move(self.move_string.to_string()).ok_or(ErrorKind::NoOpeningMovesFound)
+        }
+    }
+
+    struct FirstLegalMoveOpeningBook {}
+
+    impl OpeningBook for FirstLegalMoveOpeningBook {
+        fn get_opening_move(&self, position: &Position) -> Result<RawMove, ErrorKind> {
+            let legal_moves = position.legal_moves();
+            legal_moves
+                .first()
+                .map(|mov| r#move::convert_move_to_raw(*mov))
+                .ok_or(ErrorKind::NoOpeningMovesFound)
+        }
+    }
+
+    #[test]
+    fn test_probe_start_position() {
+        let position = Position::new_game();
+        let opening_book = FixedOpeningBook { move_string: "e2e4" };
+        let expected_move = util::create_move_list(&position, "e2e4".to_string()).unwrap()[0];
+        assert_eq!(probe_opening_book(&opening_book, &position, BOOK_DEPTH), Some(expected_move));
+    }
+
+    #[test]
+    fn test_probe_past_book_depth() {
+        let position =
+            Position::from("r1bq1rk1/pp2bppp/2n1pn2/2pp4/3P4/2PBPN2/PP1N1PPP/R1BQ1RK1 w - - 4 11");
+        let opening_book = FixedOpeningBook { move_string: "d4c5" };
+        assert_eq!(probe_opening_book(&opening_book, &position, BOOK_DEPTH), None);
+    }
+
+    #[test]
+    fn test_book_depth_counts_plies_from_the_start_of_the_game() {
+        let moves = "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 g8f6 e1g1 f8e7 f1e1";
+        let positions = util::replay_move_string(&Position::new_game(), moves.to_string()).unwrap();
+        for (ply, expect_book_move) in [(9, true), (10, false), (11, false)] {
+            let position = positions[ply - 1].0;
+            assert_eq!(position.game_ply(), ply);
+            let opening_book = FirstLegalMoveOpeningBook {};
+            assert_eq!(
+                probe_opening_book(&opening_book, &position, BOOK_DEPTH).is_some(),
+                expect_book_move
+            );
+        }
+    }
+
+    #[test]
+    fn test_illegal_book_move_is_not_played() {
+        let opening_book = FixedOpeningBook { move_string: "e2e5" };
+        assert_eq!(probe_opening_book(&opening_book, &Position::new_game(), BOOK_DEPTH), None);
+
+        // the rook is pinned so its move is generated but can't be played
+        let pinned_rook: Position = Position::from("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1");
+        let opening_book = FixedOpeningBook { move_string: "e2d2" };
+        assert_eq!(probe_opening_book(&opening_book, &pinned_rook, BOOK_DEPTH), None);
+        let opening_book = FixedOpeningBook { move_string: "e2e4" };
+        assert!(probe_opening_book(&opening_book, &pinned_rook, BOOK_DEPTH).is_some());
+    }
+
+    #[test]
+    fn test_probe_without_book_move() {
+        let opening_book = FixedOpeningBook { move_string: "" };
+        assert_eq!(probe_opening_book(&opening_book, &Position::new_game(), BOOK_DEPTH), None);
+    }
+}
@@ -6,12 +6,26 @@ use crate::core::piece::{Piece, PieceColor};
 use crate::core::position::Position;
 use crate::core::r#move::{Move, RawMove};
 use crate::utils::{fen, util};
+use once_cell::sync::Lazy;
 use rand::{rng, Rng};
+use rand_xoshiro::rand_core::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
 use reqwest;
 use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
 
 include!("../utils/generated_macro.rs");
 
+/// Chooses between the book moves. It is seeded from the thread rng unless a seed is given so
+/// that games can be reproduced
+static BOOK_RNG: Lazy<Mutex<Xoshiro256PlusPlus>> =
+    Lazy::new(|| Mutex::new(Xoshiro256PlusPlus::seed_from_u64(rng().random())));
+
+pub fn set_random_seed(seed: u64) {
+    *BOOK_RNG.lock().unwrap() = Xoshiro256PlusPlus::seed_from_u64(seed);
+}
+
+#[derive(Default)]
 pub struct LiChessOpeningBook {}
 
 impl LiChessOpeningBook {
@@ -29,7 +43,7 @@ fn get_opening_move(position: &Position) -> Result<RawMove, ErrorKind> {
     let fen = fen::write(position);
     let opening_moves = fetch_opening_moves(&fen)?;
     if !opening_moves.is_empty() {
-        let move_string = weighted_random_move(&opening_moves);
+        let move_string = weighted_random_move(&opening_moves, &mut BOOK_RNG.lock().unwrap());
         let corrected_move_string = map_castling_move_to_uci_format(&move_string, position);
         let raw_chess_move = parse_move(corrected_move_string)?;
         validate_move(position, raw_chess_move)?;
@@ -63,10 +77,9 @@ fn fetch_opening_moves(fen: &str) -> Result<Vec<LiChessMoveData>, ErrorKind> {
     Ok(response.moves)
 }
 
-fn weighted_random_move(moves: &[LiChessMoveData]) -> String {
+fn weighted_random_move(moves: &[LiChessMoveData], rng: &mut impl Rng) -> String {
     let total_games: u32 = moves.iter().map(|m| (m.white + m.black + m.draws) as u32).sum();
 
-    let mut rng = rng();
     let mut pick = rng.random_range(0..total_games);
 
     for mv in moves {
@@ -124,6 +137,22 @@ mod tests {
         assert_eq!(result.err().unwrap(), ErrorKind::NoOpeningMovesFound);
     }
 
+    #[test]
+    fn test_the_same_seed_gives_the_same_book_choices() {
+        let moves: Vec<LiChessMoveData> = ["e2e4", "d2d4", "c2c4", "g1f3"]
+            .iter()
+            .map(|uci| LiChessMoveData { uci: uci.to_string(), white: 40, draws: 30, black: 30 })
+            .collect();
+        let book_choices = |seed: u64| {
+            // hold the lock so that no other test can draw from the rng in between
+            let mut rng = BOOK_RNG.lock().unwrap();
+            *rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+            (0..20).map(|_| weighted_random_move(&moves, &mut *rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(book_choices(7), book_choices(7));
+        assert_ne!(book_choices(7), book_choices(8));
+    }
+
     #[test]
     fn test_map_castling_move_to_uci_format() {
         let position = Position::new_game();
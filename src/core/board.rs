@@ -125,6 +125,18 @@ impl Board {
         None
     }
 
+    /// The color and type of the piece on the square. The color is found from the occupancy of
+    /// each side first so that only the bitboards of that side are searched for the type.
+    pub fn piece_at(&self, square_index: usize) -> Option<(PieceColor, PieceType)> {
+        let mask: u64 = 1 << square_index;
+        let piece_color =
+            PieceColor::iter().find(|&color| self.bitboard_by_color(color) & mask != 0)?;
+        let piece_type = PieceType::iter().find(|&piece_type| {
+            self.bit_boards[piece_color as usize][piece_type as usize] & mask != 0
+        })?;
+        Some((piece_color, piece_type))
+    }
+
     pub fn put_piece(&mut self, square_index: usize, piece: Piece) {
         self.remove_piece(square_index);
         self.bit_boards[piece.piece_color as usize][piece.piece_type as usize] |= 1 << square_index;
@@ -290,6 +302,15 @@ impl Board {
         (bishops & BLACK_SQUARES_MASK).count_ones() >= 2
             || (bishops & WHITE_SQUARES_MASK).count_ones() >= 2
     }
+
+    pub fn has_opposite_colored_bishops(&self) -> bool {
+        let white_bishops = self.bit_boards[PieceColor::White as usize][PieceType::Bishop as usize];
+        let black_bishops = self.bit_boards[PieceColor::Black as usize][PieceType::Bishop as usize];
+        white_bishops.count_ones() == 1
+            && black_bishops.count_ones() == 1
+            && (white_bishops & WHITE_SQUARES_MASK == 0)
+                != (black_bishops & WHITE_SQUARES_MASK == 0)
+    }
 }
 
 impl fmt::Display for Board {
@@ -335,6 +356,26 @@ mod tests {
         assert_eq!(retrieved_piece.piece_type, PieceType::Knight);
     }
 
+    #[test]
+    fn test_piece_at_agrees_with_get_piece() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            let board = *Position::from(fen).board();
+            for square_index in 0..NUMBER_OF_SQUARES {
+                assert_eq!(
+                    board.piece_at(square_index),
+                    board
+                        .get_piece(square_index)
+                        .map(|piece| (piece.piece_color, piece.piece_type)),
+                    "{fen} square {square_index}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_remove() {
         let mut board: Board = Board::new();
@@ -363,94 +404,94 @@ mod tests {
     #[test]
     fn test_king_side_white_castling() {
         let mut board: Board = Board::new();
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::KingSide), false);
+        assert!(!board.can_castle(PieceColor::White, &BoardSide::KingSide));
         board.put_piece(4, Piece { piece_color: PieceColor::White, piece_type: PieceType::King });
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::KingSide), false);
+        assert!(!board.can_castle(PieceColor::White, &BoardSide::KingSide));
         board.put_piece(7, Piece { piece_color: PieceColor::White, piece_type: PieceType::Rook });
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::KingSide), true);
+        assert!(board.can_castle(PieceColor::White, &BoardSide::KingSide));
 
         board.put_piece(6, Piece { piece_color: PieceColor::White, piece_type: PieceType::Knight });
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::KingSide), false);
+        assert!(!board.can_castle(PieceColor::White, &BoardSide::KingSide));
         board.remove_piece(6);
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::KingSide), true);
+        assert!(board.can_castle(PieceColor::White, &BoardSide::KingSide));
 
         board.put_piece(5, Piece { piece_color: PieceColor::White, piece_type: PieceType::Bishop });
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::KingSide), false);
+        assert!(!board.can_castle(PieceColor::White, &BoardSide::KingSide));
         board.remove_piece(5);
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::KingSide), true);
+        assert!(board.can_castle(PieceColor::White, &BoardSide::KingSide));
     }
     #[test]
     fn test_queen_side_white_castling() {
         let mut board: Board = Board::new();
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::QueenSide), false);
+        assert!(!board.can_castle(PieceColor::White, &BoardSide::QueenSide));
         board.put_piece(4, Piece { piece_color: PieceColor::White, piece_type: PieceType::King });
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::QueenSide), false);
+        assert!(!board.can_castle(PieceColor::White, &BoardSide::QueenSide));
         board.put_piece(0, Piece { piece_color: PieceColor::White, piece_type: PieceType::Rook });
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::QueenSide), true);
+        assert!(board.can_castle(PieceColor::White, &BoardSide::QueenSide));
 
         board.put_piece(1, Piece { piece_color: PieceColor::White, piece_type: PieceType::Knight });
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::QueenSide), false);
+        assert!(!board.can_castle(PieceColor::White, &BoardSide::QueenSide));
         board.remove_piece(1);
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::QueenSide), true);
+        assert!(board.can_castle(PieceColor::White, &BoardSide::QueenSide));
 
         board.put_piece(2, Piece { piece_color: PieceColor::White, piece_type: PieceType::Bishop });
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::QueenSide), false);
+        assert!(!board.can_castle(PieceColor::White, &BoardSide::QueenSide));
         board.remove_piece(2);
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::QueenSide), true);
+        assert!(board.can_castle(PieceColor::White, &BoardSide::QueenSide));
 
         board.put_piece(3, Piece { piece_color: PieceColor::White, piece_type: PieceType::Queen });
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::QueenSide), false);
+        assert!(!board.can_castle(PieceColor::White, &BoardSide::QueenSide));
         board.remove_piece(3);
-        assert_eq!(board.can_castle(PieceColor::White, &BoardSide::QueenSide), true);
+        assert!(board.can_castle(PieceColor::White, &BoardSide::QueenSide));
     }
 
     #[test]
     fn test_king_side_black_castling() {
         let mut board: Board = Board::new();
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::KingSide), false);
+        assert!(!board.can_castle(PieceColor::Black, &BoardSide::KingSide));
         board.put_piece(60, Piece { piece_color: PieceColor::Black, piece_type: PieceType::King });
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::KingSide), false);
+        assert!(!board.can_castle(PieceColor::Black, &BoardSide::KingSide));
         board.put_piece(63, Piece { piece_color: PieceColor::Black, piece_type: PieceType::Rook });
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::KingSide), true);
+        assert!(board.can_castle(PieceColor::Black, &BoardSide::KingSide));
 
         board
             .put_piece(62, Piece { piece_color: PieceColor::Black, piece_type: PieceType::Knight });
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::KingSide), false);
+        assert!(!board.can_castle(PieceColor::Black, &BoardSide::KingSide));
         board.remove_piece(62);
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::KingSide), true);
+        assert!(board.can_castle(PieceColor::Black, &BoardSide::KingSide));
 
         board
             .put_piece(61, Piece { piece_color: PieceColor::Black, piece_type: PieceType::Bishop });
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::KingSide), false);
+        assert!(!board.can_castle(PieceColor::Black, &BoardSide::KingSide));
         board.remove_piece(61);
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::KingSide), true);
+        assert!(board.can_castle(PieceColor::Black, &BoardSide::KingSide));
     }
 
     #[test]
     fn test_queen_side_black_castling() {
         let mut board: Board = Board::new();
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide), false);
+        assert!(!board.can_castle(PieceColor::Black, &BoardSide::QueenSide));
         board.put_piece(60, Piece { piece_color: PieceColor::Black, piece_type: PieceType::King });
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide), false);
+        assert!(!board.can_castle(PieceColor::Black, &BoardSide::QueenSide));
         board.put_piece(56, Piece { piece_color: PieceColor::Black, piece_type: PieceType::Rook });
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide), true);
+        assert!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide));
 
         board
             .put_piece(57, Piece { piece_color: PieceColor::Black, piece_type: PieceType::Knight });
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide), false);
+        assert!(!board.can_castle(PieceColor::Black, &BoardSide::QueenSide));
         board.remove_piece(57);
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide), true);
+        assert!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide));
 
         board
             .put_piece(58, Piece { piece_color: PieceColor::Black, piece_type: PieceType::Bishop });
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide), false);
+        assert!(!board.can_castle(PieceColor::Black, &BoardSide::QueenSide));
         board.remove_piece(58);
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide), true);
+        assert!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide));
 
         board.put_piece(59, Piece { piece_color: PieceColor::Black, piece_type: PieceType::Queen });
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide), false);
+        assert!(!board.can_castle(PieceColor::Black, &BoardSide::QueenSide));
         board.remove_piece(59);
-        assert_eq!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide), true);
+        assert!(board.can_castle(PieceColor::Black, &BoardSide::QueenSide));
     }
 
     #[test]
@@ -470,16 +511,16 @@ mod tests {
 
     #[test]
     fn test_square_color() {
-        assert_eq!(Board::is_white_square(sq!("a1")), false);
-        assert_eq!(Board::is_white_square(sq!("b1")), true);
-        assert_eq!(Board::is_white_square(sq!("a2")), true);
-        assert_eq!(Board::is_white_square(sq!("b2")), false);
-        assert_eq!(Board::is_white_square(sq!("b3")), true);
-        assert_eq!(Board::is_white_square(sq!("h8")), false);
+        assert!(!Board::is_white_square(sq!("a1")));
+        assert!(Board::is_white_square(sq!("b1")));
+        assert!(Board::is_white_square(sq!("a2")));
+        assert!(!Board::is_white_square(sq!("b2")));
+        assert!(Board::is_white_square(sq!("b3")));
+        assert!(!Board::is_white_square(sq!("h8")));
 
-        assert_eq!(Board::is_black_square(sq!("a1")), true);
-        assert_eq!(Board::is_black_square(sq!("e5")), true);
-        assert_eq!(Board::is_black_square(sq!("e6")), false);
+        assert!(Board::is_black_square(sq!("a1")));
+        assert!(Board::is_black_square(sq!("e5")));
+        assert!(!Board::is_black_square(sq!("e6")));
     }
 
     #[test]
@@ -516,78 +557,87 @@ mod tests {
     #[test]
     fn test_has_bishop_pair() {
         let mut board = Board::new();
-        assert_eq!(board.has_bishop_pair(PieceColor::White), false);
-        assert_eq!(board.has_bishop_pair(PieceColor::Black), false);
+        assert!(!board.has_bishop_pair(PieceColor::White));
+        assert!(!board.has_bishop_pair(PieceColor::Black));
 
         board.put_piece(
             sq!("c1"),
             Piece { piece_color: PieceColor::White, piece_type: PieceType::Bishop },
         );
-        assert_eq!(board.has_bishop_pair(PieceColor::White), false);
+        assert!(!board.has_bishop_pair(PieceColor::White));
         board.put_piece(
             sq!("f1"),
             Piece { piece_color: PieceColor::White, piece_type: PieceType::Bishop },
         );
-        assert_eq!(board.has_bishop_pair(PieceColor::White), true);
+        assert!(board.has_bishop_pair(PieceColor::White));
 
         board.put_piece(
             sq!("c8"),
             Piece { piece_color: PieceColor::Black, piece_type: PieceType::Bishop },
         );
-        assert_eq!(board.has_bishop_pair(PieceColor::Black), false);
+        assert!(!board.has_bishop_pair(PieceColor::Black));
         board.put_piece(
             sq!("f8"),
             Piece { piece_color: PieceColor::Black, piece_type: PieceType::Bishop },
         );
-        assert_eq!(board.has_bishop_pair(PieceColor::Black), true);
+        assert!(board.has_bishop_pair(PieceColor::Black));
 
         board.remove_piece(sq!("c1"));
-        assert_eq!(board.has_bishop_pair(PieceColor::White), false);
+        assert!(!board.has_bishop_pair(PieceColor::White));
 
         board.put_piece(
             sq!("d1"),
             Piece { piece_color: PieceColor::White, piece_type: PieceType::Bishop },
         );
-        assert_eq!(board.has_bishop_pair(PieceColor::White), false);
+        assert!(!board.has_bishop_pair(PieceColor::White));
     }
 
     #[test]
     fn test_has_bishops_on_same_color_squares() {
         let mut board = Board::new();
-        assert_eq!(board.has_bishops_on_same_color_squares(PieceColor::White), false);
-        assert_eq!(board.has_bishops_on_same_color_squares(PieceColor::Black), false);
+        assert!(!board.has_bishops_on_same_color_squares(PieceColor::White));
+        assert!(!board.has_bishops_on_same_color_squares(PieceColor::Black));
 
         board.put_piece(
             sq!("c1"),
             Piece { piece_color: PieceColor::White, piece_type: PieceType::Bishop },
         );
-        assert_eq!(board.has_bishops_on_same_color_squares(PieceColor::White), false);
+        assert!(!board.has_bishops_on_same_color_squares(PieceColor::White));
         board.put_piece(
             sq!("f1"),
             Piece { piece_color: PieceColor::White, piece_type: PieceType::Bishop },
         );
-        assert_eq!(board.has_bishops_on_same_color_squares(PieceColor::White), false);
+        assert!(!board.has_bishops_on_same_color_squares(PieceColor::White));
         board.put_piece(
             sq!("g1"),
             Piece { piece_color: PieceColor::White, piece_type: PieceType::Bishop },
         );
-        assert_eq!(board.has_bishops_on_same_color_squares(PieceColor::White), true);
+        assert!(board.has_bishops_on_same_color_squares(PieceColor::White));
 
         board.put_piece(
             sq!("c8"),
             Piece { piece_color: PieceColor::Black, piece_type: PieceType::Bishop },
         );
-        assert_eq!(board.has_bishops_on_same_color_squares(PieceColor::Black), false);
+        assert!(!board.has_bishops_on_same_color_squares(PieceColor::Black));
         board.put_piece(
             sq!("f8"),
             Piece { piece_color: PieceColor::Black, piece_type: PieceType::Bishop },
         );
-        assert_eq!(board.has_bishops_on_same_color_squares(PieceColor::Black), false);
+        assert!(!board.has_bishops_on_same_color_squares(PieceColor::Black));
         board.put_piece(
             sq!("g8"),
             Piece { piece_color: PieceColor::Black, piece_type: PieceType::Bishop },
         );
-        assert_eq!(board.has_bishops_on_same_color_squares(PieceColor::Black), true);
+        assert!(board.has_bishops_on_same_color_squares(PieceColor::Black));
+    }
+
+    #[test]
+    fn test_has_opposite_colored_bishops() {
+        let board = |fen: &str| *Position::from(fen).board();
+        assert!(board("4k3/8/2b5/8/8/8/5B2/4K3 w - - 0 1").has_opposite_colored_bishops());
+        assert!(!board("4k3/8/3b4/8/8/8/5B2/4K3 w - - 0 1").has_opposite_colored_bishops());
+        assert!(!board("4k3/8/2b5/8/8/8/4BB2/4K3 w - - 0 1").has_opposite_colored_bishops());
+        assert!(!board("4k3/8/8/8/8/8/5B2/4K3 w - - 0 1").has_opposite_colored_bishops());
     }
 
     #[test]
@@ -43,6 +43,10 @@ impl Move {
             | Castling { base_move, .. } => base_move,
         }
     }
+
+    pub fn is_rook_or_bishop_promotion(&self) -> bool {
+        matches!(self, Promotion { promote_to: PieceType::Rook | PieceType::Bishop, .. })
+    }
 }
 
 impl fmt::Display for Move {
@@ -83,7 +87,7 @@ pub struct RawMove {
 }
 
 impl RawMove {
-    pub(crate) fn new(from: u8, to: u8, promote_to: Option<PieceType>) -> RawMove {
+    pub fn new(from: u8, to: u8, promote_to: Option<PieceType>) -> RawMove {
         RawMove { from, to, promote_to }
     }
 }
@@ -125,13 +129,10 @@ mod tests {
     #[test]
     fn test_basic_move() {
         let basic_move = Basic { base_move: { BaseMove::new(1, 2, false) } };
-        match basic_move {
-            Basic { base_move: BaseMove { from, to, capture } } => {
-                assert_eq!(from, 1);
-                assert_eq!(to, 2);
-                assert_eq!(capture, false);
-            }
-            _ => {}
+        if let Basic { base_move: BaseMove { from, to, capture } } = basic_move {
+            assert_eq!(from, 1);
+            assert_eq!(to, 2);
+            assert!(!capture);
         }
     }
 
@@ -139,14 +140,13 @@ mod tests {
     fn test_en_passant_move() {
         let en_passant_move =
             EnPassant { base_move: { BaseMove::new(1, 2, true) }, capture_square: 3 };
-        match en_passant_move {
-            EnPassant { base_move: BaseMove { from, to, capture }, capture_square } => {
-                assert_eq!(from, 1);
-                assert_eq!(to, 2);
-                assert_eq!(capture, true);
-                assert_eq!(capture_square, 3);
-            }
-            _ => {}
+        if let EnPassant { base_move: BaseMove { from, to, capture }, capture_square } =
+            en_passant_move
+        {
+            assert_eq!(from, 1);
+            assert_eq!(to, 2);
+            assert!(capture);
+            assert_eq!(capture_square, 3);
         }
     }
 
@@ -154,14 +154,12 @@ mod tests {
     fn test_promotion_move() {
         let promotion_move =
             Promotion { base_move: { BaseMove::new(8, 0, true) }, promote_to: PieceType::Rook };
-        match promotion_move {
-            Promotion { base_move: BaseMove { from, to, capture }, promote_to } => {
-                assert_eq!(from, 8);
-                assert_eq!(to, 0);
-                assert_eq!(capture, true);
-                assert_eq!(promote_to, PieceType::Rook);
-            }
-            _ => {}
+        if let Promotion { base_move: BaseMove { from, to, capture }, promote_to } = promotion_move
+        {
+            assert_eq!(from, 8);
+            assert_eq!(to, 0);
+            assert!(capture);
+            assert_eq!(promote_to, PieceType::Rook);
         }
     }
 
@@ -169,14 +167,11 @@ mod tests {
     fn test_castling_move() {
         let castling_move =
             Castling { base_move: { BaseMove::new(4, 6, false) }, board_side: BoardSide::KingSide };
-        match castling_move {
-            Castling { base_move: BaseMove { from, to, capture }, board_side } => {
-                assert_eq!(from, 4);
-                assert_eq!(to, 6);
-                assert_eq!(capture, false);
-                assert_eq!(board_side, BoardSide::KingSide);
-            }
-            _ => {}
+        if let Castling { base_move: BaseMove { from, to, capture }, board_side } = castling_move {
+            assert_eq!(from, 4);
+            assert_eq!(to, 6);
+            assert!(!capture);
+            assert_eq!(board_side, BoardSide::KingSide);
         }
     }
 
@@ -13,9 +13,50 @@ use strum::IntoEnumIterator;
 include!("../utils/generated_macro.rs");
 
 pub fn generate_moves(position: &Position) -> Vec<Move> {
-    let mut move_generator = MoveGeneratorImpl::new(*position, MoveListMoveProcessor::new());
-    move_generator.generate();
-    move_generator.move_processor.get_result().clone()
+    let mut moves: ArrayVec<Move, MOVE_LIST_LENGTH> = ArrayVec::new();
+    generate_moves_into(position, &mut moves);
+    moves.to_vec()
+}
+
+/// Generates the moves into a caller provided buffer (which is cleared first) to avoid allocating.
+/// The moves are in the same order as those returned by generate_moves.
+/// When in check only the check evasions are generated.
+pub fn generate_moves_into<const N: usize>(position: &Position, moves: &mut ArrayVec<Move, N>) {
+    moves.clear();
+    let move_processor = MoveBufferMoveProcessor { moves, capture_count: 0 };
+    if is_check(position) {
+        generate_check_evasions_using(position, move_processor);
+    } else {
+        MoveGeneratorImpl::new(*position, move_processor).generate();
+    }
+}
+
+/// Generates the pseudo-legal moves that might get the side to move out of check. With a single checker
+/// these are the king moves, captures of the checker and interpositions. With a double check only king
+/// moves are generated.
+pub fn generate_check_evasions(position: &Position) -> Vec<Move> {
+    generate_check_evasions_using(position, MoveListMoveProcessor::new())
+        .move_processor
+        .get_result()
+}
+
+fn generate_check_evasions_using<P: MoveProcessor>(
+    position: &Position,
+    move_processor: P,
+) -> MoveGeneratorImpl<P> {
+    let mut move_generator = MoveGeneratorImpl::new(*position, move_processor);
+    let checkers = king_attacks_finder(position, position.side_to_move());
+    if checkers.count_ones() == 1 {
+        // the other pieces can only capture the checker or move between it and the king
+        let king_square = position.board().king_square(position.side_to_move());
+        move_generator.target_squares =
+            checkers | squares_between(king_square, checkers.trailing_zeros() as usize, position);
+        move_generator.generate();
+    } else {
+        // only the king can move out of a double check
+        move_generator.generate_king_moves();
+    }
+    move_generator
 }
 
 pub fn generate_moves_for_quiescence(position: &Position) -> Vec<Move> {
@@ -31,12 +72,33 @@ pub fn has_legal_move(position: &Position) -> bool {
     get_first_legal_move(position).is_some()
 }
 
+/// Checks a move made outside the engine, for example by a GUI, without keeping the generated moves
+pub fn is_legal(position: &Position, mov: &Move) -> bool {
+    let mut moves: ArrayVec<Move, MOVE_LIST_LENGTH> = ArrayVec::new();
+    generate_moves_into(position, &mut moves);
+    let mut next_position = *position;
+    moves.contains(mov) && next_position.make_move(mov).is_some()
+}
+
+/// Counts the legal moves without keeping them. When in check only the check evasions are tried.
+pub fn legal_move_count(position: &Position) -> usize {
+    let move_processor = LegalMoveCountProcessor { position: *position, legal_move_count: 0 };
+    if is_check(position) {
+        generate_check_evasions_using(position, move_processor).move_processor.get_result()
+    } else {
+        let mut move_generator = MoveGeneratorImpl::new(*position, move_processor);
+        move_generator.generate();
+        move_generator.move_processor.get_result()
+    }
+}
+
 pub fn get_first_legal_move(position: &Position) -> Option<Move> {
     let mut move_generator = MoveGeneratorImpl {
         position: *position,
         move_processor: HasLegalMoveProcessor::new(*position),
         occupied_squares: position.board().bitboard_all_pieces(),
         friendly_squares: position.board().bitboard_by_color(position.side_to_move()),
+        target_squares: u64::MAX,
     };
     move_generator.generate();
     move_generator.move_processor.get_result()
@@ -122,6 +184,35 @@ pub fn is_check(position: &Position) -> bool {
     check_count(position) > 0
 }
 
+/// The union of the squares attacked by every piece of the given color
+pub fn attacks_by_side(position: &Position, attacking_color: PieceColor) -> u64 {
+    let board = position.board();
+    let occupied_squares = board.bitboard_all_pieces();
+    let queens = board.bitboard_by_color_and_piece_type(attacking_color, PieceType::Queen);
+    let mut attacked_squares = 0;
+    for piece_type in [PieceType::Bishop, PieceType::Rook] {
+        let sliders = board.bitboard_by_color_and_piece_type(attacking_color, piece_type) | queens;
+        for square_index in BitboardIterator::new(sliders) {
+            attacked_squares |= get_sliding_moves_by_piece_type_and_square_index(
+                &piece_type,
+                square_index,
+                occupied_squares,
+            );
+        }
+    }
+    for piece_type in [PieceType::Knight, PieceType::King] {
+        let pieces = board.bitboard_by_color_and_piece_type(attacking_color, piece_type);
+        for square_index in BitboardIterator::new(pieces) {
+            attacked_squares |= NON_SLIDING_PIECE_MOVE_TABLE[piece_type as usize][square_index];
+        }
+    }
+    let pawns = board.bitboard_by_color_and_piece_type(attacking_color, PieceType::Pawn);
+    for square_index in BitboardIterator::new(pawns) {
+        attacked_squares |= PAWN_ATTACKS_TABLE[attacking_color as usize][square_index];
+    }
+    attacked_squares
+}
+
 pub fn squares_attacked_by_pawn(piece_color: PieceColor, pawn_square_index: usize) -> u64 {
     PAWN_ATTACKS_TABLE[piece_color as usize][pawn_square_index]
 }
@@ -229,7 +320,7 @@ trait MoveProcessor {
     fn get_result(&self) -> Self::Output;
 }
 
-const MOVE_LIST_LENGTH: usize = 250;
+pub const MOVE_LIST_LENGTH: usize = 250;
 
 struct MoveListMoveProcessor {
     capture_moves: ArrayVec<Move, MOVE_LIST_LENGTH>,
@@ -237,11 +328,21 @@ struct MoveListMoveProcessor {
     move_filter: Box<dyn Fn(&Move) -> bool>,
 }
 
+struct MoveBufferMoveProcessor<'a, const N: usize> {
+    moves: &'a mut ArrayVec<Move, N>,
+    capture_count: usize,
+}
+
 struct HasLegalMoveProcessor {
     position: Position,
     legal_move: Option<Move>,
 }
 
+struct LegalMoveCountProcessor {
+    position: Position,
+    legal_move_count: usize,
+}
+
 impl MoveProcessor for MoveListMoveProcessor {
     type Output = Vec<Move>;
 
@@ -265,6 +366,50 @@ impl MoveProcessor for MoveListMoveProcessor {
         moves.into_iter().collect()
     }
 }
+impl<const N: usize> MoveProcessor for MoveBufferMoveProcessor<'_, N> {
+    type Output = ();
+
+    fn process_move(&mut self, mov: Move) {
+        self.moves.push(mov);
+        if mov.get_base_move().capture {
+            // keep the captures ahead of the non-captures
+            self.moves[self.capture_count..].rotate_right(1);
+            self.capture_count += 1;
+        }
+    }
+
+    fn continue_processing(&mut self) -> bool {
+        true
+    }
+
+    fn get_result(&self) -> Self::Output {}
+}
+
+/// The squares strictly between two squares on the same rank, file or diagonal, otherwise 0
+fn squares_between(from_square: usize, to_square: usize, position: &Position) -> u64 {
+    let occupied_squares = position.board().bitboard_all_pieces();
+    [PieceType::Bishop, PieceType::Rook]
+        .iter()
+        .map(|piece_type| {
+            let from_attacks = get_sliding_moves_by_piece_type_and_square_index(
+                piece_type,
+                from_square,
+                occupied_squares,
+            );
+            if from_attacks & (1 << to_square) != 0 {
+                from_attacks
+                    & get_sliding_moves_by_piece_type_and_square_index(
+                        piece_type,
+                        to_square,
+                        occupied_squares,
+                    )
+            } else {
+                0
+            }
+        })
+        .fold(0, |acc, squares| acc | squares)
+}
+
 impl MoveProcessor for HasLegalMoveProcessor {
     type Output = Option<Move>;
     fn process_move(&mut self, mov: Move) {
@@ -282,6 +427,24 @@ impl MoveProcessor for HasLegalMoveProcessor {
     }
 }
 
+impl MoveProcessor for LegalMoveCountProcessor {
+    type Output = usize;
+    fn process_move(&mut self, mov: Move) {
+        let mut next_position = self.position;
+        if next_position.make_move(&mov).is_some() {
+            self.legal_move_count += 1;
+        }
+    }
+
+    fn continue_processing(&mut self) -> bool {
+        true
+    }
+
+    fn get_result(&self) -> usize {
+        self.legal_move_count
+    }
+}
+
 impl MoveListMoveProcessor {
     fn new() -> Self {
         MoveListMoveProcessor {
@@ -306,13 +469,21 @@ struct MoveGeneratorImpl<P: MoveProcessor> {
     move_processor: P,
     occupied_squares: u64,
     friendly_squares: u64,
+    /// The squares that the pieces other than the king may move to
+    target_squares: u64,
 }
 
-impl<P: MoveProcessor + std::any::Any> MoveGeneratorImpl<P> {
+impl<P: MoveProcessor> MoveGeneratorImpl<P> {
     fn new(position: Position, move_processor: P) -> Self {
         let occupied_squares = position.board().bitboard_all_pieces();
         let friendly_squares = position.board().bitboard_by_color(position.side_to_move());
-        MoveGeneratorImpl { position, move_processor, occupied_squares, friendly_squares }
+        MoveGeneratorImpl {
+            position,
+            move_processor,
+            occupied_squares,
+            friendly_squares,
+            target_squares: u64::MAX,
+        }
     }
     fn generate(&mut self)
     where
@@ -335,6 +506,13 @@ impl<P: MoveProcessor + std::any::Any> MoveGeneratorImpl<P> {
             }
         }
     }
+    fn generate_king_moves(&mut self) {
+        let king_bitboard = self
+            .position
+            .board()
+            .bitboard_by_color_and_piece_type(self.position.side_to_move(), PieceType::King);
+        self.generate_moves_for_piece_type(PieceType::King, king_bitboard);
+    }
     fn generate_moves_for_piece_type(&mut self, piece_type: PieceType, bitboard: u64) {
         match piece_type {
             PieceType::Pawn => {
@@ -342,6 +520,7 @@ impl<P: MoveProcessor + std::any::Any> MoveGeneratorImpl<P> {
                     &self.position,
                     bitboard,
                     self.occupied_squares,
+                    self.target_squares,
                     &mut self.move_processor,
                 );
             }
@@ -351,6 +530,7 @@ impl<P: MoveProcessor + std::any::Any> MoveGeneratorImpl<P> {
                     bitboard,
                     self.occupied_squares,
                     self.friendly_squares,
+                    self.target_squares,
                     &mut self.move_processor,
                 );
             }
@@ -360,6 +540,7 @@ impl<P: MoveProcessor + std::any::Any> MoveGeneratorImpl<P> {
                     bitboard,
                     self.occupied_squares,
                     self.friendly_squares,
+                    self.target_squares,
                     &mut self.move_processor,
                 );
             }
@@ -370,6 +551,7 @@ impl<P: MoveProcessor + std::any::Any> MoveGeneratorImpl<P> {
                         bitboard,
                         self.occupied_squares,
                         self.friendly_squares,
+                        self.target_squares,
                         &mut self.move_processor,
                     );
                 }
@@ -392,11 +574,13 @@ fn get_non_sliding_moves_by_piece_type<U>(
     square_indexes: u64,
     occupied_squares: u64,
     friendly_squares: u64,
+    target_squares: u64,
     move_processor: &mut (impl MoveProcessor<Output = U> + Sized),
 ) {
     let square_iterator = BitboardIterator::new(square_indexes);
     for square_index in square_iterator {
-        let destinations = NON_SLIDING_PIECE_MOVE_TABLE[piece_type as usize][square_index];
+        let destinations =
+            NON_SLIDING_PIECE_MOVE_TABLE[piece_type as usize][square_index] & target_squares;
         generate_moves_for_destinations(
             square_index,
             destinations,
@@ -411,6 +595,7 @@ fn get_sliding_moves_by_piece_type<T>(
     square_indexes: u64,
     occupied_squares: u64,
     friendly_squares: u64,
+    target_squares: u64,
     move_processor: &mut (impl MoveProcessor<Output = T> + Sized),
 ) {
     let square_iterator = BitboardIterator::new(square_indexes);
@@ -419,7 +604,7 @@ fn get_sliding_moves_by_piece_type<T>(
             &piece_type,
             square_index,
             occupied_squares,
-        );
+        ) & target_squares;
         generate_moves_for_destinations(
             square_index,
             valid_moves,
@@ -459,6 +644,7 @@ fn generate_king_moves<U>(
         1 << square_indexes.trailing_zeros(),
         occupied_squares,
         friendly_squares,
+        u64::MAX,
         move_processor,
     );
     BoardSide::iter()
@@ -477,6 +663,7 @@ fn generate_pawn_moves<P, U>(
     position: &Position,
     square_indexes: u64,
     occupied_squares: u64,
+    target_squares: u64,
     move_processor: &mut P,
 ) where
     P: MoveProcessor<Output = U> + Sized,
@@ -508,16 +695,20 @@ fn generate_pawn_moves<P, U>(
         // generate forward moves
         let one_step_forward: u64 = 1 << ((square_index as isize + pawn_increment) as usize);
         if occupied_squares & one_step_forward == 0 {
-            create_moves(
-                square_index,
-                one_step_forward.trailing_zeros() as usize,
-                false,
-                move_processor,
-            );
+            if one_step_forward & target_squares != 0 {
+                create_moves(
+                    square_index,
+                    one_step_forward.trailing_zeros() as usize,
+                    false,
+                    move_processor,
+                );
+            }
             if board::Board::rank(square_index, side_to_move) == 1 {
                 let two_steps_forward =
                     1 << ((square_index as isize + pawn_increment * 2) as usize);
-                if (occupied_squares & two_steps_forward) == 0 {
+                if (occupied_squares & two_steps_forward) == 0
+                    && two_steps_forward & target_squares != 0
+                {
                     create_moves(
                         square_index,
                         two_steps_forward.trailing_zeros() as usize,
@@ -530,17 +721,22 @@ fn generate_pawn_moves<P, U>(
 
         // generate standard captures
         let attacked_squares = PAWN_ATTACKS_TABLE[side_to_move as usize][square_index];
-        let attacked_opposing_piece_squares = attacked_squares & opposing_side_bitboard;
+        let attacked_opposing_piece_squares =
+            attacked_squares & opposing_side_bitboard & target_squares;
         for attacked_square_index in BitboardIterator::new(attacked_opposing_piece_squares) {
             create_moves(square_index, attacked_square_index, true, move_processor);
         }
 
         // generate en passant capture
         if let Some(ep_square) = position.en_passant_capture_square() {
-            if ((1 << ep_square) & attacked_squares) != 0 {
+            let capture_square = (ep_square as isize - pawn_increment) as usize;
+            // the capture can block a check on the en passant square or remove the checking pawn
+            if ((1 << ep_square) & attacked_squares) != 0
+                && ((1 << ep_square) | (1 << capture_square)) & target_squares != 0
+            {
                 let ep_move = Move::EnPassant {
                     base_move: BaseMove::new(square_index as u8, ep_square as u8, true),
-                    capture_square: (ep_square as isize - pawn_increment) as usize as u8,
+                    capture_square: capture_square as u8,
                 };
                 move_processor.process_move(ep_move);
             }
@@ -685,7 +881,10 @@ mod tests {
         let position = Position::from(fen);
         let moves = util::filter_moves_by_from_square(generate_moves(&position), 10);
         assert_eq!(moves.len(), 2);
-        assert_eq!(*moves.get(0).unwrap(), Move::Basic { base_move: BaseMove::new(10, 18, false) });
+        assert_eq!(
+            *moves.first().unwrap(),
+            Move::Basic { base_move: BaseMove::new(10, 18, false) }
+        );
         assert_eq!(*moves.get(1).unwrap(), Move::Basic { base_move: BaseMove::new(10, 26, false) });
     }
 
@@ -696,7 +895,10 @@ mod tests {
         let position = Position::from(fen);
         let moves = util::filter_moves_by_from_square(generate_moves(&position), 53);
         assert_eq!(moves.len(), 2);
-        assert_eq!(*moves.get(0).unwrap(), Move::Basic { base_move: BaseMove::new(53, 45, false) });
+        assert_eq!(
+            *moves.first().unwrap(),
+            Move::Basic { base_move: BaseMove::new(53, 45, false) }
+        );
         assert_eq!(*moves.get(1).unwrap(), Move::Basic { base_move: BaseMove::new(53, 37, false) });
     }
 
@@ -725,7 +927,10 @@ mod tests {
         let position = Position::from(fen);
         let moves = util::filter_moves_by_from_square(generate_moves(&position), 10);
         assert_eq!(moves.len(), 1);
-        assert_eq!(*moves.get(0).unwrap(), Move::Basic { base_move: BaseMove::new(10, 18, false) });
+        assert_eq!(
+            *moves.first().unwrap(),
+            Move::Basic { base_move: BaseMove::new(10, 18, false) }
+        );
     }
 
     /// Black pawns can be blocked from making a double move
@@ -735,7 +940,10 @@ mod tests {
         let position = Position::from(fen);
         let moves = util::filter_moves_by_from_square(generate_moves(&position), 53);
         assert_eq!(moves.len(), 1);
-        assert_eq!(*moves.get(0).unwrap(), Move::Basic { base_move: BaseMove::new(53, 45, false) });
+        assert_eq!(
+            *moves.first().unwrap(),
+            Move::Basic { base_move: BaseMove::new(53, 45, false) }
+        );
     }
 
     /// White pawns can capture
@@ -748,23 +956,26 @@ mod tests {
 
         let moves = util::filter_moves_by_from_square(all_moves.clone(), 19);
         assert_eq!(moves.len(), 3);
-        assert_eq!(*moves.get(0).unwrap(), Move::Basic { base_move: BaseMove::new(19, 26, true) });
+        assert_eq!(*moves.first().unwrap(), Move::Basic { base_move: BaseMove::new(19, 26, true) });
         assert_eq!(*moves.get(1).unwrap(), Move::Basic { base_move: BaseMove::new(19, 28, true) });
         assert_eq!(*moves.get(2).unwrap(), Move::Basic { base_move: BaseMove::new(19, 27, false) });
 
         let moves = util::filter_moves_by_from_square(all_moves.clone(), 23);
         assert_eq!(moves.len(), 2);
-        assert_eq!(*moves.get(0).unwrap(), Move::Basic { base_move: BaseMove::new(23, 30, true) });
+        assert_eq!(*moves.first().unwrap(), Move::Basic { base_move: BaseMove::new(23, 30, true) });
         assert_eq!(*moves.get(1).unwrap(), Move::Basic { base_move: BaseMove::new(23, 31, false) });
 
         let moves = util::filter_moves_by_from_square(all_moves.clone(), 37);
         assert_eq!(moves.len(), 2);
-        assert_eq!(*moves.get(0).unwrap(), Move::Basic { base_move: BaseMove::new(37, 46, true) });
+        assert_eq!(*moves.first().unwrap(), Move::Basic { base_move: BaseMove::new(37, 46, true) });
         assert_eq!(*moves.get(1).unwrap(), Move::Basic { base_move: BaseMove::new(37, 45, false) });
 
         let moves = util::filter_moves_by_from_square(all_moves.clone(), 44);
         assert_eq!(moves.len(), 1);
-        assert_eq!(*moves.get(0).unwrap(), Move::Basic { base_move: BaseMove::new(44, 52, false) });
+        assert_eq!(
+            *moves.first().unwrap(),
+            Move::Basic { base_move: BaseMove::new(44, 52, false) }
+        );
     }
 
     /// Black pawns can capture
@@ -776,10 +987,13 @@ mod tests {
 
         let moves = util::filter_moves_by_from_square(all_moves.clone(), 32);
         assert_eq!(moves.len(), 1);
-        assert_eq!(*moves.get(0).unwrap(), Move::Basic { base_move: BaseMove::new(32, 24, false) });
+        assert_eq!(
+            *moves.first().unwrap(),
+            Move::Basic { base_move: BaseMove::new(32, 24, false) }
+        );
         let moves = util::filter_moves_by_from_square(all_moves.clone(), 26);
         assert_eq!(moves.len(), 2);
-        assert_eq!(*moves.get(0).unwrap(), Move::Basic { base_move: BaseMove::new(26, 19, true) });
+        assert_eq!(*moves.first().unwrap(), Move::Basic { base_move: BaseMove::new(26, 19, true) });
         assert_eq!(*moves.get(1).unwrap(), Move::Basic { base_move: BaseMove::new(26, 18, false) });
     }
 
@@ -787,15 +1001,15 @@ mod tests {
     fn test_is_en_passant_capture_possible() {
         let fen = "4k3/8/8/4PpP1/8/8/8/4K3 w - - 0 1";
         let position = Position::from(fen);
-        assert_eq!(is_en_passant_capture_possible(&position), false);
+        assert!(!is_en_passant_capture_possible(&position));
 
         let fen = "4k3/8/8/4PpP1/8/8/8/4K3 w - f6 0 1";
         let position = Position::from(fen);
-        assert_eq!(is_en_passant_capture_possible(&position), true);
+        assert!(is_en_passant_capture_possible(&position));
 
         let fen = "4k3/8/8/5p2/8/8/8/4K3 w - f6 0 1";
         let position = Position::from(fen);
-        assert_eq!(is_en_passant_capture_possible(&position), false);
+        assert!(!is_en_passant_capture_possible(&position));
     }
 
     /// White pawns can capture en passant
@@ -806,11 +1020,11 @@ mod tests {
         let all_moves = generate_moves(&position);
 
         assert_eq!(all_moves.len(), 9);
-        assert_eq!(is_en_passant_capture_possible(&position), true);
+        assert!(is_en_passant_capture_possible(&position));
         let moves = util::filter_moves_by_from_square(all_moves.clone(), 36);
         assert_eq!(moves.len(), 2);
         assert_eq!(
-            *moves.get(0).unwrap(),
+            *moves.first().unwrap(),
             Move::EnPassant { base_move: BaseMove::new(36, 45, true), capture_square: 37 }
         );
         assert_eq!(*moves.get(1).unwrap(), Move::Basic { base_move: BaseMove::new(36, 44, false) });
@@ -818,7 +1032,7 @@ mod tests {
         let moves = util::filter_moves_by_from_square(all_moves.clone(), 38);
         assert_eq!(moves.len(), 2);
         assert_eq!(
-            *moves.get(0).unwrap(),
+            *moves.first().unwrap(),
             Move::EnPassant { base_move: BaseMove::new(38, 45, true), capture_square: 37 }
         );
         assert_eq!(*moves.get(1).unwrap(), Move::Basic { base_move: BaseMove::new(38, 46, false) });
@@ -832,11 +1046,11 @@ mod tests {
         let all_moves = generate_moves(&position);
 
         assert_eq!(all_moves.len(), 9);
-        assert_eq!(is_en_passant_capture_possible(&position), true);
+        assert!(is_en_passant_capture_possible(&position));
         let moves = util::filter_moves_by_from_square(all_moves.clone(), 28);
         assert_eq!(moves.len(), 2);
         assert_eq!(
-            *moves.get(0).unwrap(),
+            *moves.first().unwrap(),
             Move::EnPassant { base_move: BaseMove::new(28, 21, true), capture_square: 29 }
         );
         assert_eq!(*moves.get(1).unwrap(), Move::Basic { base_move: BaseMove::new(28, 20, false) });
@@ -844,7 +1058,7 @@ mod tests {
         let moves = util::filter_moves_by_from_square(all_moves.clone(), 30);
         assert_eq!(moves.len(), 2);
         assert_eq!(
-            *moves.get(0).unwrap(),
+            *moves.first().unwrap(),
             Move::EnPassant { base_move: BaseMove::new(30, 21, true), capture_square: 29 }
         );
         assert_eq!(*moves.get(1).unwrap(), Move::Basic { base_move: BaseMove::new(30, 22, false) });
@@ -858,7 +1072,7 @@ mod tests {
         let moves = util::filter_moves_by_from_square(generate_moves(&position), 50);
         assert_eq!(moves.len(), 4);
         assert_eq!(
-            *moves.get(0).unwrap(),
+            *moves.first().unwrap(),
             Move::Promotion {
                 base_move: BaseMove::new(50, 58, false),
                 promote_to: PieceType::Queen
@@ -896,7 +1110,7 @@ mod tests {
         assert_eq!(moves.len(), 4);
 
         assert_eq!(
-            *moves.get(0).unwrap(),
+            *moves.first().unwrap(),
             Move::Promotion {
                 base_move: BaseMove::new(14, 6, false),
                 promote_to: PieceType::Queen
@@ -931,7 +1145,7 @@ mod tests {
         assert_eq!(moves.len(), 12);
 
         assert_eq!(
-            *moves.get(0).unwrap(),
+            *moves.first().unwrap(),
             Move::Promotion { base_move: BaseMove::new(14, 5, true), promote_to: PieceType::Queen }
         );
         assert_eq!(
@@ -1014,7 +1228,7 @@ mod tests {
             moves.iter().filter(|chess_move| matches!(chess_move, Move::Castling { .. })).collect();
         assert_eq!(castling_moves.len(), 2);
         assert_eq!(
-            **castling_moves.get(0).unwrap(),
+            **castling_moves.first().unwrap(),
             Move::Castling {
                 base_move: BaseMove::new(sq!("e1"), sq!("g1"), false),
                 board_side: BoardSide::KingSide
@@ -1040,7 +1254,7 @@ mod tests {
             moves.iter().filter(|chess_move| matches!(chess_move, Move::Castling { .. })).collect();
         assert_eq!(castling_moves.len(), 2);
         assert_eq!(
-            **castling_moves.get(0).unwrap(),
+            **castling_moves.first().unwrap(),
             Move::Castling {
                 base_move: BaseMove::new(sq!("e8"), sq!("g8"), false),
                 board_side: BoardSide::KingSide
@@ -1059,8 +1273,8 @@ mod tests {
     #[test]
     fn test_king_attacks_finder_using_white_rook_and_bishop() {
         let fen = "4k2R/8/8/8/B7/8/8/4K3 b - - 0 1";
-        let mut position = Position::from(fen);
-        let attacking_square_indexes = king_attacks_finder(&mut position, PieceColor::Black);
+        let position = Position::from(fen);
+        let attacking_square_indexes = king_attacks_finder(&position, PieceColor::Black);
         let attacking_squares = util::bit_indexes(attacking_square_indexes);
         assert_eq!(attacking_squares.len(), 2);
         assert_eq!(attacking_squares[0], 24);
@@ -1070,8 +1284,8 @@ mod tests {
     #[test]
     fn test_king_attacks_finder_using_black_rook_and_bishop() {
         let fen = "4k3/8/8/b7/8/8/8/1r2K3 w - - 0 1";
-        let mut position = Position::from(fen);
-        let attacking_square_indexes = king_attacks_finder(&mut position, PieceColor::White);
+        let position = Position::from(fen);
+        let attacking_square_indexes = king_attacks_finder(&position, PieceColor::White);
         let attacking_squares = util::bit_indexes(attacking_square_indexes);
         assert_eq!(attacking_squares.len(), 2);
         assert_eq!(attacking_squares[0], 1);
@@ -1081,8 +1295,8 @@ mod tests {
     #[test]
     fn test_king_attacks_finder_using_white_queen() {
         let fen = "4k3/8/2Q5/8/8/8/8/4K3 b - - 0 1";
-        let mut position = Position::from(fen);
-        let attacking_square_indexes = king_attacks_finder(&mut position, PieceColor::Black);
+        let position = Position::from(fen);
+        let attacking_square_indexes = king_attacks_finder(&position, PieceColor::Black);
         let attacking_squares = util::bit_indexes(attacking_square_indexes);
         assert_eq!(attacking_squares.len(), 1);
         assert_eq!(attacking_squares[0], 42);
@@ -1091,8 +1305,8 @@ mod tests {
     #[test]
     fn test_king_attacks_finder_using_black_knight() {
         let fen = "4k3/8/8/8/8/3n4/2N5/4K3 w - - 0 1";
-        let mut position = Position::from(fen);
-        let attacking_square_indexes = king_attacks_finder(&mut position, PieceColor::White);
+        let position = Position::from(fen);
+        let attacking_square_indexes = king_attacks_finder(&position, PieceColor::White);
         let attacking_squares = util::bit_indexes(attacking_square_indexes);
         assert_eq!(attacking_squares.len(), 1);
         assert_eq!(attacking_squares[0], 19);
@@ -1101,8 +1315,8 @@ mod tests {
     #[test]
     fn test_king_attacks_finder_using_first_json_example() {
         let fen = "r6r/1b2k1bq/8/8/7B/8/8/R3K2R b KQ - 3 2";
-        let mut position = Position::from(fen);
-        let attacking_square_indexes = king_attacks_finder(&mut position, PieceColor::Black);
+        let position = Position::from(fen);
+        let attacking_square_indexes = king_attacks_finder(&position, PieceColor::Black);
         let attacking_squares = util::bit_indexes(attacking_square_indexes);
         assert_eq!(attacking_squares.len(), 1);
         assert_eq!(attacking_squares[0], 31);
@@ -1111,8 +1325,8 @@ mod tests {
     #[test]
     fn test_king_attacks_finder_using_white_pawn() {
         let fen = "8/8/8/1K3k2/4P3/8/8/8 b - - 0 1";
-        let mut position = Position::from(fen);
-        let attacking_square_indexes = king_attacks_finder(&mut position, PieceColor::Black);
+        let position = Position::from(fen);
+        let attacking_square_indexes = king_attacks_finder(&position, PieceColor::Black);
         let attacking_squares = util::bit_indexes(attacking_square_indexes);
         assert_eq!(attacking_squares.len(), 1);
         assert_eq!(attacking_squares[0], 28);
@@ -1121,9 +1335,9 @@ mod tests {
     #[test]
     fn test_king_attacks_finder_empty_board() {
         let fen = "5rk1/5p1p/8/8/2B5/8/8/4K1R1 b - - 0 1";
-        let mut position = Position::from(fen);
+        let position = Position::from(fen);
         let attacking_square_indexes =
-            king_attacks_finder_empty_board(&mut position, PieceColor::Black);
+            king_attacks_finder_empty_board(&position, PieceColor::Black);
         let attacking_squares = util::bit_indexes(attacking_square_indexes);
         assert_eq!(attacking_squares.len(), 2);
         assert!(attacking_squares.contains(&6) && attacking_squares.contains(&26));
@@ -1147,6 +1361,26 @@ mod tests {
         assert_eq!(squares_attacked_by_pawn(PieceColor::White, 31), 1 << 38);
     }
 
+    #[test]
+    fn test_attacks_by_side_matches_square_attacks_finder() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r2qk2r/pb4pp/1n2Pb2/2B2Q2/p1p5/2P5/2B2PPP/RN2R1K1 w - - 1 1",
+        ] {
+            let position = Position::from(fen);
+            for color in [PieceColor::White, PieceColor::Black] {
+                let expected = (0..64)
+                    .filter(|&square_index| {
+                        square_attacks_finder(&position, color, square_index) != 0
+                    })
+                    .fold(0, |attacked_squares, square_index| attacked_squares | 1 << square_index);
+                assert_eq!(attacks_by_side(&position, color), expected, "{fen} {color:?}");
+            }
+        }
+    }
+
     #[test]
     fn test_generate_moves_for_quiescence() {
         let fen = "8/4k3/Q7/8/4Pp2/8/3K2p1/r6R b - e3 0 1";
@@ -1205,4 +1439,239 @@ mod tests {
             promote_to: PieceType::Queen
         }));
     }
+
+    #[test]
+    fn test_generate_moves_into_matches_generate_moves() {
+        fn count_nodes(position: &mut Position, depth: usize, use_buffer: bool) -> usize {
+            if depth == 0 {
+                return 1;
+            }
+            let mut buffer: ArrayVec<Move, MOVE_LIST_LENGTH> = ArrayVec::new();
+            generate_moves_into(position, &mut buffer);
+            let moves = generate_moves(position);
+            assert_eq!(buffer.as_slice(), moves.as_slice());
+            let mut count = 0;
+            for mov in if use_buffer { buffer.to_vec() } else { moves } {
+                if let Some(undo_move_info) = position.make_move(&mov) {
+                    count += count_nodes(position, depth - 1, use_buffer);
+                    position.unmake_move(&undo_move_info);
+                }
+            }
+            count
+        }
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            let mut position = Position::from(fen);
+            assert_eq!(count_nodes(&mut position, 3, true), count_nodes(&mut position, 3, false));
+        }
+    }
+
+    #[test]
+    fn test_generate_moves_into_reuses_buffer() {
+        let position = Position::new_game();
+        let mut buffer: ArrayVec<Move, MOVE_LIST_LENGTH> = ArrayVec::new();
+        generate_moves_into(&Position::from("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), &mut buffer);
+        assert_eq!(buffer.len(), 5);
+        generate_moves_into(&position, &mut buffer);
+        assert_eq!(buffer.len(), 20);
+        assert_eq!(buffer.to_vec(), generate_moves(&position));
+    }
+
+    fn legal_en_passant_moves(fen: &str) -> Vec<Move> {
+        let position = Position::from(fen);
+        generate_moves(&position)
+            .into_iter()
+            .filter(|mov| matches!(mov, Move::EnPassant { .. }))
+            .filter(|mov| position.clone().make_move(mov).is_some())
+            .collect()
+    }
+
+    /// An en passant capture that removes both pawns from the king's rank is illegal
+    #[test]
+    fn test_en_passant_capture_exposing_king_along_rank() {
+        assert!(legal_en_passant_moves("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1").is_empty());
+        assert!(legal_en_passant_moves("8/8/8/r2pP2K/8/8/8/k7 w - d6 0 1").is_empty());
+        assert!(legal_en_passant_moves("7K/8/8/8/R2Pp2k/8/8/8 b - d3 0 1").is_empty());
+        assert!(legal_en_passant_moves("7K/8/8/8/k2Pp2R/8/8/8 b - d3 0 1").is_empty());
+
+        // the capture is legal once the rook no longer pins the pawns
+        assert_eq!(legal_en_passant_moves("8/8/8/K2pP3/8/8/8/7k w - d6 0 1").len(), 1);
+        assert_eq!(legal_en_passant_moves("7K/8/8/8/R2Pp3/8/8/7k b - d3 0 1").len(), 1);
+    }
+
+    /// An en passant capture can also expose the king along a diagonal
+    #[test]
+    fn test_en_passant_capture_exposing_king_along_diagonal() {
+        assert!(legal_en_passant_moves("6b1/8/8/3pP3/2K5/8/8/7k w - d6 0 1").is_empty());
+        assert!(legal_en_passant_moves("8/8/8/2k5/3Pp3/8/8/K5B1 b - d3 0 1").is_empty());
+    }
+
+    fn legal_moves(position: &Position, moves: Vec<Move>) -> Vec<Move> {
+        moves.into_iter().filter(|mov| position.clone().make_move(mov).is_some()).collect()
+    }
+
+    fn all_legal_moves(position: &Position) -> Vec<Move> {
+        let mut move_generator = MoveGeneratorImpl::new(*position, MoveListMoveProcessor::new());
+        move_generator.generate();
+        legal_moves(position, move_generator.move_processor.get_result())
+    }
+
+    #[test]
+    fn test_check_evasions_single_check() {
+        // the rook check can be answered by king moves, capturing the rook or interposing
+        let position = Position::from("4k3/8/8/8/8/1B6/3N4/r3K3 w - - 0 1");
+        let evasions = generate_check_evasions(&position);
+        assert!(evasions.iter().all(|mov| {
+            let base_move = mov.get_base_move();
+            base_move.from == sq!("e1")
+                || [sq!("a1"), sq!("b1"), sq!("c1"), sq!("d1")].contains(&(base_move.to as usize))
+        }));
+        assert!(evasions
+            .contains(&Move::Basic { base_move: BaseMove::new(sq!("d2"), sq!("b1"), false) }));
+        assert!(evasions
+            .contains(&Move::Basic { base_move: BaseMove::new(sq!("b3"), sq!("d1"), false) }));
+        assert_eq!(legal_moves(&position, evasions.clone()), all_legal_moves(&position));
+        assert_eq!(generate_moves(&position), evasions);
+    }
+
+    #[test]
+    fn test_check_evasions_capturing_checking_pawn_en_passant() {
+        let position = Position::from("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1");
+        let evasions = generate_check_evasions(&position);
+        assert!(evasions.contains(&Move::EnPassant {
+            base_move: BaseMove::new(sq!("e4"), sq!("d3"), true),
+            capture_square: sq!("d4")
+        }));
+        assert_eq!(legal_moves(&position, evasions), all_legal_moves(&position));
+    }
+
+    #[test]
+    fn test_check_evasions_double_check() {
+        let position = Position::from("4k3/8/8/1B6/8/8/8/4RK2 b - - 0 1");
+        assert_eq!(check_count(&position), 2);
+        let evasions = generate_check_evasions(&position);
+        assert!(!evasions.is_empty());
+        assert!(evasions.iter().all(|mov| mov.get_base_move().from == sq!("e8")));
+        assert_eq!(legal_moves(&position, evasions), all_legal_moves(&position));
+    }
+
+    #[test]
+    fn test_is_legal() {
+        let position = Position::from("4k3/8/8/8/8/8/3R4/3K4 w - - 0 1");
+        assert!(is_legal(
+            &position,
+            &Move::Basic { base_move: BaseMove::new(sq!("d2"), sq!("d8"), false) }
+        ));
+        // the rook doesn't move like a bishop
+        assert!(!is_legal(
+            &position,
+            &Move::Basic { base_move: BaseMove::new(sq!("d2"), sq!("e3"), false) }
+        ));
+
+        // the rook is pinned by the bishop
+        let pinned = Position::from("4k3/8/8/8/8/5b2/4R3/3K4 w - - 0 1");
+        assert!(!is_legal(
+            &pinned,
+            &Move::Basic { base_move: BaseMove::new(sq!("e2"), sq!("e8"), false) }
+        ));
+        assert!(is_legal(
+            &pinned,
+            &Move::Basic { base_move: BaseMove::new(sq!("d1"), sq!("c1"), false) }
+        ));
+
+        // the king can't stay in check or move along the line of the check
+        let in_check = Position::from("4k3/8/8/8/8/8/8/r2K2R1 w - - 0 1");
+        assert!(!is_legal(
+            &in_check,
+            &Move::Basic { base_move: BaseMove::new(sq!("g1"), sq!("g8"), false) }
+        ));
+        assert!(!is_legal(
+            &in_check,
+            &Move::Basic { base_move: BaseMove::new(sq!("d1"), sq!("e1"), false) }
+        ));
+        assert!(is_legal(
+            &in_check,
+            &Move::Basic { base_move: BaseMove::new(sq!("d1"), sq!("d2"), false) }
+        ));
+    }
+
+    #[test]
+    fn test_legal_move_count() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            // in check, and in double check
+            "4k3/8/8/8/8/8/8/r2K2R1 w - - 0 1",
+            "4k3/8/8/8/8/4n3/8/r2K4 w - - 0 1",
+            // checkmate and stalemate
+            "R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1",
+            "7k/5Q2/8/8/8/8/8/6K1 b - - 0 1",
+        ] {
+            let position = Position::from(fen);
+            let expected_count = generate_moves(&position)
+                .iter()
+                .filter(|mov| {
+                    let mut next_position = position;
+                    next_position.make_move(mov).is_some()
+                })
+                .count();
+            assert_eq!(legal_move_count(&position), expected_count, "{fen}");
+            assert_eq!(legal_move_count(&position) > 0, has_legal_move(&position), "{fen}");
+        }
+        assert_eq!(legal_move_count(&Position::new_game()), 20);
+        assert_eq!(legal_move_count(&Position::from("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1")), 0);
+    }
+
+    #[test]
+    fn test_check_count() {
+        let mut position = Position::from("4k3/8/8/8/4N3/8/8/4RK2 w - - 0 1");
+        assert_eq!(check_count(&position), 0);
+        // the knight move gives check and discovers a check by the rook
+        position
+            .make_move(&Move::Basic { base_move: BaseMove::new(sq!("e4"), sq!("f6"), false) })
+            .unwrap();
+        assert_eq!(check_count(&position), 2);
+        assert!(generate_moves(&position).iter().all(|mov| mov.get_base_move().from == sq!("e8")));
+        assert_eq!(check_count(&Position::from("4k3/8/5N2/8/8/8/8/3R1K2 b - - 0 1")), 1);
+        assert_eq!(check_count(&Position::from("8/8/8/4k3/3P4/8/8/5K2 b - - 0 1")), 1);
+        assert_eq!(check_count(&Position::from("4k3/8/8/8/8/8/8/4RK2 b - - 0 1")), 1);
+    }
+
+    #[test]
+    fn test_check_evasions_match_filtered_moves_in_perft() {
+        fn compare_evasions(position: &mut Position, depth: usize) -> usize {
+            let mut check_count = 0;
+            if is_check(position) {
+                let evasions = legal_moves(position, generate_check_evasions(position));
+                assert_eq!(
+                    evasions,
+                    all_legal_moves(position),
+                    "{}",
+                    crate::utils::fen::write(position)
+                );
+                check_count += 1;
+            }
+            if depth > 0 {
+                for mov in generate_moves(position) {
+                    if let Some(undo_move_info) = position.make_move(&mov) {
+                        check_count += compare_evasions(position, depth - 1);
+                        position.unmake_move(&undo_move_info);
+                    }
+                }
+            }
+            check_count
+        }
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ] {
+            assert!(compare_evasions(&mut Position::from(fen), 3) > 0);
+        }
+    }
 }
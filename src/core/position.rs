@@ -3,7 +3,11 @@ use crate::core::board::BoardSide;
 use crate::core::piece::{Piece, PieceColor, PieceType};
 use crate::core::r#move::{BaseMove, Move, RawMove};
 use crate::core::{board, move_gen};
+use crate::eval::evaluation;
+use crate::eval::evaluation::GameResult;
+use crate::search::negamax::RepetitionKey;
 use crate::utils::{fen, util};
+use log::debug;
 use once_cell::sync::Lazy;
 use rand::Rng;
 use rand_xoshiro::rand_core::SeedableRng;
@@ -69,9 +73,13 @@ pub struct Position {
     side_to_move: PieceColor,
     castling_rights: [[bool; 2]; 2],
     en_passant_capture_square: Option<usize>,
+    /// Set whenever the en passant capture square changes so that hashing doesn't have to look
+    /// for the pawns that could make the capture
+    en_passant_capture_possible: bool,
     half_move_clock: usize,
     full_move_number: usize,
     hash_code: u64,
+    pawn_hash_code: u64,
     castled: [bool; 2],
 }
 
@@ -95,7 +103,7 @@ impl UndoMoveInfo {
             captured_piece_type: None,
             old_castling_rights: position.castling_rights,
             old_en_passant_capture_square: position.en_passant_capture_square,
-            old_is_en_passant_capture_possible: move_gen::is_en_passant_capture_possible(position),
+            old_is_en_passant_capture_possible: position.en_passant_capture_possible,
             old_half_move_clock: position.half_move_clock,
             old_full_move_number: position.full_move_number,
             old_side_to_move: position.side_to_move,
@@ -122,8 +130,7 @@ impl PartialEq for Position {
             && self.side_to_move == other.side_to_move
             && self.castling_rights == other.castling_rights
             && self.en_passant_capture_square == other.en_passant_capture_square
-            && move_gen::is_en_passant_capture_possible(self)
-                == move_gen::is_en_passant_capture_possible(other)
+            && self.en_passant_capture_possible == other.en_passant_capture_possible
     }
 }
 
@@ -141,12 +148,16 @@ impl Position {
             side_to_move,
             castling_rights: Position::create_castling_rights(fen_castling_rights.clone()),
             en_passant_capture_square,
+            en_passant_capture_possible: false,
             half_move_clock,
             full_move_number,
             hash_code: 0,
+            pawn_hash_code: 0,
             castled: [false, false],
         };
+        position.en_passant_capture_possible = move_gen::is_en_passant_capture_possible(&position);
         position.hash_code = position.create_initial_hash();
+        position.pawn_hash_code = position.create_pawn_hash();
         position
     }
 
@@ -182,6 +193,11 @@ impl Position {
         self.en_passant_capture_square
     }
 
+    /// True if a pawn of the side to move can capture on the en passant capture square
+    pub fn is_en_passant_capture_possible(&self) -> bool {
+        self.en_passant_capture_possible
+    }
+
     pub fn half_move_clock(&self) -> usize {
         self.half_move_clock
     }
@@ -190,14 +206,63 @@ impl Position {
         self.full_move_number
     }
 
+    /// The number of plies played since the start of the game, as given by the full move number
+    /// and the side to move, so the starting position is ply 0
+    pub fn game_ply(&self) -> usize {
+        2 * self.full_move_number.saturating_sub(1)
+            + (self.side_to_move == PieceColor::Black) as usize
+    }
+
     pub fn hash_code(&self) -> u64 {
         self.hash_code
     }
 
+    /// A hash of the pawns alone, used to cache the pawn structure evaluation
+    pub fn pawn_hash_code(&self) -> u64 {
+        self.pawn_hash_code
+    }
+
+    /// Identifies the material on the board regardless of where the pieces stand, packing the
+    /// count of each type of piece other than the king into four bits
+    pub fn material_signature(&self) -> u64 {
+        self.board
+            .get_piece_counts()
+            .iter()
+            .flat_map(|counts| &counts[..PieceType::King as usize])
+            .fold(0, |signature, &count| (signature << 4) | count as u64)
+    }
+
+    /// The moves that don't leave the side to move in check, unlike move_gen::generate_moves which
+    /// also returns pseudo-legal moves
+    pub fn legal_moves(&self) -> Vec<Move> {
+        move_gen::generate_moves(self)
+            .into_iter()
+            .filter(|mov| {
+                let mut next_position = *self;
+                next_position.make_move(mov).is_some()
+            })
+            .collect()
+    }
+
+    /// The result of the game if it has ended in this position, or None if it is still in progress.
+    /// The repetition keys are those of the game so far ending with this position.
+    pub fn result(&self, repetition_keys: &[RepetitionKey]) -> Option<GameResult> {
+        evaluation::get_game_result(self, repetition_keys)
+    }
+
     pub fn has_castled(&self, piece_color: PieceColor) -> bool {
         self.castled[piece_color as usize]
     }
 
+    /// Gives the move to the other side for analysis. There can be no en passant capture after
+    /// the flip because the other side didn't make the last move.
+    pub fn flip_side_to_move(&mut self) {
+        self.side_to_move = !self.side_to_move;
+        self.en_passant_capture_square = None;
+        self.en_passant_capture_possible = false;
+        self.hash_code = self.create_initial_hash();
+    }
+
     pub fn is_drawn_by_fifty_moves_rule(&self) -> bool {
         self.half_move_clock >= 100
     }
@@ -208,7 +273,7 @@ impl Position {
         initial_hash ^= POSITION_HASHES.castling_hashes_table
             [Position::castling_rights_as_u8(&self.castling_rights) as usize];
 
-        if move_gen::is_en_passant_capture_possible(self) {
+        if self.en_passant_capture_possible {
             initial_hash ^= POSITION_HASHES.en_passant_capture_square_hashes_table
                 [self.en_passant_capture_square.unwrap()];
         }
@@ -224,17 +289,35 @@ impl Position {
         result
     }
 
+    fn create_pawn_hash(&self) -> u64 {
+        let mut result: u64 = 0;
+        self.board.process_pieces(|piece_color, piece_type, square_index| {
+            if piece_type == PieceType::Pawn {
+                result ^= POSITION_HASHES.board_hashes_table[piece_color as usize]
+                    [piece_type as usize][square_index];
+            }
+        });
+        result
+    }
+
+    fn toggle_piece_hash(&mut self, square_index: usize, piece: &Piece) {
+        let piece_hash = POSITION_HASHES.board_hashes_table[piece.piece_color as usize]
+            [piece.piece_type as usize][square_index];
+        self.hash_code ^= piece_hash;
+        if piece.piece_type == PieceType::Pawn {
+            self.pawn_hash_code ^= piece_hash;
+        }
+    }
+
     fn put_piece(&mut self, square_index: usize, piece: Piece) {
         self.remove_piece(square_index);
         self.board.put_piece(square_index, piece.clone());
-        self.hash_code ^= POSITION_HASHES.board_hashes_table[piece.piece_color as usize]
-            [piece.piece_type as usize][square_index];
+        self.toggle_piece_hash(square_index, &piece);
     }
 
     fn remove_piece(&mut self, square_index: usize) -> Option<Piece> {
         if let Some(piece) = self.board.remove_piece(square_index) {
-            self.hash_code ^= POSITION_HASHES.board_hashes_table[piece.piece_color as usize]
-                [piece.piece_type as usize][square_index];
+            self.toggle_piece_hash(square_index, &piece);
             Some(piece)
         } else {
             None
@@ -252,6 +335,39 @@ impl Position {
         self.make_move(&mov)
     }
 
+    /// A move generated for a different position could corrupt the board so it is checked against
+    /// the pieces it moves and captures. This doesn't check that the move is legal.
+    fn fits_position(&self, mov: &Move) -> bool {
+        let base_move = mov.get_base_move();
+        let (from, to) = (base_move.from as usize, base_move.to as usize);
+        let Some(moving_piece) = self.board.get_piece(from) else {
+            return false;
+        };
+        let captured_piece = self.board.get_piece(to);
+        let fits_moving_piece = match mov {
+            Move::Basic { .. } => true,
+            Move::EnPassant { .. } | Move::Promotion { .. } => {
+                moving_piece.piece_type == PieceType::Pawn
+            }
+            Move::Castling { board_side, .. } => {
+                moving_piece.piece_type == PieceType::King
+                    && self.castling_rights[self.side_to_move as usize][*board_side as usize]
+            }
+        };
+        let fits_target_square = match mov {
+            Move::Basic { base_move } => base_move.capture == captured_piece.is_some(),
+            Move::EnPassant { .. } => self.en_passant_capture_square == Some(to),
+            Move::Promotion { .. } => true,
+            Move::Castling { .. } => captured_piece.is_none(),
+        };
+        moving_piece.piece_color == self.side_to_move
+            && fits_moving_piece
+            && fits_target_square
+            && captured_piece.is_none_or(|piece| {
+                piece.piece_color != self.side_to_move && piece.piece_type != PieceType::King
+            })
+    }
+
     pub fn make_move(&mut self, mov: &Move) -> Option<UndoMoveInfo> {
         fn make_en_passant_move(
             position: &mut Position,
@@ -354,6 +470,10 @@ impl Position {
                 position.half_move_clock += 1;
             }
         }
+        if !self.fits_position(mov) {
+            debug!("Rejecting move {mov} that doesn't belong to the position {}", fen::write(self));
+            return None;
+        }
         #[allow(unused_variables)]
         let original_position: Position;
         #[cfg(debug_assertions)]
@@ -390,6 +510,7 @@ impl Position {
             self.full_move_number += 1;
             PieceColor::White
         };
+        self.en_passant_capture_possible = move_gen::is_en_passant_capture_possible(self);
         if move_gen::king_attacks_finder(self, !self.side_to_move) == 0 {
             // it's a valid move because it doesn't leave the side making the move in check
             self.update_hash_code(&undo_move_info);
@@ -454,6 +575,7 @@ impl Position {
         }
         self.castling_rights = undo_move_info.old_castling_rights;
         self.en_passant_capture_square = undo_move_info.old_en_passant_capture_square;
+        self.en_passant_capture_possible = undo_move_info.old_is_en_passant_capture_possible;
         self.half_move_clock = undo_move_info.old_half_move_clock;
         self.full_move_number = undo_move_info.old_full_move_number;
         self.hash_code = undo_move_info.old_zobrist_hash;
@@ -471,14 +593,15 @@ impl Position {
                 [Position::castling_rights_as_u8(&self.castling_rights) as usize];
         }
         // en passant moves are only included in the hash if the relevant pawn can actually be captured en passant
-        if undo_move_info.old_en_passant_capture_square.is_some()
-            && undo_move_info.old_is_en_passant_capture_possible
+        if let Some(old_en_passant_capture_square) = undo_move_info
+            .old_en_passant_capture_square
+            .filter(|_| undo_move_info.old_is_en_passant_capture_possible)
         {
             // remove the old en passant from the hash only if an en passant capture could be made because it won't have been added to the hash
             self.hash_code ^= POSITION_HASHES.en_passant_capture_square_hashes_table
-                [undo_move_info.old_en_passant_capture_square.unwrap()];
+                [old_en_passant_capture_square];
         }
-        if move_gen::is_en_passant_capture_possible(self) {
+        if self.en_passant_capture_possible {
             // add the new en passant square to the hash only if an en passant capture can actually be made
             self.hash_code ^= POSITION_HASHES.en_passant_capture_square_hashes_table
                 [self.en_passant_capture_square.unwrap()];
@@ -489,6 +612,9 @@ impl Position {
             if self.hash_code != self.create_initial_hash() {
                 panic!("Hash code mismatch after move: {}", undo_move_info.mov);
             }
+            if self.pawn_hash_code != self.create_pawn_hash() {
+                panic!("Pawn hash code mismatch after move: {}", undo_move_info.mov);
+            }
         }
     }
 
@@ -529,11 +655,47 @@ impl Position {
                 << 3)
     }
 }
+
+/// Plays random games from a seed for the given number of plies, starting a new game whenever one
+/// ends. Every legal move is made and unmade along the way and the function panics if an
+/// incrementally updated hash differs from one calculated from scratch or if unmaking a move
+/// doesn't restore the position exactly. Release builds skip the equivalent debug assertion.
+#[allow(dead_code)]
+pub fn check_hash_codes_over_random_games(seed: u64, plies: usize) {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+    let mut position = Position::new_game();
+    for _ in 0..plies {
+        let mut legal_moves = Vec::new();
+        for mov in move_gen::generate_moves(&position) {
+            let previous_position = position;
+            if let Some(undo_move_info) = position.make_move(&mov) {
+                assert_eq!(
+                    position.en_passant_capture_possible,
+                    move_gen::is_en_passant_capture_possible(&position),
+                    "after {mov}"
+                );
+                assert_eq!(position.hash_code, position.create_initial_hash(), "after {mov}");
+                assert_eq!(position.pawn_hash_code, position.create_pawn_hash(), "after {mov}");
+                position.unmake_move(&undo_move_info);
+                legal_moves.push(mov);
+            }
+            assert_eq!(position.hash_code, previous_position.hash_code, "unmaking {mov}");
+            assert_eq!(format!("{position:?}"), format!("{previous_position:?}"), "unmaking {mov}");
+        }
+        if legal_moves.is_empty() || position.is_drawn_by_fifty_moves_rule() {
+            position = Position::new_game();
+        } else {
+            position.make_move(&legal_moves[rng.random_range(0..legal_moves.len())]);
+        }
+    }
+}
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::core::move_gen::generate_moves;
     use crate::core::piece::PieceType::Queen;
+    use crate::eval::evaluation::DrawReason;
+    use crate::utils::perf_t;
 
     #[test]
     fn test_general_usability() {
@@ -560,59 +722,47 @@ mod tests {
 
         let fen: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1";
         let position: Position = Position::from(fen);
-        assert_eq!(
-            position.castling_rights()[PieceColor::White as usize][BoardSide::KingSide as usize],
-            true
+        assert!(
+            position.castling_rights()[PieceColor::White as usize][BoardSide::KingSide as usize]
         );
-        assert_eq!(
-            position.castling_rights()[PieceColor::White as usize][BoardSide::QueenSide as usize],
-            true
+        assert!(
+            position.castling_rights()[PieceColor::White as usize][BoardSide::QueenSide as usize]
         );
-        assert_eq!(
-            position.castling_rights()[PieceColor::Black as usize][BoardSide::KingSide as usize],
-            false
+        assert!(
+            !position.castling_rights()[PieceColor::Black as usize][BoardSide::KingSide as usize]
         );
-        assert_eq!(
-            position.castling_rights()[PieceColor::Black as usize][BoardSide::QueenSide as usize],
-            false
+        assert!(
+            !position.castling_rights()[PieceColor::Black as usize][BoardSide::QueenSide as usize]
         );
 
         let fen: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w kq - 0 1";
         let position: Position = Position::from(fen);
-        assert_eq!(
-            position.castling_rights()[PieceColor::White as usize][BoardSide::KingSide as usize],
-            false
+        assert!(
+            !position.castling_rights()[PieceColor::White as usize][BoardSide::KingSide as usize]
         );
-        assert_eq!(
-            position.castling_rights()[PieceColor::White as usize][BoardSide::QueenSide as usize],
-            false
+        assert!(
+            !position.castling_rights()[PieceColor::White as usize][BoardSide::QueenSide as usize]
         );
-        assert_eq!(
-            position.castling_rights()[PieceColor::Black as usize][BoardSide::KingSide as usize],
-            true
+        assert!(
+            position.castling_rights()[PieceColor::Black as usize][BoardSide::KingSide as usize]
         );
-        assert_eq!(
-            position.castling_rights()[PieceColor::Black as usize][BoardSide::QueenSide as usize],
-            true
+        assert!(
+            position.castling_rights()[PieceColor::Black as usize][BoardSide::QueenSide as usize]
         );
 
         let fen: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Qk - 0 1";
         let position: Position = Position::from(fen);
-        assert_eq!(
-            position.castling_rights()[PieceColor::White as usize][BoardSide::KingSide as usize],
-            false
+        assert!(
+            !position.castling_rights()[PieceColor::White as usize][BoardSide::KingSide as usize]
         );
-        assert_eq!(
-            position.castling_rights()[PieceColor::White as usize][BoardSide::QueenSide as usize],
-            true
+        assert!(
+            position.castling_rights()[PieceColor::White as usize][BoardSide::QueenSide as usize]
         );
-        assert_eq!(
-            position.castling_rights()[PieceColor::Black as usize][BoardSide::KingSide as usize],
-            true
+        assert!(
+            position.castling_rights()[PieceColor::Black as usize][BoardSide::KingSide as usize]
         );
-        assert_eq!(
-            position.castling_rights()[PieceColor::Black as usize][BoardSide::QueenSide as usize],
-            false
+        assert!(
+            !position.castling_rights()[PieceColor::Black as usize][BoardSide::QueenSide as usize]
         );
     }
 
@@ -683,12 +833,12 @@ mod tests {
             moves.iter().filter(|chess_move| matches!(chess_move, Move::Castling { .. })).collect();
         assert_eq!(castling_moves.len(), 2);
 
-        let mut position_0 = position.clone();
-        position_0.make_move(&castling_moves[0]).unwrap();
+        let mut position_0 = position;
+        position_0.make_move(castling_moves[0]).unwrap();
         assert_eq!(position_0.castling_rights[PieceColor::White as usize], [false, false]);
 
-        let mut position_1 = position.clone();
-        position_1.make_move(&castling_moves[0]).unwrap();
+        let mut position_1 = position;
+        position_1.make_move(castling_moves[0]).unwrap();
         assert_eq!(position_1.castling_rights[PieceColor::White as usize], [false, false]);
         assert_eq!(Position::castling_rights_as_u8(&position_1.castling_rights), 12);
     }
@@ -715,13 +865,13 @@ mod tests {
         assert_eq!(original_position.castling_rights[PieceColor::White as usize], [true, true]);
         assert_eq!(original_position.castling_rights[PieceColor::Black as usize], [true, true]);
 
-        let mut position = original_position.clone();
+        let mut position = original_position;
         position.make_raw_move(&RawMove::new(sq!("a1"), sq!("a2"), None)).unwrap();
         assert_eq!(position.castling_rights[PieceColor::White as usize], [true, false]);
         assert_eq!(position.castling_rights[PieceColor::Black as usize], [true, true]);
         assert_eq!(Position::castling_rights_as_u8(&position.castling_rights), 13);
 
-        let mut position = original_position.clone();
+        let mut position = original_position;
         position.make_raw_move(&RawMove::new(sq!("h1"), sq!("h2"), None)).unwrap();
         assert_eq!(position.castling_rights[PieceColor::White as usize], [false, true]);
         assert_eq!(position.castling_rights[PieceColor::Black as usize], [true, true]);
@@ -738,6 +888,18 @@ mod tests {
         assert_eq!(position.full_move_number, 2);
     }
 
+    #[test]
+    fn test_game_ply() {
+        let mut position = Position::new_game();
+        assert_eq!(position.game_ply(), 0);
+        position.make_raw_move(&RawMove::new(sq!("e2"), sq!("e4"), None)).unwrap();
+        assert_eq!(position.game_ply(), 1);
+        position.make_raw_move(&RawMove::new(sq!("e7"), sq!("e5"), None)).unwrap();
+        assert_eq!(position.game_ply(), 2);
+        assert_eq!(Position::from("4k3/8/8/8/8/8/8/4K3 b - - 0 30").game_ply(), 59);
+        assert_eq!(Position::from("4k3/8/8/8/8/8/8/4K3 w - - 0 0").game_ply(), 0);
+    }
+
     #[test]
     fn test_half_move_counter_incrementation() {
         let mut position = Position::new_game();
@@ -765,12 +927,12 @@ mod tests {
         assert_eq!(original_position.half_move_clock, 10);
 
         // non capture promotion
-        let mut position = original_position.clone();
+        let mut position = original_position;
         position.make_raw_move(&RawMove::new(sq!("f2"), sq!("f1"), Some(Queen))).unwrap();
         assert_eq!(position.half_move_clock, 0);
 
         // capturing promotion
-        let mut position = original_position.clone();
+        let mut position = original_position;
         position.make_raw_move(&RawMove::new(sq!("f2"), sq!("e1"), Some(Queen))).unwrap();
         assert_eq!(position.half_move_clock, 0);
     }
@@ -779,18 +941,60 @@ mod tests {
     fn test_castling_move_sets_castled_flag() {
         let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
         let position = Position::from(fen);
-        assert_eq!(position.has_castled(PieceColor::White), false);
-        assert_eq!(position.has_castled(PieceColor::Black), false);
+        assert!(!position.has_castled(PieceColor::White));
+        assert!(!position.has_castled(PieceColor::Black));
 
         let moves = generate_moves(&position);
         let castling_moves: Vec<_> =
             moves.iter().filter(|chess_move| matches!(chess_move, Move::Castling { .. })).collect();
         assert_eq!(castling_moves.len(), 2);
-        let mut position_0 = position.clone();
-        position_0.make_move(&castling_moves[0]).unwrap();
+        let mut position_0 = position;
+        position_0.make_move(castling_moves[0]).unwrap();
+
+        let mut position_1 = position;
+        position_1.make_move(castling_moves[1]).unwrap();
+    }
+
+    #[test]
+    fn test_moves_from_another_position_are_rejected() {
+        fn stale_move(fen: &str, raw_move: &str) -> Move {
+            util::create_move_list(&Position::from(fen), raw_move.to_string()).unwrap()[0]
+        }
+        fn assert_rejected(fen: &str, mov: &Move) {
+            let original_position = Position::from(fen);
+            let mut position = original_position;
+            assert!(position.make_move(mov).is_none(), "{mov} in {fen}");
+            assert_eq!(format!("{position:?}"), format!("{original_position:?}"));
+        }
 
-        let mut position_1 = position.clone();
-        position_1.make_move(&castling_moves[1]).unwrap();
+        let new_game = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let pawn_push = stale_move(new_game, "e2e4");
+        // the square moved from is empty
+        assert_rejected("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1", &pawn_push);
+        // the piece moved belongs to the other side
+        assert_rejected("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1", &pawn_push);
+        // the square moved to holds a piece of the side moving
+        assert_rejected("rnbqkbnr/pppppppp/8/8/4N3/8/PPPPPPPP/RNBQKB1R w KQkq - 0 1", &pawn_push);
+
+        let capture = stale_move("4k3/8/8/6n1/4R3/8/8/4K3 b - - 0 1", "g5e4");
+        assert_rejected("4k3/8/8/6n1/8/4R3/8/4K3 b - - 0 1", &capture);
+        let quiet_move = stale_move("4k3/8/8/6n1/8/4R3/8/4K3 b - - 0 1", "g5e4");
+        assert_rejected("4k3/8/8/6n1/4R3/8/8/4K3 b - - 0 1", &quiet_move);
+
+        let castling = stale_move("4k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1");
+        assert_rejected("4k3/8/8/8/8/8/8/4K2R w - - 0 1", &castling);
+        assert_rejected("4k3/8/8/8/8/8/8/4K1NR w K - 0 1", &castling);
+
+        let en_passant = stale_move("4k3/8/8/4pP2/8/8/8/4K3 w - e6 0 1", "f5e6");
+        assert_rejected("4k3/8/8/4pP2/8/8/8/4K3 w - - 0 1", &en_passant);
+
+        let promotion = stale_move("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q");
+        assert_rejected("R3k3/8/8/8/8/8/8/4K3 w - - 0 1", &promotion);
+        assert_rejected("4k3/Q7/8/8/8/8/8/4K3 w - - 0 1", &promotion);
+
+        // the same moves are accepted where they fit
+        let mut position = Position::from(new_game);
+        assert!(position.make_move(&pawn_push).is_some());
     }
 
     #[test]
@@ -799,21 +1003,21 @@ mod tests {
         let original_position = Position::from(fen);
 
         // no capture
-        let mut position = original_position.clone();
+        let mut position = original_position;
         let undo_move_info =
             position.make_raw_move(&RawMove::new(sq!("g5"), sq!("e6"), None)).unwrap();
         position.unmake_move(&undo_move_info);
         assert_eq!(format!("{:?}", original_position), format!("{:?}", position));
 
         // with capture
-        let mut position = original_position.clone();
+        let mut position = original_position;
         let undo_move_info =
             position.make_raw_move(&RawMove::new(sq!("g5"), sq!("e4"), None)).unwrap();
         position.unmake_move(&undo_move_info);
         assert_eq!(format!("{:?}", original_position), format!("{:?}", position));
 
         // still in check
-        let mut position = original_position.clone();
+        let mut position = original_position;
         let undo_move_info = position.make_raw_move(&RawMove::new(sq!("g5"), sq!("f3"), None));
         assert!(undo_move_info.is_none());
         assert_eq!(format!("{:?}", original_position), format!("{:?}", original_position));
@@ -825,7 +1029,7 @@ mod tests {
         let original_position = Position::from(fen);
 
         // with capture
-        let mut position = original_position.clone();
+        let mut position = original_position;
         let undo_move_info =
             position.make_raw_move(&RawMove::new(sq!("f5"), sq!("e6"), None)).unwrap();
         position.unmake_move(&undo_move_info);
@@ -838,7 +1042,7 @@ mod tests {
         let original_position = Position::from(fen);
 
         // legal castling
-        let mut position = original_position.clone();
+        let mut position = original_position;
         let undo_move_info =
             position.make_raw_move(&RawMove::new(sq!("e8"), sq!("c8"), None)).unwrap();
         position.unmake_move(&undo_move_info);
@@ -848,7 +1052,7 @@ mod tests {
         let original_position = Position::from(fen);
 
         // castling into check
-        let mut position = original_position.clone();
+        let mut position = original_position;
         let undo_move_info = position.make_raw_move(&RawMove::new(sq!("e8"), sq!("c8"), None));
         assert!(undo_move_info.is_none());
         assert_eq!(format!("{:?}", original_position), format!("{:?}", position));
@@ -860,14 +1064,14 @@ mod tests {
         let original_position = Position::from(fen);
 
         // no capture
-        let mut position = original_position.clone();
+        let mut position = original_position;
         let undo_move_info =
             position.make_raw_move(&RawMove::new(sq!("b7"), sq!("b8"), Some(Queen))).unwrap();
         position.unmake_move(&undo_move_info);
         assert_eq!(format!("{:?}", original_position), format!("{:?}", position));
 
         // with capture
-        let mut position = original_position.clone();
+        let mut position = original_position;
         let undo_move_info =
             position.make_raw_move(&RawMove::new(sq!("b7"), sq!("c8"), Some(Queen))).unwrap();
         position.unmake_move(&undo_move_info);
@@ -877,7 +1081,7 @@ mod tests {
         let fen = "2n1k3/1P6/8/8/7b/8/8/4K3 w - - 0 1";
         let original_position = Position::from(fen);
 
-        let mut position = original_position.clone();
+        let mut position = original_position;
         let undo_move_info =
             position.make_raw_move(&RawMove::new(sq!("b7"), sq!("c8"), Some(Queen)));
         assert!(undo_move_info.is_none());
@@ -888,15 +1092,210 @@ mod tests {
     fn test_castling_through_check_does_not_mutate_position() {
         let fen = "4k3/8/b7/8/8/8/8/4K2R w K - 0 1";
         let original_position = Position::from(fen);
-        let mut position = original_position.clone();
+        let mut position = original_position;
         position.make_raw_move(&RawMove::new(sq!("e1"), sq!("g1"), None));
         assert_eq!(format!("{:?}", original_position), format!("{:?}", position));
 
         // now set the enpassant square
         let fen = "4k3/8/b7/2pP4/8/8/8/4K2R w K c6 0 1";
         let original_position = Position::from(fen);
-        let mut position = original_position.clone();
+        let mut position = original_position;
         position.make_raw_move(&RawMove::new(sq!("e1"), sq!("g1"), None));
         assert_eq!(format!("{:?}", original_position), format!("{:?}", position));
     }
+
+    #[test]
+    fn test_pawn_hash_code_is_maintained_incrementally() {
+        let mut position = Position::new_game();
+        let new_game_pawn_hash_code = position.pawn_hash_code();
+        assert_ne!(new_game_pawn_hash_code, 0);
+
+        position.make_raw_move(&RawMove::new(sq!("g1"), sq!("f3"), None)).unwrap();
+        assert_eq!(position.pawn_hash_code(), new_game_pawn_hash_code);
+
+        for raw_move in ["e7e5", "f3e5", "d7d5", "e2e4", "d5e4"] {
+            position.make_raw_move(&util::parse_move(raw_move.to_string()).unwrap()).unwrap();
+            let pawn_hash_code = Position::from(fen::write(&position).as_str()).pawn_hash_code();
+            assert_eq!(position.pawn_hash_code(), pawn_hash_code);
+        }
+        assert_ne!(position.pawn_hash_code(), new_game_pawn_hash_code);
+    }
+
+    #[test]
+    fn test_en_passant_square_is_only_hashed_when_capturable() {
+        let hash_code = |fen: &str| Position::from(fen).hash_code();
+        assert_eq!(
+            hash_code("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1"),
+            hash_code("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1")
+        );
+        assert_eq!(
+            hash_code("4k3/8/8/8/1p2P3/8/8/4K3 b - e3 0 1"),
+            hash_code("4k3/8/8/8/1p2P3/8/8/4K3 b - - 0 1")
+        );
+        assert_ne!(
+            hash_code("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1"),
+            hash_code("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1")
+        );
+        assert_ne!(
+            hash_code("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1"),
+            hash_code("4k3/8/8/3Pp3/8/8/8/4K3 w - - 0 1")
+        );
+    }
+
+    #[test]
+    fn test_en_passant_square_is_only_hashed_incrementally_when_capturable() {
+        let play = |fen: &str, raw_moves: &str| {
+            let mut position = Position::from(fen);
+            for raw_move in raw_moves.split_whitespace() {
+                position.make_raw_move(&util::parse_move(raw_move.to_string()).unwrap()).unwrap();
+            }
+            position
+        };
+        let double_push = play("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", "e2e4");
+        assert_eq!(double_push.en_passant_capture_square(), Some(sq!("e3")));
+        let single_pushes = play("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1", "e8d8 e2e3 d8e8 e3e4");
+        let transposed = play("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", "e1d1 e8d8 d1e1 d8e8 e2e4");
+        assert_eq!(single_pushes.hash_code(), transposed.hash_code());
+        assert_eq!(double_push.hash_code(), transposed.hash_code());
+
+        let capturable = play("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1", "e2e4");
+        assert_eq!(
+            capturable.hash_code(),
+            Position::from("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").hash_code()
+        );
+        assert_ne!(
+            capturable.hash_code(),
+            Position::from("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").hash_code()
+        );
+
+        // the en passant square is removed from the hash once the capture is no longer possible
+        let after_reply = play("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1", "e2e4 e8d8");
+        assert_eq!(
+            after_reply.hash_code(),
+            Position::from("3k4/8/8/8/3pP3/8/8/4K3 w - - 1 2").hash_code()
+        );
+    }
+
+    #[test]
+    fn test_cached_en_passant_capture_possible_matches_move_gen() {
+        let mut position = Position::new_game();
+        let mut undo_move_infos = Vec::new();
+        let mut capturable_en_passant = Vec::new();
+        for raw_move in "e2e4 d7d5 e4e5 f7f5 e5f6 g7f6 g1f3 d5d4 c2c4 d4c3 b2c3 e7e5".split(' ') {
+            undo_move_infos.push(
+                position.make_raw_move(&util::parse_move(raw_move.to_string()).unwrap()).unwrap(),
+            );
+            assert_eq!(
+                position.is_en_passant_capture_possible(),
+                move_gen::is_en_passant_capture_possible(&position),
+                "after {raw_move}"
+            );
+            if position.is_en_passant_capture_possible() {
+                capturable_en_passant.push(raw_move);
+            }
+        }
+        assert_eq!(capturable_en_passant, vec!["f7f5", "c2c4"]);
+        while let Some(undo_move_info) = undo_move_infos.pop() {
+            position.unmake_move(&undo_move_info);
+            assert_eq!(
+                position.is_en_passant_capture_possible(),
+                move_gen::is_en_passant_capture_possible(&position),
+                "unmaking {}",
+                undo_move_info.mov
+            );
+        }
+        assert_eq!(position, Position::new_game());
+
+        let mut position = Position::from("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1");
+        assert!(position.is_en_passant_capture_possible());
+        position.flip_side_to_move();
+        assert!(!position.is_en_passant_capture_possible());
+    }
+
+    #[test]
+    fn test_result() {
+        let result = |fen: &str| {
+            let position = Position::from(fen);
+            position.result(&[RepetitionKey::new(&position)])
+        };
+        assert_eq!(result(NEW_GAME_FEN), None);
+        assert_eq!(
+            result("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"),
+            Some(GameResult::BlackWins)
+        );
+        assert_eq!(result("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1"), Some(GameResult::WhiteWins));
+        assert_eq!(
+            result("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            Some(GameResult::Draw(DrawReason::Stalemate))
+        );
+        assert_eq!(
+            result("8/8/4k3/8/8/3KB3/8/8 w - - 0 1"),
+            Some(GameResult::Draw(DrawReason::InsufficientMaterial))
+        );
+        assert_eq!(
+            result("8/8/4k3/8/8/3KR3/8/8 w - - 100 80"),
+            Some(GameResult::Draw(DrawReason::FiftyMoveRule))
+        );
+    }
+
+    #[test]
+    fn test_hash_codes_over_random_games() {
+        check_hash_codes_over_random_games(1, 2000);
+        check_hash_codes_over_random_games(2, 2000);
+    }
+
+    #[test]
+    fn test_legal_moves_match_perft_to_depth_one() {
+        for fen in [
+            NEW_GAME_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/8/8/8/3q4/4K3 w - - 0 1",
+            "4k3/8/8/1b6/8/8/8/r3K3 w - - 0 1",
+            "4k3/8/8/8/8/5n2/8/4K2r w - - 0 1",
+            "7K/5k2/8/7r/8/8/8/8 w - - 0 1",
+        ] {
+            let position = Position::from(fen);
+            assert_eq!(
+                position.legal_moves().len(),
+                perf_t::count_nodes(&position, 1).node_count,
+                "{fen}"
+            );
+        }
+        assert!(Position::from("7K/5k2/8/7r/8/8/8/8 w - - 0 1").legal_moves().is_empty());
+        assert_eq!(Position::from("4k3/8/8/8/8/5n2/8/4K2r w - - 0 1").legal_moves().len(), 2);
+    }
+
+    #[test]
+    fn test_material_signature() {
+        let position = Position::from("4k3/pp6/8/3n4/8/8/5PPP/2R3K1 w - - 0 1");
+        let same_material = Position::from("2n1k3/7p/p7/8/8/1P6/1R3P1P/6K1 b - - 0 1");
+        assert_eq!(position.material_signature(), same_material.material_signature());
+
+        let colors_reversed = Position::from("4k3/5ppp/8/3N4/8/8/PP6/2r3K1 w - - 0 1");
+        assert_ne!(position.material_signature(), colors_reversed.material_signature());
+        let bishop_for_knight = Position::from("4k3/pp6/8/3b4/8/8/5PPP/2R3K1 w - - 0 1");
+        assert_ne!(position.material_signature(), bishop_for_knight.material_signature());
+        let extra_pawn = Position::from("4k3/ppp5/8/3n4/8/8/5PPP/2R3K1 w - - 0 1");
+        assert_ne!(position.material_signature(), extra_pawn.material_signature());
+        assert_ne!(Position::new_game().material_signature(), position.material_signature());
+    }
+
+    #[test]
+    fn test_flip_side_to_move() {
+        let mut position = Position::from("4k3/8/8/8/2pP4/8/8/4K3 b - d3 0 30");
+        assert_eq!(position.en_passant_capture_square(), Some(sq!("d3")));
+        position.flip_side_to_move();
+        assert_eq!(fen::write(&position), "4k3/8/8/8/2pP4/8/8/4K3 w - - 0 30");
+        assert_eq!(
+            position.hash_code(),
+            Position::from(fen::write(&position).as_str()).hash_code()
+        );
+
+        position.flip_side_to_move();
+        assert_eq!(
+            position.hash_code(),
+            Position::from("4k3/8/8/8/2pP4/8/8/4K3 b - - 0 30").hash_code()
+        );
+    }
 }
@@ -3,4 +3,6 @@ pub mod evaluation;
 pub mod pawns;
 
 pub mod kings;
-mod psq;
+pub mod psq;
+#[cfg(feature = "tuning")]
+pub mod tuning;
@@ -3,13 +3,16 @@ use crate::core::move_gen;
 use crate::core::piece::{PieceColor, PieceType};
 use crate::core::position::Position;
 use crate::eval::kings::score_kings;
+use crate::eval::pawns;
 use crate::eval::pawns::score_pawns;
-use crate::eval::psq::score_board_psq_values;
-use crate::search::negamax::{RepetitionKey, Search, MAXIMUM_SCORE};
+use crate::eval::psq::{score_board_psq_values_using_tables, PieceSquareTables};
+use crate::search::negamax::{RepetitionKey, Search, MAXIMUM_SCORE, MAXIMUM_SEARCH_DEPTH};
+use crate::uci::config;
 use crate::uci::config::get_contempt;
 use crate::utils::bitboard_iterator::BitboardIterator;
 use crate::utils::util;
 use crate::utils::util::row_bitboard;
+use std::fmt;
 use strum::IntoEnumIterator;
 
 include!("../utils/generated_macro.rs");
@@ -21,11 +24,95 @@ pub enum GameStatus {
     InProgress,
     DrawnByFiftyMoveRule,
     DrawnByThreefoldRepetition,
+    DrawnBySeventyFiveMoveRule,
+    DrawnByFivefoldRepetition,
     DrawnByInsufficientMaterial,
     Stalemate,
     Checkmate,
 }
 
+impl GameStatus {
+    pub fn is_draw(&self) -> bool {
+        !matches!(self, GameStatus::InProgress | GameStatus::Checkmate)
+    }
+
+    /// Draws that end the game without either player having to claim them
+    pub fn is_automatic_draw(&self) -> bool {
+        self.is_draw()
+            && !matches!(
+                self,
+                GameStatus::DrawnByFiftyMoveRule | GameStatus::DrawnByThreefoldRepetition
+            )
+    }
+
+    /// The side that has won when this is the status of a position with the given side to move.
+    /// Only checkmate has a winner, which is the side that isn't to move.
+    pub fn winner(&self, side_to_move: PieceColor) -> Option<PieceColor> {
+        (*self == GameStatus::Checkmate).then_some(!side_to_move)
+    }
+
+    pub fn draw_reason(&self) -> Option<DrawReason> {
+        match self {
+            GameStatus::DrawnByFiftyMoveRule => Some(DrawReason::FiftyMoveRule),
+            GameStatus::DrawnByThreefoldRepetition => Some(DrawReason::ThreefoldRepetition),
+            GameStatus::DrawnBySeventyFiveMoveRule => Some(DrawReason::SeventyFiveMoveRule),
+            GameStatus::DrawnByFivefoldRepetition => Some(DrawReason::FivefoldRepetition),
+            GameStatus::DrawnByInsufficientMaterial => Some(DrawReason::InsufficientMaterial),
+            GameStatus::Stalemate => Some(DrawReason::Stalemate),
+            GameStatus::InProgress | GameStatus::Checkmate => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DrawReason {
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    SeventyFiveMoveRule,
+    FivefoldRepetition,
+    InsufficientMaterial,
+    Stalemate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw(DrawReason),
+}
+
+impl GameResult {
+    /// The result of a game that has reached the status with the side to move to play, or None if
+    /// the game is still in progress
+    pub fn from_status(status: GameStatus, side_to_move: PieceColor) -> Option<GameResult> {
+        match (status.winner(side_to_move), status.draw_reason()) {
+            (Some(PieceColor::White), _) => Some(GameResult::WhiteWins),
+            (Some(PieceColor::Black), _) => Some(GameResult::BlackWins),
+            (None, Some(draw_reason)) => Some(GameResult::Draw(draw_reason)),
+            (None, None) => None,
+        }
+    }
+
+    pub fn winner(&self) -> Option<PieceColor> {
+        match self {
+            GameResult::WhiteWins => Some(PieceColor::White),
+            GameResult::BlackWins => Some(PieceColor::Black),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let result = match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw(_) => "1/2-1/2",
+        };
+        write!(f, "{result}")
+    }
+}
+
 pub const PIECE_SCORES: [i32; 6] = [100, 300, 300, 500, 900, 10000];
 
 const PHASE_TOTAL: i32 = 24;
@@ -39,9 +126,60 @@ const PHASE_WEIGHTS: [i32; 6] = [
     0, // king
 ];
 
-const BISHOP_PAIR_BONUS: i32 = 50;
+pub const BISHOP_PAIR_BONUS: i32 = 50;
 const ROOK_ON_OPEN_FILE_BONUS: i32 = 30;
 const DOUBLED_ROOKS_ON_SEVENTH_RANK_BONUS: i32 = 75;
+const CONNECTED_ROOKS_BONUS: i32 = 15;
+const ROOK_BEHIND_PASSED_PAWN_BONUS: i32 = 30;
+const OPPOSITE_COLORED_BISHOPS_SCALE_PERCENTAGE: i32 = 50;
+const EXCHANGE_NEUTRAL_PAWN_COUNT: i32 = 10;
+const EXCHANGE_VALUE_PER_PAWN: i32 = 8;
+const MAXIMUM_EXCHANGE_ADJUSTMENT: i32 = 80;
+const TRAPPED_BISHOP_PENALTY: i32 = 150;
+/// Static scores are kept strictly below the mate band so they can never be read as a mate
+const MAXIMUM_EVALUATION: i32 = MAXIMUM_SCORE - MAXIMUM_SEARCH_DEPTH as i32 - 1;
+const SPACE_BONUS_PER_SQUARE: i32 = 2;
+// space only matters while there are enough pieces left to make use of it
+const SPACE_PHASE_LIMIT: i32 = PHASE_TOTAL / 2;
+const CENTRAL_FILES: u64 = util::column_bitboard(2)
+    | util::column_bitboard(3)
+    | util::column_bitboard(4)
+    | util::column_bitboard(5);
+/// The central squares from the fourth to the sixth rank, which white gains space by controlling
+const WHITE_SPACE_AREA: u64 = CENTRAL_FILES & (row_bitboard(3) | row_bitboard(4) | row_bitboard(5));
+const BLACK_SPACE_AREA: u64 = CENTRAL_FILES & (row_bitboard(2) | row_bitboard(3) | row_bitboard(4));
+/// A white bishop on the first square is trapped by a black pawn on the second, for example a
+/// bishop that has taken the pawn on a7 and is shut in by b6. The squares are flipped for black.
+const TRAPPED_BISHOP_SQUARES: [(usize, usize); 4] = [
+    (sq!("a7"), sq!("b6")),
+    (sq!("h7"), sq!("g6")),
+    (sq!("b8"), sq!("c7")),
+    (sq!("g8"), sq!("f7")),
+];
+
+/// The weights of the evaluation terms that can be adjusted at runtime for tuning
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EvaluationWeights {
+    pub bishop_pair_bonus: i32,
+    pub passed_pawn_bonus: i32,
+    pub king_safety_percentage: i32,
+    pub doubled_pawn_penalty: i32,
+    pub isolated_pawn_penalty: i32,
+    pub piece_values: [i32; 6],
+}
+
+impl Default for EvaluationWeights {
+    fn default() -> Self {
+        EvaluationWeights {
+            bishop_pair_bonus: BISHOP_PAIR_BONUS,
+            passed_pawn_bonus: pawns::PASSED_PAWN_BONUS,
+            king_safety_percentage: 100,
+            doubled_pawn_penalty: pawns::DOUBLED_PAWN_PENALTY,
+            isolated_pawn_penalty: pawns::ISOLATED_PAWN_PENALTY,
+            piece_values: PIECE_SCORES,
+        }
+    }
+}
 
 fn calculate_game_phase(piece_counts: [[usize; 6]; 2]) -> i32 {
     let mut phase = PHASE_TOTAL;
@@ -55,6 +193,15 @@ fn calculate_game_phase(piece_counts: [[usize; 6]; 2]) -> i32 {
     phase.clamp(0, PHASE_TOTAL)
 }
 
+/// Only a draw that has to be claimed is avoided by contempt because an automatic draw is forced
+pub fn draw_score(game_status: GameStatus, contempt: i32) -> i32 {
+    if game_status.is_automatic_draw() {
+        0
+    } else {
+        -contempt
+    }
+}
+
 pub fn apply_contempt(score: i32) -> i32 {
     if score == 0 {
         -get_contempt()
@@ -63,7 +210,27 @@ pub fn apply_contempt(score: i32) -> i32 {
     }
 }
 
+/// Scores the position in centipawns from the point of view of the side to move, so a position
+/// and its colour-mirrored counterpart with the other side to move always score the same
 pub fn score_position(position: &Position) -> i32 {
+    score_position_using(
+        position,
+        &config::get_evaluation_weights(),
+        &config::get_piece_square_tables(),
+    )
+}
+
+pub fn score_position_using_weights(position: &Position, weights: &EvaluationWeights) -> i32 {
+    score_position_using(position, weights, &config::get_piece_square_tables())
+}
+
+/// The search reads the weights and tables from the config once and passes them to every
+/// evaluation rather than taking the config locks at each node
+pub fn score_position_using(
+    position: &Position,
+    weights: &EvaluationWeights,
+    piece_square_tables: &PieceSquareTables,
+) -> i32 {
     let board = position.board();
     let piece_counts = board.get_piece_counts();
     let phase = calculate_game_phase(piece_counts);
@@ -71,37 +238,61 @@ pub fn score_position(position: &Position) -> i32 {
     let material_score = piece_material_balance
         .iter()
         .enumerate()
-        .map(|(idx, &balance)| balance as i32 * PIECE_SCORES[idx])
+        .map(|(idx, &balance)| balance as i32 * weights.piece_values[idx])
         .sum::<i32>();
 
-    let (psq_mg, psq_eg) = score_board_psq_values(board);
-    let (king_mg, king_eg) = score_kings(position);
-    let (pawn_mg, pawn_eg) = score_pawns(position);
+    let (psq_mg, psq_eg) = score_board_psq_values_using_tables(board, piece_square_tables);
+    let (king_mg, king_eg) = score_kings(position, weights);
+    let (pawn_mg, pawn_eg) = score_pawns(position, weights);
 
-    let (score_mg, score_eg) = (psq_mg + king_mg + pawn_mg, psq_eg + king_eg + pawn_eg);
+    let space_mg = if phase < SPACE_PHASE_LIMIT { score_space(position) } else { 0 };
+
+    let (score_mg, score_eg) = (psq_mg + king_mg + pawn_mg + space_mg, psq_eg + king_eg + pawn_eg);
     let blended_score = (score_mg * (PHASE_TOTAL - phase) + score_eg * phase) / PHASE_TOTAL;
 
-    let mut score =
-        blended_score + material_score + score_bishops(position) + score_rooks(position);
+    let mut score = blended_score
+        + material_score
+        + score_material_imbalance(piece_counts)
+        + score_bishops(position, weights)
+        + score_rooks(position);
 
-    if score == 0 {
-        score = -1;
+    if is_opposite_colored_bishop_ending(piece_counts, board) {
+        score = score * OPPOSITE_COLORED_BISHOPS_SCALE_PERCENTAGE / 100;
     }
-    if position.side_to_move() == PieceColor::White {
-        score
+    if is_two_knights_against_lone_king(piece_counts) {
+        score = 0;
+    }
+    if position.side_to_move() == PieceColor::Black {
+        score = -score;
+    }
+    score = score.clamp(-MAXIMUM_EVALUATION, MAXIMUM_EVALUATION);
+    // a level position is scored just below zero for the side to move so that it is never
+    // mistaken for a draw
+    if score == 0 {
+        -1
     } else {
-        -score
+        score
     }
 }
 
 pub fn evaluate(position: &Position, depth: u8, repetition_key_stack: &[RepetitionKey]) -> i32 {
+    evaluate_with_contempt(position, depth, repetition_key_stack, get_contempt())
+}
+
+pub fn evaluate_with_contempt(
+    position: &Position,
+    depth: u8,
+    repetition_key_stack: &[RepetitionKey],
+    contempt: i32,
+) -> i32 {
     let game_status = get_game_status(position, repetition_key_stack);
     match game_status {
         GameStatus::InProgress => score_position(position),
         GameStatus::Checkmate => depth as i32 - MAXIMUM_SCORE,
-        _ => apply_contempt(0),
+        _ => draw_score(game_status, contempt),
     }
 }
+
 pub fn has_insufficient_material(position: &Position) -> bool {
     let board = position.board();
     let all_bitboards = &board.all_bitboards();
@@ -158,19 +349,49 @@ pub fn get_game_status(position: &Position, repetition_key_stack: &[RepetitionKe
     match (!has_legal_move, check_count > 0) {
         (true, true) => GameStatus::Checkmate,
         (true, false) => GameStatus::Stalemate,
-        _ => {
-            if position.half_move_clock() >= 100 {
-                GameStatus::DrawnByFiftyMoveRule
-            } else if Search::position_occurrence_count_static(repetition_key_stack) >= 3 {
-                GameStatus::DrawnByThreefoldRepetition
-            } else if has_insufficient_material(position) {
-                GameStatus::DrawnByInsufficientMaterial
-            } else {
-                GameStatus::InProgress
-            }
-        }
+        _ => is_draw_now(position, repetition_key_stack).unwrap_or(GameStatus::InProgress),
+    }
+}
+
+/// The result of the game if it has ended in the position by checkmate, stalemate or any of the
+/// draw rules, or None if it is still in progress. The repetition keys are those of the game so
+/// far ending with the position.
+pub fn get_game_result(
+    position: &Position,
+    repetition_keys: &[RepetitionKey],
+) -> Option<GameResult> {
+    GameResult::from_status(get_game_status(position, repetition_keys), position.side_to_move())
+}
+
+/// Returns the rule by which the position is drawn, if any. The repetition keys are those of the
+/// game so far ending with the position itself. Stalemate is reported by get_game_status.
+pub fn is_draw_now(position: &Position, repetition_keys: &[RepetitionKey]) -> Option<GameStatus> {
+    let repetition_count = repetition_count(repetition_keys);
+    let move_rule_applies = position.half_move_clock() >= 100 && !is_checkmate(position);
+    if move_rule_applies && position.half_move_clock() >= 150 {
+        Some(GameStatus::DrawnBySeventyFiveMoveRule)
+    } else if repetition_count >= 5 {
+        Some(GameStatus::DrawnByFivefoldRepetition)
+    } else if move_rule_applies {
+        Some(GameStatus::DrawnByFiftyMoveRule)
+    } else if repetition_count >= 3 {
+        Some(GameStatus::DrawnByThreefoldRepetition)
+    } else if has_insufficient_material(position) {
+        Some(GameStatus::DrawnByInsufficientMaterial)
+    } else {
+        None
     }
 }
+
+fn is_checkmate(position: &Position) -> bool {
+    is_check(position) && !move_gen::has_legal_move(position)
+}
+
+/// The number of times the last position in the stack has occurred since the last irreversible move
+pub fn repetition_count(repetition_key_stack: &[RepetitionKey]) -> usize {
+    Search::position_occurrence_count_static(repetition_key_stack)
+}
+
 pub fn is_check(position: &Position) -> bool {
     check_count(position) >= 1
 }
@@ -179,11 +400,76 @@ pub fn check_count(position: &Position) -> usize {
     move_gen::check_count(position)
 }
 
-fn score_bishops(position: &Position) -> i32 {
+/// Two knights cannot force mate against a lone king, although they can when the king also has
+/// pawns because they take away its stalemate defences
+fn is_two_knights_against_lone_king(piece_counts: [[usize; 6]; 2]) -> bool {
+    // the counts of pawns, knights, bishops, rooks and queens
+    let is_bare = |counts: &[usize; 6]| counts[..5] == [0, 0, 0, 0, 0];
+    let is_two_knights = |counts: &[usize; 6]| counts[..5] == [0, 2, 0, 0, 0];
+    let [white, black] = &piece_counts;
+    (is_two_knights(white) && is_bare(black)) || (is_two_knights(black) && is_bare(white))
+}
+
+/// Endings with only bishops and pawns where the bishops are on opposite colored squares are often drawn
+fn is_opposite_colored_bishop_ending(piece_counts: [[usize; 6]; 2], board: &Board) -> bool {
+    piece_counts.iter().all(|counts| {
+        [PieceType::Knight, PieceType::Rook, PieceType::Queen]
+            .iter()
+            .all(|piece_type| counts[*piece_type as usize] == 0)
+    }) && board.has_opposite_colored_bishops()
+}
+
+/// Adjusts the value of the exchange (a rook for a minor piece) for the number of pawns on the
+/// board. Rooks need open lines so the exchange is worth more as the pawns come off and less when
+/// the board is crowded with pawns.
+fn score_material_imbalance(piece_counts: [[usize; 6]; 2]) -> i32 {
+    let material_balance = calculate_material_balance(piece_counts);
+    let rook_balance = material_balance[PieceType::Rook as usize] as i32;
+    let minor_piece_balance = (material_balance[PieceType::Knight as usize]
+        + material_balance[PieceType::Bishop as usize]) as i32;
+    if rook_balance.signum() * minor_piece_balance.signum() >= 0 {
+        return 0;
+    }
+    let exchanges = rook_balance.signum() * rook_balance.abs().min(minor_piece_balance.abs());
+    let pawn_count = (piece_counts[PieceColor::White as usize][PieceType::Pawn as usize]
+        + piece_counts[PieceColor::Black as usize][PieceType::Pawn as usize])
+        as i32;
+    let adjustment_per_exchange = ((EXCHANGE_NEUTRAL_PAWN_COUNT - pawn_count)
+        * EXCHANGE_VALUE_PER_PAWN)
+        .clamp(-MAXIMUM_EXCHANGE_ADJUSTMENT, MAXIMUM_EXCHANGE_ADJUSTMENT);
+    exchanges * adjustment_per_exchange
+}
+
+fn score_bishops(position: &Position, weights: &EvaluationWeights) -> i32 {
+    fn count_trapped_bishops(board: &Board, piece_color: PieceColor) -> i32 {
+        let bishops = board.bitboard_by_color_and_piece_type(piece_color, PieceType::Bishop);
+        let their_pawns = board.bitboard_by_color_and_piece_type(!piece_color, PieceType::Pawn);
+        let flip = if piece_color == PieceColor::White { 0 } else { 56 };
+        TRAPPED_BISHOP_SQUARES
+            .iter()
+            .filter(|(bishop_square, pawn_square)| {
+                bishops & 1 << (bishop_square ^ flip) != 0
+                    && their_pawns & 1 << (pawn_square ^ flip) != 0
+            })
+            .count() as i32
+    }
     let board = position.board();
     (board.has_bishop_pair(PieceColor::White) as i32
         - board.has_bishop_pair(PieceColor::Black) as i32)
-        * BISHOP_PAIR_BONUS
+        * weights.bishop_pair_bonus
+        - (count_trapped_bishops(board, PieceColor::White)
+            - count_trapped_bishops(board, PieceColor::Black))
+            * TRAPPED_BISHOP_PENALTY
+}
+
+/// Rewards the control of the center and the opponent's half of the board in the middlegame
+fn score_space(position: &Position) -> i32 {
+    let controlled_squares = |piece_color: PieceColor, space_area: u64| {
+        (move_gen::attacks_by_side(position, piece_color) & space_area).count_ones() as i32
+    };
+    (controlled_squares(PieceColor::White, WHITE_SPACE_AREA)
+        - controlled_squares(PieceColor::Black, BLACK_SPACE_AREA))
+        * SPACE_BONUS_PER_SQUARE
 }
 
 fn score_rooks(position: &Position) -> i32 {
@@ -197,13 +483,51 @@ fn score_rooks(position: &Position) -> i32 {
             as i32
             * DOUBLED_ROOKS_ON_SEVENTH_RANK_BONUS;
         let mut on_open_file_count = 0;
+        let mut connected = false;
+        let mut behind_passed_pawn_count = 0;
         let rook_iterator = BitboardIterator::new(rooks);
         for rook_index in rook_iterator {
             if util::column_bitboard(rook_index % 8) & (pawns) == 0 {
                 on_open_file_count += 1;
             }
+            let rook_attacks = move_gen::get_sliding_moves_by_piece_type_and_square_index(
+                &PieceType::Rook,
+                rook_index,
+                board.bitboard_all_pieces(),
+            );
+            connected |= rook_attacks & rooks != 0;
+            behind_passed_pawn_count +=
+                count_passed_pawns_in_front_of_rook(board, rook_index, rook_attacks);
         }
-        seventh_rank_bonus + on_open_file_count * ROOK_ON_OPEN_FILE_BONUS
+        seventh_rank_bonus
+            + on_open_file_count * ROOK_ON_OPEN_FILE_BONUS
+            + connected as i32 * CONNECTED_ROOKS_BONUS
+            + behind_passed_pawn_count * ROOK_BEHIND_PASSED_PAWN_BONUS
+    }
+
+    /// Counts the passed pawns of either color attacked by the rook from behind
+    fn count_passed_pawns_in_front_of_rook(
+        board: &Board,
+        rook_index: usize,
+        rook_attacks: u64,
+    ) -> i32 {
+        PieceColor::iter()
+            .map(|pawn_color| {
+                let pawns = board.bitboard_by_color_and_piece_type(pawn_color, PieceType::Pawn);
+                let their_pawns =
+                    board.bitboard_by_color_and_piece_type(!pawn_color, PieceType::Pawn);
+                BitboardIterator::new(pawns & rook_attacks & util::column_bitboard(rook_index % 8))
+                    .filter(|&pawn_index| {
+                        let is_behind = if pawn_color == PieceColor::White {
+                            rook_index < pawn_index
+                        } else {
+                            rook_index > pawn_index
+                        };
+                        is_behind && pawns::is_passed_pawn(pawn_index, pawn_color, their_pawns)
+                    })
+                    .count() as i32
+            })
+            .sum()
     }
     let board = position.board();
     score_rooks_for_color(board, PieceColor::White)
@@ -240,7 +564,7 @@ mod tests {
 
         let fen = "rnbqkbnr/pppppppp/8/8/8/8/8/4K3 b kq - 0 1";
         let all_black_no_white: Position = Position::from(fen);
-        assert_eq!(score_position(&all_black_no_white), 4060);
+        assert_eq!(score_position(&all_black_no_white), 4120);
 
         let fen = "3k4/8/8/8/8/8/2p5/4K3 w - - 0 1";
         let black_pawn_on_seventh_rank: Position = Position::from(fen);
@@ -249,27 +573,118 @@ mod tests {
 
     #[test]
     fn test_get_repetition_count() {
-        assert_eq!(Search::position_occurrence_count_static(&vec!()), 0);
+        assert_eq!(Search::position_occurrence_count_static(&[]), 0);
 
         let k1 = || RepetitionKey { zobrist_hash: 1, half_move_clock: 100 };
         let k2 = || RepetitionKey { zobrist_hash: 2, half_move_clock: 100 };
         let k3 = || RepetitionKey { zobrist_hash: 2, half_move_clock: 0 };
-        assert_eq!(Search::position_occurrence_count_static(&vec![]), 0);
-        assert_eq!(Search::position_occurrence_count_static(&vec![k1()]), 1);
-        assert_eq!(Search::position_occurrence_count_static(&vec![k2(), k1()]), 1);
-        assert_eq!(Search::position_occurrence_count_static(&vec![k1(), k2(), k1()]), 2);
-        assert_eq!(Search::position_occurrence_count_static(&vec![k2(), k3(), k1(), k2()]), 2);
-        assert_eq!(Search::position_occurrence_count_static(&vec![k2(), k3(), k1(), k3()]), 1);
+        assert_eq!(Search::position_occurrence_count_static(&[]), 0);
+        assert_eq!(Search::position_occurrence_count_static(&[k1()]), 1);
+        assert_eq!(Search::position_occurrence_count_static(&[k2(), k1()]), 1);
+        assert_eq!(Search::position_occurrence_count_static(&[k1(), k2(), k1()]), 2);
+        assert_eq!(Search::position_occurrence_count_static(&[k2(), k3(), k1(), k2()]), 2);
+        assert_eq!(Search::position_occurrence_count_static(&[k2(), k3(), k1(), k3()]), 1);
+        assert_eq!(Search::position_occurrence_count_static(&[k2(), k2(), k2(), k2(), k2()]), 5);
+        assert_eq!(Search::position_occurrence_count_static(&[k2(), k3(), k2(), k2(), k2()]), 4);
+    }
+
+    #[test]
+    fn test_repetition_count() {
+        let k1 = || RepetitionKey { zobrist_hash: 1, half_move_clock: 100 };
+        let k2 = || RepetitionKey { zobrist_hash: 2, half_move_clock: 100 };
+        let k3 = || RepetitionKey { zobrist_hash: 2, half_move_clock: 0 };
+        assert_eq!(repetition_count(&[]), 0);
+        assert_eq!(repetition_count(&[k1()]), 1);
+        assert_eq!(repetition_count(&[k2(), k1()]), 1);
+        assert_eq!(repetition_count(&[k1(), k2(), k1()]), 2);
+        assert_eq!(repetition_count(&[k2(), k3(), k1(), k2()]), 2);
+        assert_eq!(repetition_count(&[k2(), k3(), k1(), k3()]), 1);
+        assert_eq!(repetition_count(&[k2(), k2(), k2(), k2(), k2()]), 5);
+        assert_eq!(repetition_count(&[k2(), k3(), k2(), k2(), k2()]), 4);
+    }
+
+    #[test]
+    fn test_connected_rooks() {
+        let connected: Position = Position::from("4k3/pp6/8/8/8/8/PP6/R4RK1 w - - 0 1");
+        let not_connected: Position = Position::from("4k3/pp6/8/8/8/8/PP6/R2B1RK1 w - - 0 1");
+        assert_eq!(score_rooks(&connected), CONNECTED_ROOKS_BONUS + ROOK_ON_OPEN_FILE_BONUS);
+        assert_eq!(score_rooks(&not_connected), ROOK_ON_OPEN_FILE_BONUS);
+    }
+
+    #[test]
+    fn test_rook_behind_passed_pawn() {
+        let behind_own_passer: Position = Position::from("8/7k/8/3P4/8/8/8/3R2K1 w - - 0 1");
+        assert_eq!(score_rooks(&behind_own_passer), ROOK_BEHIND_PASSED_PAWN_BONUS);
+        for in_front_of_own_passer in
+            ["3R4/7k/8/3P4/8/8/8/6K1 w - - 0 1", "8/3R3k/8/3P4/8/8/8/6K1 w - - 0 1"]
+        {
+            let in_front_of_own_passer: Position = Position::from(in_front_of_own_passer);
+            assert_eq!(score_rooks(&in_front_of_own_passer), 0);
+            assert!(score_position(&behind_own_passer) > score_position(&in_front_of_own_passer));
+        }
+
+        let behind_enemy_passer: Position = Position::from("3R4/7k/8/8/3p4/8/8/6K1 w - - 0 1");
+        let in_front_of_enemy_passer: Position = Position::from("8/7k/8/8/3p4/8/8/3R2K1 w - - 0 1");
         assert_eq!(
-            Search::position_occurrence_count_static(&vec![k2(), k2(), k2(), k2(), k2()]),
-            5
+            score_rooks(&behind_enemy_passer) - score_rooks(&in_front_of_enemy_passer),
+            ROOK_BEHIND_PASSED_PAWN_BONUS
         );
+
+        let blocked: Position = Position::from("8/7k/8/3P4/8/8/3N4/3R2K1 w - - 0 1");
+        assert_eq!(score_rooks(&blocked), 0);
+    }
+
+    #[test]
+    fn test_space() {
+        assert_eq!(score_space(&Position::new_game()), 0);
+        let after_d4 = Position::from("rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq - 0 1");
+        let after_d4_d5 =
+            Position::from("rnbqkbnr/ppp1pppp/8/3p4/3P4/8/PPP1PPPP/RNBQKBNR w KQkq - 0 2");
+        assert!(score_space(&after_d4) > 0);
+        assert!(score_space(&after_d4) <= 4 * SPACE_BONUS_PER_SQUARE);
+        assert_eq!(score_space(&after_d4_d5), 0);
+    }
+
+    #[test]
+    fn test_exchange_is_worth_more_in_an_open_position() {
+        // white has a rook for a knight in both positions
+        let open: Position = Position::from("2n1k3/p7/8/8/8/8/P7/3RK3 w - - 0 1");
+        let blocked: Position =
+            Position::from("2n1k3/1p1p1p1p/p1p1p1p1/P1P1P1P1/1P1P1P1P/8/8/3RK3 w - - 0 1");
+        let open_imbalance = score_material_imbalance(open.board().get_piece_counts());
+        let blocked_imbalance = score_material_imbalance(blocked.board().get_piece_counts());
+        assert_eq!(open_imbalance, (EXCHANGE_NEUTRAL_PAWN_COUNT - 2) * EXCHANGE_VALUE_PER_PAWN);
+        assert_eq!(blocked_imbalance, (EXCHANGE_NEUTRAL_PAWN_COUNT - 16) * EXCHANGE_VALUE_PER_PAWN);
+        assert!(open_imbalance > 0 && blocked_imbalance < 0);
+
+        let mirrored: Position =
+            Position::from("3rk3/8/8/1p1p1p1p/p1p1p1p1/P1P1P1P1/1P1P1P1P/2N1K3 b - - 0 1");
+        assert_eq!(
+            score_material_imbalance(mirrored.board().get_piece_counts()),
+            -blocked_imbalance
+        );
+        assert_eq!(score_position(&mirrored), score_position(&blocked));
+
+        let no_pawns: Position = Position::from("2n1k3/8/8/8/8/8/8/3RK3 w - - 0 1");
         assert_eq!(
-            Search::position_occurrence_count_static(&vec![k2(), k3(), k2(), k2(), k2()]),
-            4
+            score_material_imbalance(no_pawns.board().get_piece_counts()),
+            MAXIMUM_EXCHANGE_ADJUSTMENT
         );
     }
 
+    #[test]
+    fn test_no_exchange_imbalance() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            "1n2k3/8/8/8/8/8/8/RN2K3 w - - 0 1",
+            "r3k3/8/8/8/8/8/8/1NR1K3 w - - 0 1",
+        ] {
+            let position: Position = Position::from(fen);
+            assert_eq!(score_material_imbalance(position.board().get_piece_counts()), 0, "{fen}");
+        }
+    }
+
     #[test]
     fn test_calculate_new_game_phase() {
         let position: Position = Position::new_game();
@@ -313,20 +728,21 @@ mod tests {
 
     #[test]
     fn test_bishop_scores() {
+        // the bishops are on opposite colored squares so the score is halved
         let position: Position = Position::from("b3k3/8/8/8/3B4/8/8/4K3 w - - 0 1");
-        assert_eq!(score_position(&position), 43);
+        assert_eq!(score_position(&position), 21);
     }
 
     #[test]
     fn test_rook_scores() {
         let position: Position = Position::from("4k1r1/8/R7/8/8/8/8/4K3 w - - 0 1");
-        assert_eq!(score_position(&position), 7);
+        assert_eq!(score_position(&position), 8);
     }
 
     #[test]
     fn test_queen_scores() {
         let position: Position = Position::from("4k1q1/8/QQ6/8/8/8/8/4K3 w - - 0 1");
-        assert_eq!(score_position(&position), 903);
+        assert_eq!(score_position(&position), 917);
     }
 
     #[test]
@@ -342,7 +758,109 @@ mod tests {
             let position: Position = Position::from(
                 "r2qk1nr/pppb1ppp/2n1b3/3pp3/3PP3/3B1N2/PPPB1PPP/RN1QK2R w KQkq - 0 1",
             );
-            assert_eq!(score_bishops(&position), BISHOP_PAIR_BONUS);
+            assert_eq!(score_bishops(&position, &EvaluationWeights::default()), BISHOP_PAIR_BONUS);
+        }
+
+        #[test]
+        fn test_piece_values() {
+            let position = Position::from("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1");
+            let weights = EvaluationWeights::default();
+            let mut piece_values = weights.piece_values;
+            piece_values[PieceType::Bishop as usize] = 325;
+            let heavier_bishop = EvaluationWeights { piece_values, ..weights };
+            assert_eq!(
+                score_position_using_weights(&position, &heavier_bishop)
+                    - score_position_using_weights(&position, &weights),
+                25
+            );
+        }
+
+        #[test]
+        fn test_evaluation_stays_below_the_mate_band() {
+            let position = Position::from("QQQQkQQQ/QQQQQQQQ/8/8/8/8/8/4K3 w - - 0 1");
+            let weights = EvaluationWeights::default();
+            let mut piece_values = weights.piece_values;
+            piece_values[PieceType::Queen as usize] = 10000;
+            let absurd_queens = EvaluationWeights { piece_values, ..weights };
+
+            let score = score_position_using_weights(&position, &absurd_queens);
+            assert_eq!(score, MAXIMUM_EVALUATION);
+            assert!(!Search::is_mating_score(score));
+
+            let flipped = Position::from("4k3/8/8/8/8/8/qqqqqqqq/qqqqKqqq b - - 0 1");
+            assert_eq!(score_position_using_weights(&flipped, &absurd_queens), MAXIMUM_EVALUATION);
+            let black_to_move = Position::from("QQQQkQQQ/QQQQQQQQ/8/8/8/8/8/4K3 b - - 0 1");
+            assert_eq!(
+                score_position_using_weights(&black_to_move, &absurd_queens),
+                -MAXIMUM_EVALUATION
+            );
+            assert!(!Search::is_mating_score(score_position(&position)));
+        }
+
+        #[test]
+        fn test_bishop_pair_weight() {
+            let position: Position = Position::from(
+                "r2qk1nr/pppb1ppp/2n1b3/3pp3/3PP3/3B1N2/PPPB1PPP/RN1QK2R w KQkq - 0 1",
+            );
+            let weights = EvaluationWeights::default();
+            let without_bishop_pair = EvaluationWeights { bishop_pair_bonus: 0, ..weights };
+            assert_eq!(
+                score_position_using_weights(&position, &weights),
+                score_position(&position)
+            );
+            assert_eq!(
+                score_position_using_weights(&position, &weights)
+                    - score_position_using_weights(&position, &without_bishop_pair),
+                BISHOP_PAIR_BONUS
+            );
+        }
+
+        #[test]
+        fn test_trapped_bishop() {
+            let trapped = Position::from("4k3/B7/1p6/8/8/8/8/4K3 w - - 0 1");
+            let escaped = Position::from("4k3/B7/8/1p6/8/8/8/4K3 w - - 0 1");
+            let weights = EvaluationWeights::default();
+            assert_eq!(score_bishops(&trapped, &weights), -TRAPPED_BISHOP_PENALTY);
+            assert_eq!(score_bishops(&escaped, &weights), 0);
+            assert!(score_position(&trapped) < score_position(&escaped));
+
+            let trapped_black_bishop = Position::from("4k3/8/8/8/8/6P1/7b/4K3 w - - 0 1");
+            assert_eq!(score_bishops(&trapped_black_bishop, &weights), TRAPPED_BISHOP_PENALTY);
+        }
+
+        #[test]
+        fn test_opposite_colored_bishop_ending_is_scaled_towards_a_draw() {
+            let opposite_colored = Position::from("4k3/8/2b5/8/3P4/8/5B2/4K3 w - - 0 1");
+            let same_colored = Position::from("4k3/8/3b4/8/3P4/8/5B2/4K3 w - - 0 1");
+            assert_eq!(score_position(&opposite_colored), 48);
+            assert_eq!(score_position(&same_colored), 95);
+        }
+
+        #[test]
+        fn test_two_knights_against_lone_king_is_scaled_to_a_draw() {
+            let two_knights = Position::from("4k3/8/8/8/8/8/8/1N2K1N1 w - - 0 1");
+            assert!(score_position(&two_knights).abs() <= 1);
+            let black_two_knights = Position::from("1n2k1n1/8/8/8/8/8/8/4K3 b - - 0 1");
+            assert!(score_position(&black_two_knights).abs() <= 1);
+            let against_pawn = Position::from("4k3/4p3/8/8/8/8/8/1N2K1N1 w - - 0 1");
+            assert!(score_position(&against_pawn) > 200);
+            let with_pawn = Position::from("4k3/8/8/8/8/8/4P3/1N2K1N1 w - - 0 1");
+            assert!(score_position(&with_pawn) > 400);
+        }
+
+        #[test]
+        fn test_is_opposite_colored_bishop_ending() {
+            let is_ocb_ending = |fen: &str| {
+                let position = Position::from(fen);
+                is_opposite_colored_bishop_ending(
+                    position.board().get_piece_counts(),
+                    position.board(),
+                )
+            };
+            assert!(is_ocb_ending("4k3/8/2b5/8/3P4/8/5B2/4K3 w - - 0 1"));
+            assert!(!is_ocb_ending("4k3/8/3b4/8/3P4/8/5B2/4K3 w - - 0 1"));
+            assert!(!is_ocb_ending("4k3/8/2b5/8/3P4/8/5B2/R3K3 w - - 0 1"));
+            assert!(!is_ocb_ending("4k3/8/2b5/8/3P4/8/5B2/1N2K3 w - - 0 1"));
         }
     }
 
@@ -353,11 +871,16 @@ mod tests {
             let position: Position = Position::from("4k3/1R5R/8/8/8/8/7P/4K3 w - - 0 1");
             assert_eq!(
                 score_rooks(&position),
-                DOUBLED_ROOKS_ON_SEVENTH_RANK_BONUS + ROOK_ON_OPEN_FILE_BONUS
+                DOUBLED_ROOKS_ON_SEVENTH_RANK_BONUS
+                    + ROOK_ON_OPEN_FILE_BONUS
+                    + CONNECTED_ROOKS_BONUS
             );
 
             let position: Position = Position::from("4k3/p6p/8/8/8/8/r6r/4K3 w - - 0 1");
-            assert_eq!(score_rooks(&position), -DOUBLED_ROOKS_ON_SEVENTH_RANK_BONUS);
+            assert_eq!(
+                score_rooks(&position),
+                -DOUBLED_ROOKS_ON_SEVENTH_RANK_BONUS - CONNECTED_ROOKS_BONUS
+            );
         }
 
         #[test]
@@ -372,10 +895,18 @@ mod tests {
         #[test]
         fn test_rook_on_open_file() {
             let position: Position = Position::from("4k3/8/8/8/8/8/5P1P/4KRRR w K - 0 1");
-            assert_eq!(score_rooks(&position), ROOK_ON_OPEN_FILE_BONUS);
+            assert_eq!(
+                score_rooks(&position),
+                ROOK_ON_OPEN_FILE_BONUS + CONNECTED_ROOKS_BONUS + ROOK_BEHIND_PASSED_PAWN_BONUS * 2
+            );
 
             let position: Position = Position::from("2rrk2r/8/3p4/8/8/8/8/4K3 w k - 0 1");
-            assert_eq!(score_rooks(&position), -(ROOK_ON_OPEN_FILE_BONUS * 2));
+            assert_eq!(
+                score_rooks(&position),
+                -(ROOK_ON_OPEN_FILE_BONUS * 2
+                    + CONNECTED_ROOKS_BONUS
+                    + ROOK_BEHIND_PASSED_PAWN_BONUS)
+            );
         }
     }
 
@@ -385,59 +916,59 @@ mod tests {
         #[test]
         fn test_new_game() {
             let position = Position::new_game();
-            assert_eq!(has_insufficient_material(&position), false);
+            assert!(!has_insufficient_material(&position));
         }
 
         #[test]
         fn test_only_kings() {
             let fen = "4k3/8/8/8/8/8/8/3K4 b - - 1 1";
             let position = Position::from(fen);
-            assert_eq!(has_insufficient_material(&position), true);
+            assert!(has_insufficient_material(&position));
         }
 
         #[test]
         fn test_has_one_queen() {
             let fen = "4k3/8/8/8/8/8/4q3/1K6 b - - 5 3";
             let position = Position::from(fen);
-            assert_eq!(has_insufficient_material(&position), false);
+            assert!(!has_insufficient_material(&position));
         }
         #[test]
         fn test_has_one_rook() {
             let fen = "4k3/8/8/8/8/8/4r3/1K6 b - - 5 3";
             let position = Position::from(fen);
-            assert_eq!(has_insufficient_material(&position), false);
+            assert!(!has_insufficient_material(&position));
         }
         #[test]
         fn test_has_one_bishop() {
             let fen = "4k3/8/8/8/8/8/4b3/1K6 b - - 5 3";
             let position = Position::from(fen);
-            assert_eq!(has_insufficient_material(&position), true);
+            assert!(has_insufficient_material(&position));
         }
         #[test]
         fn test_has_one_knight() {
             let fen = "4k3/8/8/8/8/8/4n3/1K6 b - - 5 3";
             let position = Position::from(fen);
-            assert_eq!(has_insufficient_material(&position), true);
+            assert!(has_insufficient_material(&position));
         }
         #[test]
         fn test_has_two_knights() {
             let fen = "4k3/8/8/8/8/8/n3n3/1K6 b - - 5 3";
             let position = Position::from(fen);
-            assert_eq!(has_insufficient_material(&position), true);
+            assert!(has_insufficient_material(&position));
         }
 
         #[test]
         fn test_has_two_bishops_on_same_color_squares() {
             let fen = "4k3/1b6/8/8/6b1/8/8/1K6 w - - 5 3";
             let position = Position::from(fen);
-            assert_eq!(has_insufficient_material(&position), true);
+            assert!(has_insufficient_material(&position));
         }
 
         #[test]
         fn test_has_two_bishops_on_different_color_squares() {
             let fen = "4k3/1b6/8/8/5b2/8/8/1K6 w - - 5 3";
             let position = Position::from(fen);
-            assert_eq!(has_insufficient_material(&position), false);
+            assert!(!has_insufficient_material(&position));
         }
     }
 
@@ -445,34 +976,221 @@ mod tests {
     mod game_tests {
         use super::*;
         use crate::core::move_gen::has_legal_move;
+        use crate::utils::util;
+
+        fn history(fen: &str, moves: &str) -> (Position, Vec<RepetitionKey>) {
+            let position = Position::from(fen);
+            let (position_move_pairs, illegal_move) = util::replay_legal_moves(&position, moves);
+            assert_eq!(illegal_move, None);
+            let keys = util::repetition_keys_from_history(&position, &position_move_pairs);
+            (position_move_pairs.last().map_or(position, |(p, _)| *p), keys)
+        }
+
+        #[test]
+        fn test_is_draw_now() {
+            let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+            let (position, keys) = history(fen, "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1");
+            assert_eq!(is_draw_now(&position, &keys), None);
+
+            let (position, keys) = history(fen, "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8");
+            assert_eq!(is_draw_now(&position, &keys), Some(GameStatus::DrawnByThreefoldRepetition));
+
+            let (position, keys) = history("4k3/8/8/8/8/8/4P3/4K3 w - - 99 80", "e1d1");
+            assert_eq!(is_draw_now(&position, &keys), Some(GameStatus::DrawnByFiftyMoveRule));
+
+            let position = Position::from("4k3/8/8/8/8/8/8/4KN2 w - - 0 1");
+            assert_eq!(
+                is_draw_now(&position, &[RepetitionKey::new(&position)]),
+                Some(GameStatus::DrawnByInsufficientMaterial)
+            );
+        }
+
+        #[test]
+        fn test_automatic_draws() {
+            let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+            let moves = "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8";
+            let (position, keys) = history(fen, &format!("{moves} g1f3 g8f6 f3g1"));
+            assert_eq!(is_draw_now(&position, &keys), Some(GameStatus::DrawnByThreefoldRepetition));
+            let (position, keys) = history(fen, &format!("{moves} g1f3 g8f6 f3g1 f6g8"));
+            assert_eq!(is_draw_now(&position, &keys), Some(GameStatus::DrawnByFivefoldRepetition));
+            assert_eq!(get_game_status(&position, &keys), GameStatus::DrawnByFivefoldRepetition);
+
+            let (position, keys) = history("4k3/8/8/8/8/8/4P3/4K3 w - - 148 130", "e1d1");
+            assert_eq!(is_draw_now(&position, &keys), Some(GameStatus::DrawnByFiftyMoveRule));
+            let (position, keys) = history("4k3/8/8/8/8/8/4P3/4K3 w - - 149 130", "e1d1");
+            assert_eq!(is_draw_now(&position, &keys), Some(GameStatus::DrawnBySeventyFiveMoveRule));
+
+            let (position, keys) = history("6k1/5ppp/8/8/8/8/8/R5K1 w - - 149 130", "a1a8");
+            assert_eq!(get_game_status(&position, &keys), GameStatus::Checkmate);
+        }
+
+        #[test]
+        fn test_only_claimable_draws_are_subject_to_contempt() {
+            assert_eq!(draw_score(GameStatus::DrawnByThreefoldRepetition, 100), -100);
+            assert_eq!(draw_score(GameStatus::DrawnByFiftyMoveRule, 100), -100);
+            assert_eq!(draw_score(GameStatus::DrawnByFivefoldRepetition, 100), 0);
+            assert_eq!(draw_score(GameStatus::DrawnBySeventyFiveMoveRule, 100), 0);
+            assert_eq!(draw_score(GameStatus::Stalemate, 100), 0);
+            assert_eq!(draw_score(GameStatus::DrawnByInsufficientMaterial, 100), 0);
+        }
+
+        #[test]
+        fn test_analyse_mode_scores_a_draw_objectively() {
+            // the position has occurred three times so the side to move can claim a draw
+            let (position, keys) = history(
+                "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 10 1",
+                "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8",
+            );
+            let score = |analyse_mode| {
+                evaluate_with_contempt(
+                    &position,
+                    0,
+                    &keys,
+                    config::contempt_in_mode(50, analyse_mode),
+                )
+            };
+            assert_eq!(score(false), -50);
+            assert_eq!(score(true), 0);
+        }
+
+        #[test]
+        fn test_checkmate_takes_precedence_over_fifty_move_rule() {
+            let (position, keys) = history("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80", "a1a8");
+            assert!(is_checkmate(&position));
+            assert_eq!(is_draw_now(&position, &keys), None);
+            assert_eq!(get_game_status(&position, &keys), GameStatus::Checkmate);
+        }
+
         #[test]
         fn test_double_check() {
             let fen = "2r2q1k/5pp1/4p1N1/8/1bp5/5P1R/6P1/2R4K b - - 0 1";
             let position = Position::from(fen);
-            assert_eq!(is_check(&position), true);
+            assert!(is_check(&position));
             assert_eq!(check_count(&position), 2);
-            assert_eq!(get_game_status(&position, &vec!()), GameStatus::InProgress);
-            assert_eq!(has_legal_move(&position), true);
+            assert_eq!(get_game_status(&position, &[]), GameStatus::InProgress);
+            assert!(has_legal_move(&position));
         }
 
         #[test]
         fn test_checkmate() {
             let fen = "8/8/8/5k1K/8/8/8/7r w - - 0 1";
             let position = Position::from(fen);
-            assert_eq!(is_check(&position), true);
+            assert!(is_check(&position));
             assert_eq!(check_count(&position), 1);
-            assert_eq!(get_game_status(&position, &vec!()), GameStatus::Checkmate);
-            assert_eq!(has_legal_move(&position), false);
+            assert_eq!(get_game_status(&position, &[]), GameStatus::Checkmate);
+            assert!(!has_legal_move(&position));
         }
 
         #[test]
         fn test_stalemate() {
             let fen = "7K/5k2/5n2/8/8/8/8/8 w - - 0 1";
             let position = Position::from(fen);
-            assert_eq!(is_check(&position), false);
+            assert!(!is_check(&position));
             assert_eq!(check_count(&position), 0);
-            assert_eq!(get_game_status(&position, &vec!()), GameStatus::Stalemate);
-            assert_eq!(has_legal_move(&position), false);
+            assert_eq!(get_game_status(&position, &[]), GameStatus::Stalemate);
+            assert!(!has_legal_move(&position));
+        }
+    }
+
+    mod symmetry {
+        use super::*;
+        use crate::core::move_gen::generate_moves;
+        use crate::utils::fen;
+        use rand::Rng;
+        use rand_xoshiro::rand_core::SeedableRng;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        const NUMBER_OF_RANDOM_POSITIONS: usize = 500;
+        const MAXIMUM_RANDOM_PLIES: usize = 80;
+
+        // swaps the colours of the pieces, reflects the board top to bottom and passes the move
+        // to the other side, giving the same position from the other player's point of view
+        fn mirror_fen(fen: &str) -> String {
+            fn swap_case(field: &str) -> String {
+                field
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_uppercase() {
+                            c.to_ascii_lowercase()
+                        } else {
+                            c.to_ascii_uppercase()
+                        }
+                    })
+                    .collect()
+            }
+            let fields: Vec<&str> = fen.split_whitespace().collect();
+            let board = fields[0].split('/').rev().map(swap_case).collect::<Vec<_>>().join("/");
+            let side_to_move = if fields[1] == "w" { "b" } else { "w" };
+            let mut castling: Vec<char> = swap_case(fields[2]).chars().collect();
+            castling.sort_by_key(|c| (c.is_ascii_lowercase(), *c != 'K' && *c != 'k'));
+            let en_passant = fields[3].replace('3', "x").replace('6', "3").replace('x', "6");
+            format!(
+                "{board} {side_to_move} {} {en_passant} {} {}",
+                castling.into_iter().collect::<String>(),
+                fields[4],
+                fields[5]
+            )
+        }
+
+        fn random_position(rng: &mut Xoshiro256PlusPlus) -> Position {
+            let mut position = Position::new_game();
+            for _ in 0..rng.random_range(0..MAXIMUM_RANDOM_PLIES) {
+                let legal_moves: Vec<Position> = generate_moves(&position)
+                    .iter()
+                    .filter_map(|mov| {
+                        let mut next_position = position;
+                        next_position.make_move(mov).map(|_| next_position)
+                    })
+                    .collect();
+                if legal_moves.is_empty() {
+                    break;
+                }
+                position = legal_moves[rng.random_range(0..legal_moves.len())];
+            }
+            position
+        }
+
+        // the positions are compared after a round trip through FEN because whether a side has
+        // castled is part of the evaluation but is not recorded in a FEN
+        fn assert_symmetric(position: &Position) {
+            let fen = fen::write(position);
+            let mirrored = Position::from(mirror_fen(&fen).as_str());
+            assert_eq!(
+                score_position(&Position::from(fen.as_str())),
+                score_position(&mirrored),
+                "{fen} mirrored to {}",
+                fen::write(&mirrored)
+            );
+        }
+
+        #[test]
+        fn test_mirror_fen() {
+            assert_eq!(
+                mirror_fen("r3k2r/pp1n1ppp/8/2pP4/8/8/PPP2PPP/R3K2R w KQq c6 0 12"),
+                "r3k2r/ppp2ppp/8/8/2Pp4/8/PP1N1PPP/R3K2R b Qkq c3 0 12"
+            );
+        }
+
+        #[test]
+        fn test_fixed_positions_are_symmetric() {
+            for fen in [
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+                "8/8/4k3/3p4/3P4/4K3/8/8 b - - 0 1",
+                "4k3/B7/1p6/8/8/8/8/4K3 w - - 0 1",
+            ] {
+                assert_symmetric(&Position::from(fen));
+            }
+        }
+
+        #[test]
+        fn test_random_positions_are_symmetric() {
+            let mut rng = Xoshiro256PlusPlus::seed_from_u64(1105);
+            for _ in 0..NUMBER_OF_RANDOM_POSITIONS {
+                assert_symmetric(&random_position(&mut rng));
+            }
         }
     }
 }
@@ -2,6 +2,7 @@ use crate::core::move_gen;
 use crate::core::piece::PieceColor;
 use crate::core::piece::PieceType;
 use crate::core::position::Position;
+use crate::eval::evaluation::EvaluationWeights;
 use crate::eval::pawns;
 use crate::utils::bitboard_iterator::BitboardIterator;
 use crate::utils::util;
@@ -18,9 +19,22 @@ const ENEMY_PIECE_NEAR_KING_PENALTIES: [isize; 6] = [
     0,  // king
 ];
 
-pub fn score_kings(position: &Position) -> (i32, i32) {
-    let score_mg =
-        score_king_mg(position, PieceColor::White) - score_king_mg(position, PieceColor::Black);
+// the bonus per square closer than the maximum distance for pieces near the enemy king
+const KING_TROPISM_WEIGHTS: [i32; 6] = [
+    0, // pawn
+    3, // knight
+    1, // bishop
+    2, // rook
+    4, // queen
+    0, // king
+];
+const MAXIMUM_DISTANCE: i32 = 7;
+
+pub fn score_kings(position: &Position, weights: &EvaluationWeights) -> (i32, i32) {
+    let score_mg = (score_king_mg(position, PieceColor::White)
+        - score_king_mg(position, PieceColor::Black))
+        * weights.king_safety_percentage
+        / 100;
     let score_eg =
         score_king_eg(position, PieceColor::White) - score_king_eg(position, PieceColor::Black);
     (score_mg, score_eg)
@@ -52,6 +66,8 @@ fn score_king_mg(position: &Position, piece_color: PieceColor) -> i32 {
 
     score -= score_enemy_pieces_near_king(position, piece_color, king_square) as i32;
 
+    score -= score_king_tropism(position, piece_color, king_square);
+
     score
 }
 
@@ -101,6 +117,18 @@ fn score_enemy_pieces_near_king(
     })
 }
 
+fn score_king_tropism(position: &Position, piece_color: PieceColor, king_square: usize) -> i32 {
+    let enemy_piece_bitboards = position.board().bitboards_for_color(!piece_color);
+    enemy_piece_bitboards.iter().enumerate().fold(0, |acc, (index, bitboard)| {
+        acc + BitboardIterator::new(*bitboard)
+            .map(|square| {
+                let distance = util::distance(square as isize, king_square as isize) as i32;
+                KING_TROPISM_WEIGHTS[index] * (MAXIMUM_DISTANCE - distance)
+            })
+            .sum::<i32>()
+    })
+}
+
 fn king_near_passed_pawns(
     position: &Position,
     piece_color: PieceColor,
@@ -209,15 +237,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_score_king_tropism() {
+        let position = Position::new_game();
+        assert_eq!(
+            score_king_tropism(&position, PieceColor::White, sq!("e1")),
+            score_king_tropism(&position, PieceColor::Black, sq!("e8"))
+        );
+
+        let position = Position::from("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1");
+        assert_eq!(score_king_tropism(&position, PieceColor::Black, sq!("e8")), 0);
+        let position = Position::from("4k3/8/8/4N3/8/8/8/4K3 w - - 0 1");
+        assert_eq!(
+            score_king_tropism(&position, PieceColor::Black, sq!("e8")),
+            4 * KING_TROPISM_WEIGHTS[Knight as usize]
+        );
+    }
+
+    #[test]
+    fn test_knight_approaching_enemy_king_raises_score() {
+        let weights = EvaluationWeights::default();
+        let mut previous_score = i32::MIN;
+        for fen in [
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/8/PPPP1PPP/RNBQKB1R w KQkq - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p1N1/4P3/8/PPPP1PPP/RNBQKB1R w KQkq - 0 1",
+        ] {
+            let (score_mg, _) = score_kings(&Position::from(fen), &weights);
+            assert!(score_mg > previous_score);
+            previous_score = score_mg;
+        }
+    }
+
     #[test]
     fn test_is_open_file() {
         let position = Position::from("2r4k/ppqb1p1Q/5Np1/3pPp2/8/P7/2P1RPPP/R5K1 b - - 0 30");
         for file in 0..8 {
-            assert_eq!(is_open_file(&position, file), false);
+            assert!(!is_open_file(&position, file));
         }
 
         let position = Position::from("2r4k/ppqb1p1Q/5Np1/3p1p2/8/P7/2P1RPPP/R5K1 b - - 0 30");
-        assert_eq!(is_open_file(&position, 4), true);
+        assert!(is_open_file(&position, 4));
         for file in 0..8 {
             let expected = file == 4;
             assert_eq!(is_open_file(&position, file), expected);
@@ -236,7 +296,8 @@ mod tests {
     #[test]
     fn test_king_safety_opening() {
         let position = Position::new_game();
-        assert_eq!(score_kings(&position), (0, 0)); // Initial position should be balanced
+        assert_eq!(score_kings(&position, &EvaluationWeights::default()), (0, 0));
+        // Initial position should be balanced
     }
 
     #[test]
@@ -2,8 +2,22 @@ use crate::core::board::{Board, BoardSide};
 use crate::core::move_gen;
 use crate::core::piece::{PieceColor, PieceType};
 use crate::core::position::Position;
+use crate::eval::evaluation::EvaluationWeights;
 use crate::utils::bitboard_iterator::BitboardIterator;
 use crate::utils::util::column_bitboard;
+use std::cell::RefCell;
+
+pub const PASSED_PAWN_BONUS: i32 = 20;
+pub const DOUBLED_PAWN_PENALTY: i32 = 10;
+pub const ISOLATED_PAWN_PENALTY: i32 = 15;
+const BACKWARD_PAWN_PENALTY: i32 = 10;
+const BLOCKED_CENTRAL_PAWN_PENALTY: i32 = 20;
+const CONNECTED_PASSED_PAWN_BONUS_PER_RANK: i32 = 10;
+
+const PAWN_HASH_TABLE_SIZE: usize = 16384;
+
+const LIGHT_SQUARES: u64 = 0x55AA55AA55AA55AA;
+const CENTRAL_FILES: u64 = column_bitboard(3) | column_bitboard(4);
 
 const BITBOARD_REGIONS: [u64; 2] = [
     column_bitboard(5) | column_bitboard(6) | column_bitboard(7), // kingside
@@ -44,31 +58,136 @@ const PASSED_PAWNS_RANKS: [[u64; 8]; 2] = [
     ],
 ];
 
-pub fn score_pawns(position: &Position) -> (i32, i32) {
-    let score_mg = score_pawn_structure_mg(position, PieceColor::White)
-        - score_pawn_structure_mg(position, PieceColor::Black);
-    let score_eg = score_pawn_structure_eg(position, PieceColor::White)
-        - score_pawn_structure_eg(position, PieceColor::Black);
+#[derive(Clone, Copy)]
+struct PawnHashEntry {
+    pawn_hash_code: u64,
+    weights: [i32; 3],
+    score: (i32, i32),
+}
+
+/// Caches the part of the pawn structure score that depends only on where the pawns are
+struct PawnHashTable {
+    entries: Vec<Option<PawnHashEntry>>,
+}
+
+impl PawnHashTable {
+    fn new(size: usize) -> Self {
+        PawnHashTable { entries: vec![None; size] }
+    }
+
+    fn score(
+        &mut self,
+        position: &Position,
+        weights: &EvaluationWeights,
+        score_fn: impl FnOnce() -> (i32, i32),
+    ) -> (i32, i32) {
+        let pawn_hash_code = position.pawn_hash_code();
+        let pawn_weights = [
+            weights.passed_pawn_bonus,
+            weights.doubled_pawn_penalty,
+            weights.isolated_pawn_penalty,
+        ];
+        let index = (pawn_hash_code as usize) % self.entries.len();
+        match self.entries[index] {
+            Some(entry)
+                if entry.pawn_hash_code == pawn_hash_code && entry.weights == pawn_weights =>
+            {
+                entry.score
+            }
+            _ => {
+                let score = score_fn();
+                self.entries[index] =
+                    Some(PawnHashEntry { pawn_hash_code, weights: pawn_weights, score });
+                score
+            }
+        }
+    }
+}
+
+thread_local! {
+    static PAWN_HASH_TABLE: RefCell<PawnHashTable> =
+        RefCell::new(PawnHashTable::new(PAWN_HASH_TABLE_SIZE));
+}
+
+pub fn score_pawns(position: &Position, weights: &EvaluationWeights) -> (i32, i32) {
+    let (pawns_only_mg, score_eg) = PAWN_HASH_TABLE.with(|pawn_hash_table| {
+        pawn_hash_table
+            .borrow_mut()
+            .score(position, weights, || score_pawns_only(position, weights))
+    });
+    let score_mg = pawns_only_mg + score_blocked_central_pawns(position, PieceColor::White)
+        - score_blocked_central_pawns(position, PieceColor::Black);
+    (score_mg, score_eg)
+}
+
+/// Scores the pawn structure without using the pawn hash table
+pub fn score_pawns_uncached(position: &Position, weights: &EvaluationWeights) -> (i32, i32) {
+    let score_mg = score_pawn_structure_mg(position, PieceColor::White, weights)
+        - score_pawn_structure_mg(position, PieceColor::Black, weights);
+    let score_eg = score_pawn_structure_eg(position, PieceColor::White, weights)
+        - score_pawn_structure_eg(position, PieceColor::Black, weights);
     (score_mg, score_eg)
 }
 
-pub fn score_pawn_structure_mg(position: &Position, piece_color: PieceColor) -> i32 {
+/// The terms that depend on nothing but the pawns, so they can be cached by the pawn hash
+fn score_pawns_only(position: &Position, weights: &EvaluationWeights) -> (i32, i32) {
+    let score_mg = score_pawns_only_mg(position, PieceColor::White, weights)
+        - score_pawns_only_mg(position, PieceColor::Black, weights);
+    let score_eg = score_pawn_structure_eg(position, PieceColor::White, weights)
+        - score_pawn_structure_eg(position, PieceColor::Black, weights);
+    (score_mg, score_eg)
+}
+
+pub fn score_pawn_structure_mg(
+    position: &Position,
+    piece_color: PieceColor,
+    weights: &EvaluationWeights,
+) -> i32 {
+    score_pawns_only_mg(position, piece_color, weights)
+        + score_blocked_central_pawns(position, piece_color)
+}
+
+fn score_blocked_central_pawns(position: &Position, piece_color: PieceColor) -> i32 {
+    let board = position.board();
+    let pawns = board.bitboard_by_color_and_piece_type(piece_color, PieceType::Pawn);
+    -BLOCKED_CENTRAL_PAWN_PENALTY
+        * BitboardIterator::new(pawns)
+            .filter(|&pawn_square| {
+                is_blocked_central_pawn_hemming_in_bishop(board, pawn_square, piece_color)
+            })
+            .count() as i32
+}
+
+fn score_pawns_only_mg(
+    position: &Position,
+    piece_color: PieceColor,
+    weights: &EvaluationWeights,
+) -> i32 {
     let board = position.board();
     let pawns = board.bitboard_by_color_and_piece_type(piece_color, PieceType::Pawn);
+    let their_pawn_attacks = if piece_color == PieceColor::White {
+        board.black_pawn_attacks()
+    } else {
+        board.white_pawn_attacks()
+    };
 
     let mut score = 0i32;
 
     BitboardIterator::new(pawns).for_each(|pawn_square| {
+        if is_backward_pawn(pawn_square, piece_color, pawns, their_pawn_attacks) {
+            score -= BACKWARD_PAWN_PENALTY;
+        }
+
         if is_part_of_chain(piece_color, pawn_square, pawns) {
             score += 15; // Bonus for pawn chain
         }
 
         if is_doubled_pawn(pawn_square, pawns) {
-            score -= 10;
+            score -= weights.doubled_pawn_penalty;
         }
 
         if is_isolated_pawn(pawn_square, pawns) {
-            score -= 15;
+            score -= weights.isolated_pawn_penalty;
         }
     });
     score
@@ -83,13 +202,18 @@ pub fn is_passed_pawn(square: usize, piece_color: PieceColor, their_pawns: u64)
         == 0
 }
 
-fn score_pawn_structure_eg(position: &Position, piece_color: PieceColor) -> i32 {
+fn score_pawn_structure_eg(
+    position: &Position,
+    piece_color: PieceColor,
+    weights: &EvaluationWeights,
+) -> i32 {
     let board: &Board = position.board();
     let our_pawns = board.bitboard_by_color_and_piece_type(piece_color, PieceType::Pawn);
     let their_pawns = board.bitboard_by_color_and_piece_type(!piece_color, PieceType::Pawn);
 
     let mut score = 0i32;
-    score += score_passed_pawns(piece_color, our_pawns, their_pawns);
+    score += count_passed_pawns(piece_color, our_pawns, their_pawns) * weights.passed_pawn_bonus;
+    score += score_connected_passed_pawns(piece_color, our_pawns, their_pawns);
 
     if has_pawn_majority(board, piece_color, BoardSide::KingSide) {
         score += 15;
@@ -101,14 +225,29 @@ fn score_pawn_structure_eg(position: &Position, piece_color: PieceColor) -> i32
     score
 }
 
-fn score_passed_pawns(piece_color: PieceColor, our_pawns: u64, their_pawns: u64) -> i32 {
-    let mut score = 0i32;
-    for pawn_square in BitboardIterator::new(our_pawns) {
-        if is_passed_pawn(pawn_square, piece_color, their_pawns) {
-            score += 20;
-        }
-    }
-    score
+fn count_passed_pawns(piece_color: PieceColor, our_pawns: u64, their_pawns: u64) -> i32 {
+    BitboardIterator::new(our_pawns)
+        .filter(|pawn_square| is_passed_pawn(*pawn_square, piece_color, their_pawns))
+        .count() as i32
+}
+
+/// Passed pawns with a passed pawn on an adjacent file are worth more the further they have advanced
+fn score_connected_passed_pawns(piece_color: PieceColor, our_pawns: u64, their_pawns: u64) -> i32 {
+    let passed_pawns = BitboardIterator::new(our_pawns)
+        .filter(|&pawn_square| is_passed_pawn(pawn_square, piece_color, their_pawns))
+        .fold(0u64, |passed_pawns, pawn_square| passed_pawns | 1 << pawn_square);
+    BitboardIterator::new(passed_pawns)
+        .filter(|&pawn_square| passed_pawns & adjacent_file_mask(pawn_square % 8) != 0)
+        .map(|pawn_square| {
+            let relative_rank = if piece_color == PieceColor::White {
+                pawn_square / 8
+            } else {
+                7 - pawn_square / 8
+            };
+            // nothing for pawns that haven't moved yet
+            (relative_rank as i32 - 1) * CONNECTED_PASSED_PAWN_BONUS_PER_RANK
+        })
+        .sum()
 }
 
 fn has_pawn_majority(board: &Board, piece_color: PieceColor, board_side: BoardSide) -> bool {
@@ -134,6 +273,49 @@ fn is_isolated_pawn(square: usize, pawns: u64) -> bool {
     neighbors == 0
 }
 
+fn stop_square(square: usize, piece_color: PieceColor) -> usize {
+    if piece_color == PieceColor::White {
+        square + 8
+    } else {
+        square - 8
+    }
+}
+
+/// A pawn that can't safely advance because an enemy pawn controls the square in front of it and
+/// which can't be supported by a friendly pawn on an adjacent file
+fn is_backward_pawn(
+    square: usize,
+    piece_color: PieceColor,
+    our_pawns: u64,
+    their_pawn_attacks: u64,
+) -> bool {
+    let rank = square / 8;
+    let adjacent_pawns = our_pawns & adjacent_file_mask(square % 8);
+    let supporting_pawns = adjacent_pawns & PASSED_PAWNS_RANKS[!piece_color as usize][rank];
+    adjacent_pawns != 0
+        && supporting_pawns == 0
+        && their_pawn_attacks & (1 << stop_square(square, piece_color)) != 0
+}
+
+/// A d or e pawn that hasn't got past its third rank, is blocked and is on the same colored squares
+/// as one of its own bishops
+fn is_blocked_central_pawn_hemming_in_bishop(
+    board: &Board,
+    square: usize,
+    piece_color: PieceColor,
+) -> bool {
+    let relative_rank = if piece_color == PieceColor::White { square / 8 } else { 7 - square / 8 };
+    let square_bit = 1u64 << square;
+    let same_colored_squares =
+        if LIGHT_SQUARES & square_bit != 0 { LIGHT_SQUARES } else { !LIGHT_SQUARES };
+    CENTRAL_FILES & square_bit != 0
+        && relative_rank <= 2
+        && board.bitboard_all_pieces() & (1 << stop_square(square, piece_color)) != 0
+        && board.bitboard_by_color_and_piece_type(piece_color, PieceType::Bishop)
+            & same_colored_squares
+            != 0
+}
+
 fn is_doubled_pawn(square: usize, pawns: u64) -> bool {
     let file = square % 8;
     let file_mask = column_bitboard(file);
@@ -148,6 +330,8 @@ fn adjacent_file_mask(file: usize) -> u64 {
 mod tests {
     #[allow(unused_imports)]
     use super::*;
+    #[allow(unused_imports)]
+    use crate::utils::util;
 
     include!("../utils/generated_macro.rs");
 
@@ -157,11 +341,11 @@ mod tests {
         let position: Position = Position::from(fen);
         let pawn_bitboard =
             position.board().bitboard_by_color_and_piece_type(PieceColor::Black, PieceType::Pawn);
-        assert_eq!(is_doubled_pawn(sq!("a5"), pawn_bitboard), false);
-        assert_eq!(is_doubled_pawn(sq!("c3"), pawn_bitboard), false);
-        assert_eq!(is_doubled_pawn(sq!("d5"), pawn_bitboard), false);
-        assert_eq!(is_doubled_pawn(sq!("g3"), pawn_bitboard), true);
-        assert_eq!(is_doubled_pawn(sq!("g6"), pawn_bitboard), true);
+        assert!(!is_doubled_pawn(sq!("a5"), pawn_bitboard));
+        assert!(!is_doubled_pawn(sq!("c3"), pawn_bitboard));
+        assert!(!is_doubled_pawn(sq!("d5"), pawn_bitboard));
+        assert!(is_doubled_pawn(sq!("g3"), pawn_bitboard));
+        assert!(is_doubled_pawn(sq!("g6"), pawn_bitboard));
     }
     #[test]
     fn test_is_isolated_pawn() {
@@ -169,70 +353,34 @@ mod tests {
         let position: Position = Position::from(fen);
         let pawn_bitboard =
             position.board().bitboard_by_color_and_piece_type(PieceColor::Black, PieceType::Pawn);
-        assert_eq!(is_isolated_pawn(sq!("a5"), pawn_bitboard), true);
-        assert_eq!(is_isolated_pawn(sq!("c3"), pawn_bitboard), false);
-        assert_eq!(is_isolated_pawn(sq!("d5"), pawn_bitboard), false);
-        assert_eq!(is_isolated_pawn(sq!("g3"), pawn_bitboard), true);
-        assert_eq!(is_isolated_pawn(sq!("g6"), pawn_bitboard), true);
+        assert!(is_isolated_pawn(sq!("a5"), pawn_bitboard));
+        assert!(!is_isolated_pawn(sq!("c3"), pawn_bitboard));
+        assert!(!is_isolated_pawn(sq!("d5"), pawn_bitboard));
+        assert!(is_isolated_pawn(sq!("g3"), pawn_bitboard));
+        assert!(is_isolated_pawn(sq!("g6"), pawn_bitboard));
     }
 
     #[test]
     fn test_has_pawn_majority() {
         let position: Position = Position::new_game();
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::White, BoardSide::KingSide),
-            false
-        );
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::White, BoardSide::QueenSide),
-            false
-        );
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::Black, BoardSide::KingSide),
-            false
-        );
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::Black, BoardSide::QueenSide),
-            false
-        );
+        assert!(!has_pawn_majority(position.board(), PieceColor::White, BoardSide::KingSide));
+        assert!(!has_pawn_majority(position.board(), PieceColor::White, BoardSide::QueenSide));
+        assert!(!has_pawn_majority(position.board(), PieceColor::Black, BoardSide::KingSide));
+        assert!(!has_pawn_majority(position.board(), PieceColor::Black, BoardSide::QueenSide));
 
         let fen = "5rk1/5p1p/2P5/8/2b5/8/8/4K1R1 b - - 0 1";
         let position: Position = Position::from(fen);
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::White, BoardSide::KingSide),
-            false
-        );
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::White, BoardSide::QueenSide),
-            true
-        );
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::Black, BoardSide::KingSide),
-            true
-        );
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::Black, BoardSide::QueenSide),
-            false
-        );
+        assert!(!has_pawn_majority(position.board(), PieceColor::White, BoardSide::KingSide));
+        assert!(has_pawn_majority(position.board(), PieceColor::White, BoardSide::QueenSide));
+        assert!(has_pawn_majority(position.board(), PieceColor::Black, BoardSide::KingSide));
+        assert!(!has_pawn_majority(position.board(), PieceColor::Black, BoardSide::QueenSide));
 
         let fen = "4k3/8/3p4/8/8/4P3/8/4K3 b - - 0 1";
         let position: Position = Position::from(fen);
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::White, BoardSide::KingSide),
-            false
-        );
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::White, BoardSide::QueenSide),
-            false
-        );
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::Black, BoardSide::KingSide),
-            false
-        );
-        assert_eq!(
-            has_pawn_majority(position.board(), PieceColor::Black, BoardSide::QueenSide),
-            false
-        );
+        assert!(!has_pawn_majority(position.board(), PieceColor::White, BoardSide::KingSide));
+        assert!(!has_pawn_majority(position.board(), PieceColor::White, BoardSide::QueenSide));
+        assert!(!has_pawn_majority(position.board(), PieceColor::Black, BoardSide::KingSide));
+        assert!(!has_pawn_majority(position.board(), PieceColor::Black, BoardSide::QueenSide));
     }
 
     #[test]
@@ -244,13 +392,13 @@ mod tests {
         let black_pawns =
             position.board().bitboard_by_color_and_piece_type(PieceColor::Black, PieceType::Pawn);
 
-        assert_eq!(is_part_of_chain(PieceColor::White, sq!("d5"), white_pawns), false);
-        assert_eq!(is_part_of_chain(PieceColor::White, sq!("h5"), white_pawns), true);
-        assert_eq!(is_part_of_chain(PieceColor::White, sq!("g6"), white_pawns), false);
+        assert!(!is_part_of_chain(PieceColor::White, sq!("d5"), white_pawns));
+        assert!(is_part_of_chain(PieceColor::White, sq!("h5"), white_pawns));
+        assert!(!is_part_of_chain(PieceColor::White, sq!("g6"), white_pawns));
 
-        assert_eq!(is_part_of_chain(PieceColor::Black, sq!("b7"), black_pawns), true);
-        assert_eq!(is_part_of_chain(PieceColor::Black, sq!("a6"), black_pawns), false);
-        assert_eq!(is_part_of_chain(PieceColor::Black, sq!("c6"), black_pawns), false);
+        assert!(is_part_of_chain(PieceColor::Black, sq!("b7"), black_pawns));
+        assert!(!is_part_of_chain(PieceColor::Black, sq!("a6"), black_pawns));
+        assert!(!is_part_of_chain(PieceColor::Black, sq!("c6"), black_pawns));
     }
 
     #[test]
@@ -261,6 +409,106 @@ mod tests {
         assert_eq!(adjacent_file_mask(7), column_bitboard(6));
     }
 
+    #[test]
+    fn test_is_backward_pawn() {
+        let position: Position = Position::from("4k3/8/8/4p3/2P5/3P4/8/4K3 w - - 0 1");
+        let board = position.board();
+        let white_pawns =
+            board.bitboard_by_color_and_piece_type(PieceColor::White, PieceType::Pawn);
+        let black_pawn_attacks = board.black_pawn_attacks();
+        assert!(is_backward_pawn(sq!("d3"), PieceColor::White, white_pawns, black_pawn_attacks));
+        assert!(!is_backward_pawn(sq!("c4"), PieceColor::White, white_pawns, black_pawn_attacks));
+
+        let position: Position = Position::from("4k3/8/8/4p3/2P5/3P4/4P3/4K3 w - - 0 1");
+        let white_pawns =
+            position.board().bitboard_by_color_and_piece_type(PieceColor::White, PieceType::Pawn);
+        let black_pawn_attacks = position.board().black_pawn_attacks();
+        assert!(!is_backward_pawn(sq!("d3"), PieceColor::White, white_pawns, black_pawn_attacks));
+
+        let position: Position = Position::from("4k3/3p1p2/4p3/3P4/8/8/8/4K3 b - - 0 1");
+        let black_pawns =
+            position.board().bitboard_by_color_and_piece_type(PieceColor::Black, PieceType::Pawn);
+        let white_pawn_attacks = position.board().white_pawn_attacks();
+        assert!(!is_backward_pawn(sq!("e6"), PieceColor::Black, black_pawns, white_pawn_attacks));
+        let position: Position = Position::from("4k3/5p2/4p3/4P3/8/8/8/4K3 b - - 0 1");
+        let black_pawns =
+            position.board().bitboard_by_color_and_piece_type(PieceColor::Black, PieceType::Pawn);
+        let white_pawn_attacks = position.board().white_pawn_attacks();
+        assert!(is_backward_pawn(sq!("f7"), PieceColor::Black, black_pawns, white_pawn_attacks));
+    }
+
+    #[test]
+    fn test_backward_pawn_penalty() {
+        let weights = EvaluationWeights::default();
+        let backward: Position = Position::from("4k3/8/8/4p3/2P5/3P4/8/4K3 w - - 0 1");
+        let not_backward: Position = Position::from("4k3/8/8/8/2P5/3P4/8/4K3 w - - 0 1");
+        assert_eq!(
+            score_pawn_structure_mg(&not_backward, PieceColor::White, &weights)
+                - score_pawn_structure_mg(&backward, PieceColor::White, &weights),
+            BACKWARD_PAWN_PENALTY
+        );
+    }
+
+    #[test]
+    fn test_is_blocked_central_pawn_hemming_in_bishop() {
+        let position: Position = Position::from("4k3/8/8/8/8/3n4/3P4/2B1K3 w - - 0 1");
+        assert!(is_blocked_central_pawn_hemming_in_bishop(
+            position.board(),
+            sq!("d2"),
+            PieceColor::White
+        ));
+
+        let position: Position = Position::from("4k3/8/8/8/8/3n4/3P4/1B2K3 w - - 0 1");
+        assert!(!is_blocked_central_pawn_hemming_in_bishop(
+            position.board(),
+            sq!("d2"),
+            PieceColor::White
+        ));
+
+        let position: Position = Position::from("4k3/8/8/8/8/8/3P4/2B1K3 w - - 0 1");
+        assert!(!is_blocked_central_pawn_hemming_in_bishop(
+            position.board(),
+            sq!("d2"),
+            PieceColor::White
+        ));
+
+        let position: Position = Position::from("4kb2/4p3/4N3/8/8/8/8/4K3 b - - 0 1");
+        assert!(is_blocked_central_pawn_hemming_in_bishop(
+            position.board(),
+            sq!("e7"),
+            PieceColor::Black
+        ));
+
+        let position: Position = Position::from("4kb2/8/8/8/4p3/4N3/8/4K3 b - - 0 1");
+        assert!(!is_blocked_central_pawn_hemming_in_bishop(
+            position.board(),
+            sq!("e4"),
+            PieceColor::Black
+        ));
+    }
+
+    #[test]
+    fn test_cached_pawn_scores_match_recomputed_scores() {
+        let weights = EvaluationWeights::default();
+        let mut position = Position::from(
+            "r1bqkb1r/pp3ppp/2n1pn2/2pp4/3P1B2/2P1PN2/PP1N1PPP/R2QKB1R b KQkq - 0 6",
+        );
+        for raw_move in ["c5d4", "e3d4", "f8d6", "f4d6", "d8d6"] {
+            assert_eq!(score_pawns(&position, &weights), score_pawns_uncached(&position, &weights));
+            assert_eq!(score_pawns(&position, &weights), score_pawns_uncached(&position, &weights));
+            position.make_raw_move(&util::parse_move(raw_move.to_string()).unwrap()).unwrap();
+        }
+
+        let position = Position::from("4k3/8/6p1/p2p4/8/2p3p1/8/4K3 w - - 0 1");
+        assert_eq!(score_pawns(&position, &weights), score_pawns_uncached(&position, &weights));
+        let changed_weights = EvaluationWeights { isolated_pawn_penalty: 50, ..weights };
+        assert_eq!(
+            score_pawns(&position, &changed_weights),
+            score_pawns_uncached(&position, &changed_weights)
+        );
+        assert_ne!(score_pawns(&position, &changed_weights), score_pawns(&position, &weights));
+    }
+
     mod passed_pawns {
         #[allow(unused_imports)]
         use super::*;
@@ -271,7 +519,7 @@ mod tests {
             let pawns = position
                 .board()
                 .bitboard_by_color_and_piece_type(PieceColor::White, PieceType::Pawn);
-            assert_eq!(is_passed_pawn(sq!("e7"), PieceColor::Black, pawns), false);
+            assert!(!is_passed_pawn(sq!("e7"), PieceColor::Black, pawns));
         }
         #[test]
         fn test_is_passed_pawn_e2() {
@@ -279,7 +527,7 @@ mod tests {
             let pawns = position
                 .board()
                 .bitboard_by_color_and_piece_type(PieceColor::Black, PieceType::Pawn);
-            assert_eq!(is_passed_pawn(sq!("e2"), PieceColor::White, pawns), false);
+            assert!(!is_passed_pawn(sq!("e2"), PieceColor::White, pawns));
         }
 
         #[test]
@@ -292,14 +540,14 @@ mod tests {
             let black_pawns = position
                 .board()
                 .bitboard_by_color_and_piece_type(PieceColor::Black, PieceType::Pawn);
-            assert_eq!(is_passed_pawn(sq!("a5"), PieceColor::White, black_pawns), false);
-            assert_eq!(is_passed_pawn(sq!("c3"), PieceColor::White, black_pawns), true);
-            assert_eq!(is_passed_pawn(sq!("d5"), PieceColor::White, black_pawns), true);
-            assert_eq!(is_passed_pawn(sq!("g3"), PieceColor::White, black_pawns), false);
-            assert_eq!(is_passed_pawn(sq!("g6"), PieceColor::White, black_pawns), false);
-
-            assert_eq!(is_passed_pawn(sq!("a7"), PieceColor::Black, white_pawns), false);
-            assert_eq!(is_passed_pawn(sq!("h7"), PieceColor::Black, white_pawns), false);
+            assert!(!is_passed_pawn(sq!("a5"), PieceColor::White, black_pawns));
+            assert!(is_passed_pawn(sq!("c3"), PieceColor::White, black_pawns));
+            assert!(is_passed_pawn(sq!("d5"), PieceColor::White, black_pawns));
+            assert!(!is_passed_pawn(sq!("g3"), PieceColor::White, black_pawns));
+            assert!(!is_passed_pawn(sq!("g6"), PieceColor::White, black_pawns));
+
+            assert!(!is_passed_pawn(sq!("a7"), PieceColor::Black, white_pawns));
+            assert!(!is_passed_pawn(sq!("h7"), PieceColor::Black, white_pawns));
         }
 
         #[test]
@@ -313,17 +561,17 @@ mod tests {
                 .board()
                 .bitboard_by_color_and_piece_type(PieceColor::Black, PieceType::Pawn);
 
-            assert_eq!(is_passed_pawn(sq!("b5"), PieceColor::White, black_pawns), true);
-            assert_eq!(is_passed_pawn(sq!("c4"), PieceColor::White, black_pawns), true);
-            assert_eq!(is_passed_pawn(sq!("e5"), PieceColor::White, black_pawns), true);
+            assert!(is_passed_pawn(sq!("b5"), PieceColor::White, black_pawns));
+            assert!(is_passed_pawn(sq!("c4"), PieceColor::White, black_pawns));
+            assert!(is_passed_pawn(sq!("e5"), PieceColor::White, black_pawns));
 
-            assert_eq!(is_passed_pawn(sq!("f4"), PieceColor::White, black_pawns), false);
-            assert_eq!(is_passed_pawn(sq!("g4"), PieceColor::White, black_pawns), false);
-            assert_eq!(is_passed_pawn(sq!("h5"), PieceColor::White, black_pawns), false);
+            assert!(!is_passed_pawn(sq!("f4"), PieceColor::White, black_pawns));
+            assert!(!is_passed_pawn(sq!("g4"), PieceColor::White, black_pawns));
+            assert!(!is_passed_pawn(sq!("h5"), PieceColor::White, black_pawns));
 
-            assert_eq!(is_passed_pawn(sq!("d4"), PieceColor::Black, white_pawns), true);
-            assert_eq!(is_passed_pawn(sq!("f5"), PieceColor::Black, white_pawns), false);
-            assert_eq!(is_passed_pawn(sq!("h6"), PieceColor::Black, white_pawns), false);
+            assert!(is_passed_pawn(sq!("d4"), PieceColor::Black, white_pawns));
+            assert!(!is_passed_pawn(sq!("f5"), PieceColor::Black, white_pawns));
+            assert!(!is_passed_pawn(sq!("h6"), PieceColor::Black, white_pawns));
         }
         #[test]
         fn test_simple_white_passed_pawn() {
@@ -332,7 +580,7 @@ mod tests {
             let pawns = position
                 .board()
                 .bitboard_by_color_and_piece_type(PieceColor::Black, PieceType::Pawn);
-            assert_eq!(is_passed_pawn(sq!("e5"), PieceColor::White, pawns), true);
+            assert!(is_passed_pawn(sq!("e5"), PieceColor::White, pawns));
         }
 
         #[test]
@@ -342,7 +590,7 @@ mod tests {
             let pawns = position
                 .board()
                 .bitboard_by_color_and_piece_type(PieceColor::White, PieceType::Pawn);
-            assert_eq!(is_passed_pawn(sq!("e5"), PieceColor::Black, pawns), true);
+            assert!(is_passed_pawn(sq!("e5"), PieceColor::Black, pawns));
         }
 
         #[test]
@@ -375,6 +623,34 @@ mod tests {
             assert!(is_passed_pawn(sq!("e5"), PieceColor::White, pawns));
         }
 
+        #[test]
+        fn test_score_connected_passed_pawns() {
+            let score = |fen: &str| {
+                let position = Position::from(fen);
+                let board = position.board();
+                let white_pawns =
+                    board.bitboard_by_color_and_piece_type(PieceColor::White, PieceType::Pawn);
+                let black_pawns =
+                    board.bitboard_by_color_and_piece_type(PieceColor::Black, PieceType::Pawn);
+                (
+                    score_connected_passed_pawns(PieceColor::White, white_pawns, black_pawns),
+                    score_connected_passed_pawns(PieceColor::Black, black_pawns, white_pawns),
+                )
+            };
+            assert_eq!(score("k7/8/3PP3/8/8/8/8/7K w - - 0 1"), (80, 0));
+            assert_eq!(score("k7/8/2P2P2/8/8/8/8/7K w - - 0 1"), (0, 0));
+            assert_eq!(score("k7/8/8/8/8/3pp3/8/7K w - - 0 1"), (0, 80));
+            // the d pawn isn't passed
+            assert_eq!(score("k7/2p5/3PP3/8/8/8/8/7K w - - 0 1"), (0, 0));
+
+            let connected = Position::from("k7/8/3PP3/8/8/8/8/7K w - - 0 1");
+            let isolated = Position::from("k7/8/2P2P2/8/8/8/8/7K w - - 0 1");
+            assert!(
+                crate::eval::evaluation::score_position(&connected)
+                    > crate::eval::evaluation::score_position(&isolated)
+            );
+        }
+
         #[test]
         fn test_connected_passed_pawns() {
             let fen = "8/8/8/3PP3/8/8/8/k6K w - - 0 1";
@@ -3,11 +3,71 @@ use crate::core::board::Board;
 use crate::core::piece::{Piece, PieceColor, PieceType};
 #[allow(unused_imports)]
 use crate::core::position::Position;
+use crate::uci::config;
 use crate::utils::bitboard_iterator::BitboardIterator;
+use serde_derive::Deserialize;
+use std::fs;
 use strum::IntoEnumIterator;
 
 include!("../utils/generated_macro.rs");
 
+const PIECE_TYPE_COUNT: usize = 6;
+const SQUARE_COUNT: usize = 64;
+
+/// The middlegame and endgame piece square tables, indexed by piece type and then by square as
+/// the board is seen from white's side with a8 first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PieceSquareTables {
+    pub mg: [[i32; SQUARE_COUNT]; PIECE_TYPE_COUNT],
+    pub eg: [[i32; SQUARE_COUNT]; PIECE_TYPE_COUNT],
+}
+
+impl Default for PieceSquareTables {
+    fn default() -> Self {
+        PieceSquareTables { mg: MG_PST, eg: EG_PST }
+    }
+}
+
+#[derive(Deserialize)]
+struct PieceSquareTablesFile {
+    mg: Vec<Vec<i32>>,
+    eg: Vec<Vec<i32>>,
+}
+
+impl PieceSquareTables {
+    /// Reads the tables from a JSON file with mg and eg arrays, each holding a table of 64 squares
+    /// for each of the pawn, knight, bishop, rook, queen and king
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|err| format!("cannot read {path}: {err}"))?;
+        PieceSquareTables::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let file: PieceSquareTablesFile = json5::from_str(json).map_err(|err| err.to_string())?;
+        Ok(PieceSquareTables { mg: to_tables("mg", file.mg)?, eg: to_tables("eg", file.eg)? })
+    }
+}
+
+fn to_tables(
+    name: &str,
+    tables: Vec<Vec<i32>>,
+) -> Result<[[i32; SQUARE_COUNT]; PIECE_TYPE_COUNT], String> {
+    let table_count = tables.len();
+    let tables: Vec<[i32; SQUARE_COUNT]> = tables
+        .into_iter()
+        .enumerate()
+        .map(|(index, table)| {
+            let square_count = table.len();
+            table.try_into().map_err(|_| {
+                format!("{name} table {index} has {square_count} squares instead of {SQUARE_COUNT}")
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    tables
+        .try_into()
+        .map_err(|_| format!("{name} has {table_count} tables instead of {PIECE_TYPE_COUNT}"))
+}
+
 #[rustfmt::skip]
 const MG_PST: [[i32; 64]; 6] = [
     [ // mg pawns
@@ -137,19 +197,31 @@ pub const EG_PST: [[i32; 64]; 6] = [
 ];
 
 pub fn score_board_psq_values(board: &Board) -> (i32, i32) {
-    let (white_mg, white_eg) = score_board_psq_values_for_color(board, PieceColor::White);
-    let (black_mg, black_eg) = score_board_psq_values_for_color(board, PieceColor::Black);
+    score_board_psq_values_using_tables(board, &config::get_piece_square_tables())
+}
+
+pub fn score_board_psq_values_using_tables(
+    board: &Board,
+    tables: &PieceSquareTables,
+) -> (i32, i32) {
+    let (white_mg, white_eg) = score_board_psq_values_for_color(board, PieceColor::White, tables);
+    let (black_mg, black_eg) = score_board_psq_values_for_color(board, PieceColor::Black, tables);
     (white_mg - black_mg, white_eg - black_eg)
 }
-fn score_board_psq_values_for_color(board: &Board, color: PieceColor) -> (i32, i32) {
+
+fn score_board_psq_values_for_color(
+    board: &Board,
+    color: PieceColor,
+    tables: &PieceSquareTables,
+) -> (i32, i32) {
     let mut mg_score = 0i32;
     let mut eg_score = 0i32;
     let bitboards = board.bitboards_for_color(color);
     let square_index_xor = if color == PieceColor::White { 56 } else { 0 };
     for piece_type in PieceType::iter() {
         for square_index in BitboardIterator::new(bitboards[piece_type as usize]) {
-            mg_score += MG_PST[piece_type as usize][square_index ^ square_index_xor];
-            eg_score += EG_PST[piece_type as usize][square_index ^ square_index_xor];
+            mg_score += tables.mg[piece_type as usize][square_index ^ square_index_xor];
+            eg_score += tables.eg[piece_type as usize][square_index ^ square_index_xor];
         }
     }
     (mg_score, eg_score)
@@ -163,18 +235,53 @@ mod tests {
     fn test_score_board_material_balance() {
         let position = Position::new_game();
         let board = position.board();
-        assert_eq!(score_board_psq_values_for_color(&board, PieceColor::White), (-147, -193));
-        assert_eq!(score_board_psq_values_for_color(&board, PieceColor::Black), (-147, -193));
+        assert_eq!(
+            score_board_psq_values_for_color(
+                board,
+                PieceColor::White,
+                &PieceSquareTables::default()
+            ),
+            (-147, -193)
+        );
+        assert_eq!(
+            score_board_psq_values_for_color(
+                board,
+                PieceColor::Black,
+                &PieceSquareTables::default()
+            ),
+            (-147, -193)
+        );
 
         let mut board = Board::new();
-        assert_eq!(score_board_psq_values_for_color(&board, PieceColor::White), (0, 0));
-        assert_eq!(score_board_psq_values_for_color(&board, PieceColor::Black), (0, 0));
+        assert_eq!(
+            score_board_psq_values_for_color(
+                &board,
+                PieceColor::White,
+                &PieceSquareTables::default()
+            ),
+            (0, 0)
+        );
+        assert_eq!(
+            score_board_psq_values_for_color(
+                &board,
+                PieceColor::Black,
+                &PieceSquareTables::default()
+            ),
+            (0, 0)
+        );
 
         board.put_piece(
             sq!("a2"),
             Piece { piece_color: PieceColor::White, piece_type: PieceType::Pawn },
         );
-        assert_eq!(score_board_psq_values_for_color(&board, PieceColor::White), (-35, 13));
+        assert_eq!(
+            score_board_psq_values_for_color(
+                &board,
+                PieceColor::White,
+                &PieceSquareTables::default()
+            ),
+            (-35, 13)
+        );
 
         board.put_piece(
             sq!("b2"),
@@ -200,8 +307,81 @@ mod tests {
             sq!("b7"),
             Piece { piece_color: PieceColor::Black, piece_type: PieceType::Queen },
         );
-        assert_eq!(score_board_psq_values_for_color(&board, PieceColor::White), (-67, -9));
+        assert_eq!(
+            score_board_psq_values_for_color(
+                &board,
+                PieceColor::White,
+                &PieceSquareTables::default()
+            ),
+            (-67, -9)
+        );
         board.remove_piece(sq!("b2"));
-        assert_eq!(score_board_psq_values_for_color(&board, PieceColor::White), (-59, 14));
+        assert_eq!(
+            score_board_psq_values_for_color(
+                &board,
+                PieceColor::White,
+                &PieceSquareTables::default()
+            ),
+            (-59, 14)
+        );
+    }
+
+    #[test]
+    fn test_piece_square_tables_from_file() {
+        let mut tables = PieceSquareTables::default();
+        // knights on their home squares are no longer penalised
+        tables.mg[PieceType::Knight as usize][sq!("b1") ^ 56] = 0;
+        tables.mg[PieceType::Knight as usize][sq!("g1") ^ 56] = 0;
+        let path = std::env::temp_dir().join("natto_test_piece_square_tables.json");
+        fs::write(&path, format!("{{ mg: {:?}, eg: {:?} }}", tables.mg, tables.eg)).unwrap();
+        let loaded_tables = PieceSquareTables::from_file(path.to_str().unwrap()).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded_tables, tables);
+
+        // black has developed a knight so only white gains on g1
+        let position = Position::from("rnbqkb1r/pppppppp/5n2/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let (default_mg, default_eg) =
+            score_board_psq_values_using_tables(position.board(), &PieceSquareTables::default());
+        let (mg, eg) = score_board_psq_values_using_tables(position.board(), &loaded_tables);
+        assert_eq!(mg - default_mg, -MG_PST[PieceType::Knight as usize][sq!("g1") ^ 56]);
+        assert_eq!(eg, default_eg);
+    }
+
+    #[test]
+    fn test_piece_square_tables_with_the_wrong_dimensions() {
+        let tables = PieceSquareTables::default();
+        let json = |mg: &[Vec<i32>]| format!("{{ mg: {mg:?}, eg: {:?} }}", tables.eg);
+        let mg: Vec<Vec<i32>> = tables.mg.iter().map(|table| table.to_vec()).collect();
+        assert_eq!(PieceSquareTables::from_json(&json(&mg)), Ok(tables.clone()));
+        assert_eq!(
+            PieceSquareTables::from_json(&json(&mg[..5])),
+            Err("mg has 5 tables instead of 6".to_string())
+        );
+        let mut short_table = mg.clone();
+        short_table[2].pop();
+        assert_eq!(
+            PieceSquareTables::from_json(&json(&short_table)),
+            Err("mg table 2 has 63 squares instead of 64".to_string())
+        );
+        assert!(PieceSquareTables::from_json("{ mg: [] }").is_err());
+        assert!(PieceSquareTables::from_file("/no/such/file.json").is_err());
+    }
+
+    #[test]
+    fn test_endgame_king_table_prefers_the_centre() {
+        let king_scores = |piece_color: PieceColor, square: usize| {
+            let mut board = Board::new();
+            board.put_piece(square, Piece { piece_color, piece_type: PieceType::King });
+            score_board_psq_values_for_color(&board, piece_color, &PieceSquareTables::default())
+        };
+        for piece_color in [PieceColor::White, PieceColor::Black] {
+            for centre in [sq!("d4"), sq!("e4"), sq!("d5"), sq!("e5")] {
+                for corner in [sq!("a1"), sq!("h1"), sq!("a8"), sq!("h8")] {
+                    let (_, centre_eg) = king_scores(piece_color, centre);
+                    let (_, corner_eg) = king_scores(piece_color, corner);
+                    assert!(centre_eg > corner_eg);
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,205 @@
+use crate::core::piece::PieceColor;
+use crate::core::position::Position;
+use crate::eval::evaluation::{score_position_using_weights, EvaluationWeights};
+use crate::utils::fen;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+static EPD_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"^\s*(?<fen>\S+\s+[bw]\s+\S+\s+\S+)(\s+\d+\s+\d+)?\s*.*\bc9\s+"(?<result>1-0|0-1|1/2-1/2)"\s*;"#,
+    )
+    .unwrap()
+});
+
+/// The scaling constant used by the logistic function that maps a score to an expected result
+pub const DEFAULT_SCALING_CONSTANT: f64 = 1.0;
+
+#[derive(Debug, Error)]
+pub enum TuningError {
+    #[error("Failed to read training data: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid training data on line {0}: {1}")]
+    InvalidLine(usize, String),
+}
+
+/// A position labeled with the result of the game it was taken from, from white's point of view
+#[derive(Debug)]
+pub struct LabeledPosition {
+    pub position: Position,
+    pub result: f64,
+}
+
+pub fn load_labeled_positions(path: &Path) -> Result<Vec<LabeledPosition>, TuningError> {
+    parse_labeled_positions(&fs::read_to_string(path)?)
+}
+
+pub fn parse_labeled_positions(text: &str) -> Result<Vec<LabeledPosition>, TuningError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(index, line)| {
+            parse_labeled_position(line)
+                .ok_or_else(|| TuningError::InvalidLine(index + 1, line.to_string()))
+        })
+        .collect()
+}
+
+fn parse_labeled_position(line: &str) -> Option<LabeledPosition> {
+    let captures = EPD_REGEX.captures(line)?;
+    let position = fen::parse(format!("{} 0 1", &captures["fen"])).ok()?;
+    let result = match &captures["result"] {
+        "1-0" => 1.0,
+        "0-1" => 0.0,
+        _ => 0.5,
+    };
+    Some(LabeledPosition { position, result })
+}
+
+fn expected_result(score: i32, scaling_constant: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-scaling_constant * score as f64 / 400.0))
+}
+
+fn white_relative_score(position: &Position, weights: &EvaluationWeights) -> i32 {
+    let score = score_position_using_weights(position, weights);
+    if position.side_to_move() == PieceColor::White {
+        score
+    } else {
+        -score
+    }
+}
+
+/// The mean squared difference between the game results and the results predicted by the evaluation
+pub fn evaluation_error(
+    positions: &[LabeledPosition],
+    weights: &EvaluationWeights,
+    scaling_constant: f64,
+) -> f64 {
+    if positions.is_empty() {
+        return 0.0;
+    }
+    positions
+        .iter()
+        .map(|labeled| {
+            let score = white_relative_score(&labeled.position, weights);
+            (labeled.result - expected_result(score, scaling_constant)).powi(2)
+        })
+        .sum::<f64>()
+        / positions.len() as f64
+}
+
+fn parameters(weights: &mut EvaluationWeights) -> [&mut i32; 5] {
+    [
+        &mut weights.bishop_pair_bonus,
+        &mut weights.passed_pawn_bonus,
+        &mut weights.king_safety_percentage,
+        &mut weights.doubled_pawn_penalty,
+        &mut weights.isolated_pawn_penalty,
+    ]
+}
+
+/// Adjusts each weight by a single unit at a time while doing so reduces the evaluation error
+pub fn tune(
+    positions: &[LabeledPosition],
+    initial_weights: EvaluationWeights,
+    scaling_constant: f64,
+    max_iterations: usize,
+) -> EvaluationWeights {
+    let mut best_weights = initial_weights;
+    let mut best_error = evaluation_error(positions, &best_weights, scaling_constant);
+    for _ in 0..max_iterations {
+        let mut improved = false;
+        for index in 0..parameters(&mut best_weights).len() {
+            for delta in [1, -1] {
+                let mut candidate = best_weights;
+                *parameters(&mut candidate)[index] += delta;
+                let error = evaluation_error(positions, &candidate, scaling_constant);
+                if error < best_error {
+                    best_weights = candidate;
+                    best_error = error;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+    best_weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = "tests/test_data/texel_positions.epd";
+
+    #[test]
+    fn test_load_labeled_positions() {
+        let positions = load_labeled_positions(Path::new(FIXTURE)).unwrap();
+        assert_eq!(positions.len(), 10);
+        assert_eq!(positions[0].result, 0.5);
+        assert_eq!(positions[1].result, 1.0);
+        assert_eq!(positions[2].result, 0.0);
+        assert_eq!(positions[2].position.side_to_move(), PieceColor::Black);
+    }
+
+    #[test]
+    fn test_parse_labeled_positions_rejects_missing_result() {
+        let text = "8/5k2/8/3P4/8/8/5K2/8 w - - c9 \"1-0\";\n8/5k2/8/3P4/8/8/5K2/8 w - -\n";
+        match parse_labeled_positions(text) {
+            Err(TuningError::InvalidLine(line, _)) => assert_eq!(line, 2),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_labeled_position_with_move_counters() {
+        let labeled =
+            parse_labeled_position("8/5k2/8/3P4/8/8/5K2/8 b - - 3 40 c9 \"1/2-1/2\";").unwrap();
+        assert_eq!(labeled.result, 0.5);
+        assert_eq!(labeled.position.side_to_move(), PieceColor::Black);
+    }
+
+    #[test]
+    fn test_expected_result() {
+        assert_eq!(expected_result(0, DEFAULT_SCALING_CONSTANT), 0.5);
+        assert!(expected_result(400, DEFAULT_SCALING_CONSTANT) > 0.9);
+        assert!(expected_result(-400, DEFAULT_SCALING_CONSTANT) < 0.1);
+    }
+
+    #[test]
+    fn test_error_decreases_as_weights_move_towards_known_good_values() {
+        let positions = load_labeled_positions(Path::new(FIXTURE)).unwrap();
+        let known_good = EvaluationWeights::default();
+        let untuned =
+            EvaluationWeights { bishop_pair_bonus: 0, passed_pawn_bonus: 0, ..known_good };
+        let halfway = EvaluationWeights {
+            bishop_pair_bonus: known_good.bishop_pair_bonus / 2,
+            passed_pawn_bonus: known_good.passed_pawn_bonus / 2,
+            ..known_good
+        };
+        let untuned_error = evaluation_error(&positions, &untuned, DEFAULT_SCALING_CONSTANT);
+        let halfway_error = evaluation_error(&positions, &halfway, DEFAULT_SCALING_CONSTANT);
+        let known_good_error = evaluation_error(&positions, &known_good, DEFAULT_SCALING_CONSTANT);
+        assert!(halfway_error < untuned_error);
+        assert!(known_good_error < halfway_error);
+    }
+
+    #[test]
+    fn test_tune_reduces_error() {
+        let positions = load_labeled_positions(Path::new(FIXTURE)).unwrap();
+        let initial =
+            EvaluationWeights { bishop_pair_bonus: 0, passed_pawn_bonus: 0, ..Default::default() };
+        let tuned = tune(&positions, initial, DEFAULT_SCALING_CONSTANT, 10);
+        assert_ne!(tuned, initial);
+        assert!(
+            evaluation_error(&positions, &tuned, DEFAULT_SCALING_CONSTANT)
+                < evaluation_error(&positions, &initial, DEFAULT_SCALING_CONSTANT)
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use crate::core::move_gen;
+use crate::core::piece::PieceColor;
+use crate::core::position::Position;
+use crate::core::r#move::{Move, RawMove};
+use crate::eval::evaluation;
+use crate::eval::evaluation::GameStatus;
+pub use crate::eval::evaluation::{DrawReason, GameResult};
+use crate::search::move_ordering::MoveOrderer;
+use crate::search::negamax::{RepetitionKey, Search, SearchParams};
+use crate::search::transposition_table::TranspositionTable;
+use crate::utils::move_formatter::{FormatMove, PGN_FORMATTER};
+use crate::utils::{fen, util};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum GameError {
+    #[error("Illegal move: {0}")]
+    IllegalMove(String),
+    #[error("The game is over: {0:?}")]
+    GameOver(GameStatus),
+}
+
+/// A game played from a start position, keeping the history needed to detect repetitions
+#[derive(Clone, Debug)]
+pub struct Game {
+    start_position: Position,
+    position_move_pairs: Vec<(Position, Move)>,
+    repetition_keys: Vec<RepetitionKey>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(Position::new_game())
+    }
+}
+
+impl Game {
+    pub fn new(start_position: Position) -> Self {
+        Game {
+            start_position,
+            position_move_pairs: Vec::new(),
+            repetition_keys: vec![RepetitionKey::new(&start_position)],
+        }
+    }
+
+    pub fn start_position(&self) -> &Position {
+        &self.start_position
+    }
+
+    pub fn position(&self) -> &Position {
+        self.position_move_pairs.last().map_or(&self.start_position, |(position, _)| position)
+    }
+
+    pub fn moves(&self) -> Vec<Move> {
+        self.position_move_pairs.iter().map(|(_, mov)| *mov).collect()
+    }
+
+    pub fn repetition_keys(&self) -> &[RepetitionKey] {
+        &self.repetition_keys
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.position().legal_moves()
+    }
+
+    pub fn make_move(&mut self, mov: &Move) -> Result<(), GameError> {
+        let status = self.status();
+        if status != GameStatus::InProgress {
+            return Err(GameError::GameOver(status));
+        }
+        if !self.legal_moves().contains(mov) {
+            return Err(GameError::IllegalMove(mov.to_string()));
+        }
+        let mut next_position = *self.position();
+        next_position.make_move(mov);
+        self.repetition_keys.push(RepetitionKey::new(&next_position));
+        self.position_move_pairs.push((next_position, *mov));
+        Ok(())
+    }
+
+    pub fn make_raw_move(&mut self, raw_move: &RawMove) -> Result<(), GameError> {
+        let mov = util::find_generated_move(move_gen::generate_moves(self.position()), raw_move)
+            .ok_or_else(|| GameError::IllegalMove(raw_move.to_string()))?;
+        self.make_move(&mov)
+    }
+
+    pub fn status(&self) -> GameStatus {
+        evaluation::get_game_status(self.position(), &self.repetition_keys)
+    }
+
+    /// The result of the game, or None if it is still in progress
+    pub fn result(&self) -> Option<GameResult> {
+        self.position().result(&self.repetition_keys)
+    }
+
+    /// Plays the engine against itself until the game ends or the ply limit is reached, searching
+    /// each move with the given parameters. Returns the total number of nodes searched.
+    pub fn play(
+        &mut self,
+        search_params: &SearchParams,
+        transposition_table: &TranspositionTable,
+        max_plies: usize,
+    ) -> usize {
+        let mut node_count = 0;
+        while self.status() == GameStatus::InProgress && self.position_move_pairs.len() < max_plies
+        {
+            let mut position = *self.position();
+            let mut search = Search::new(
+                &mut position,
+                transposition_table,
+                search_params.clone(),
+                Arc::new(AtomicBool::new(false)),
+                self.repetition_keys.clone(),
+                MoveOrderer::new(),
+                0,
+            );
+            let search_results = search.go();
+            node_count += search.node_counter.node_count();
+            let best_move = search_results
+                .pv
+                .first()
+                .copied()
+                .or_else(|| move_gen::get_first_legal_move(self.position()))
+                .expect("a game in progress has a legal move");
+            self.make_move(&best_move).expect("the search returned an illegal move");
+        }
+        node_count
+    }
+
+    pub fn pgn(&self) -> String {
+        let result = self.result().map_or("*".to_string(), |result| result.to_string());
+        let mut tags = vec![
+            ("Event", "natto self-play".to_string()),
+            ("White", "natto".to_string()),
+            ("Black", "natto".to_string()),
+            ("Result", result.clone()),
+        ];
+        if fen::write(&self.start_position) != fen::write(&Position::new_game()) {
+            tags.push(("SetUp", "1".to_string()));
+            tags.push(("FEN", fen::write(&self.start_position)));
+        }
+        let mut pgn: String =
+            tags.iter().map(|(name, value)| format!("[{name} \"{value}\"]\n")).collect();
+        pgn.push('\n');
+
+        let formatted_moves =
+            PGN_FORMATTER.format_move_list(&self.start_position, &self.moves()).unwrap();
+        let mut move_text: Vec<String> = Vec::new();
+        let mut position = self.start_position;
+        for (formatted_move, (next_position, _)) in
+            formatted_moves.iter().zip(self.position_move_pairs.iter())
+        {
+            if position.side_to_move() == PieceColor::White {
+                move_text.push(format!("{}. {formatted_move}", position.full_move_number()));
+            } else if move_text.is_empty() {
+                move_text.push(format!("{}... {formatted_move}", position.full_move_number()));
+            } else {
+                move_text.push(formatted_move.clone());
+            }
+            position = *next_position;
+        }
+        move_text.push(result);
+        pgn.push_str(&move_text.join(" "));
+        pgn.push('\n');
+        pgn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::util::parse_move;
+
+    fn play_moves(game: &mut Game, moves: &str) {
+        for raw_move in moves.split_whitespace() {
+            game.make_raw_move(&parse_move(raw_move.to_string()).unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_make_move() {
+        let mut game = Game::default();
+        assert_eq!(game.legal_moves().len(), 20);
+        play_moves(&mut game, "e2e4 e7e5");
+        assert_eq!(game.moves().len(), 2);
+        assert_eq!(
+            fen::write(game.position()),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+        );
+        assert_eq!(
+            game.make_raw_move(&parse_move("e4e5".to_string()).unwrap()),
+            Err(GameError::IllegalMove("e4e5".to_string()))
+        );
+        assert_eq!(game.status(), GameStatus::InProgress);
+        assert_eq!(game.result(), None);
+    }
+
+    #[test]
+    fn test_checkmate() {
+        let mut game = Game::default();
+        play_moves(&mut game, "f2f3 e7e5 g2g4 d8h4");
+        assert_eq!(game.status(), GameStatus::Checkmate);
+        assert_eq!(game.result(), Some(GameResult::BlackWins));
+        assert!(game.legal_moves().is_empty());
+        assert_eq!(
+            game.make_raw_move(&parse_move("e1f2".to_string()).unwrap()),
+            Err(GameError::GameOver(GameStatus::Checkmate))
+        );
+        assert!(game.pgn().ends_with("\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+    }
+
+    #[test]
+    fn test_repetition() {
+        let mut game = Game::default();
+        play_moves(&mut game, "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8");
+        assert_eq!(game.status(), GameStatus::DrawnByThreefoldRepetition);
+        assert_eq!(game.result(), Some(GameResult::Draw(DrawReason::ThreefoldRepetition)));
+        assert_eq!(
+            game.make_move(&game.legal_moves()[0]),
+            Err(GameError::GameOver(GameStatus::DrawnByThreefoldRepetition))
+        );
+    }
+
+    #[test]
+    fn test_pgn_from_fen() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K2R b K - 0 30";
+        let mut game = Game::new(Position::from(fen));
+        play_moves(&mut game, "e8d7 e1g1 d7c6 e2e4");
+        assert_eq!(
+            game.pgn(),
+            format!(
+                "[Event \"natto self-play\"]\n[White \"natto\"]\n[Black \"natto\"]\n[Result \"*\"]\n\
+                [SetUp \"1\"]\n[FEN \"{fen}\"]\n\n30... Kd7 31. O-O Kc6 32. e4 *\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_self_play_terminates() {
+        let mut game = Game::default();
+        let transposition_table = TranspositionTable::new(16);
+        let node_count =
+            game.play(&SearchParams::new(usize::MAX, 20, 2000), &transposition_table, 1000);
+        assert_ne!(game.status(), GameStatus::InProgress);
+        assert!(game.result().is_some());
+        assert!(node_count < game.moves().len() * 4000);
+
+        let mut replayed_game = Game::default();
+        for mov in game.moves() {
+            replayed_game.make_move(&mov).unwrap();
+        }
+        assert_eq!(replayed_game.status(), game.status());
+        assert!(game.pgn().trim_end().ends_with(&game.result().unwrap().to_string()));
+    }
+}
@@ -1,6 +1,7 @@
-mod book;
+pub mod book;
 pub mod core;
 pub mod eval;
+pub mod game;
 pub mod search;
 pub mod utils;
 
@@ -4,3 +4,239 @@ pub mod quiescence;
 
 pub mod move_ordering;
 pub mod transposition_table;
+
+use crate::core::move_gen;
+use crate::core::position::Position;
+use crate::core::r#move::{Move, RawMove};
+use crate::search::move_ordering::MoveOrderer;
+use crate::search::negamax::{RepetitionKey, Search, SearchParams, SearchResults};
+use crate::search::transposition_table::TranspositionTable;
+use crate::utils::util;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+include!("utils/generated_macro.rs");
+
+pub const SEARCH_HASH_SIZE_MB: usize = 16;
+
+/// Searches the position with a transposition table of its own. The history holds the repetition
+/// keys of the game positions before this one so that repetitions can be detected.
+///
+/// ```
+/// use natto::core::position::Position;
+/// use natto::search;
+/// use natto::search::negamax::SearchParams;
+///
+/// let position = Position::from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+/// let search_results = search::search(&position, SearchParams::new_by_depth(3), &[]);
+/// assert_eq!(search_results.pv[0].to_string(), "a1-a8");
+/// assert_eq!(search::negamax::Search::mate_in_moves(search_results.score), Some(1));
+/// ```
+#[allow(dead_code)]
+pub fn search(
+    position: &Position,
+    search_params: SearchParams,
+    history: &[RepetitionKey],
+) -> SearchResults {
+    search_using_transposition_table(
+        position,
+        search_params,
+        history,
+        &TranspositionTable::new(SEARCH_HASH_SIZE_MB),
+    )
+}
+
+/// Searches the position reached by playing the moves from the given position, without the moves
+/// or the position having to be written as strings. The moves are the game history so repetitions
+/// of the positions they pass through are detected. Returns None if any of the moves is illegal.
+///
+/// ```
+/// use natto::core::position::Position;
+/// use natto::core::r#move::RawMove;
+/// use natto::search;
+/// use natto::search::negamax::SearchParams;
+///
+/// let moves = [RawMove::new(12, 28, None), RawMove::new(52, 36, None)];
+/// let search_results =
+///     search::search_after_moves(&Position::new_game(), &moves, SearchParams::new_by_depth(2));
+/// assert_eq!(search_results.unwrap().position.full_move_number(), 2);
+/// ```
+#[allow(dead_code)]
+pub fn search_after_moves(
+    position: &Position,
+    moves: &[RawMove],
+    search_params: SearchParams,
+) -> Option<SearchResults> {
+    let position_move_pairs = util::replay_raw_moves(position, moves)?;
+    let mut history = util::repetition_keys_from_history(position, &position_move_pairs);
+    // the search adds the key of the position it searches
+    history.pop();
+    let end_position = position_move_pairs.last().map_or(*position, |(p, _)| *p);
+    Some(search(&end_position, search_params, &history))
+}
+
+/// A move scored by analyze
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub struct MoveAnalysis {
+    pub mov: Move,
+    /// The score from the point of view of the side to move in the analyzed position
+    pub score: i32,
+    /// The principal variation starting with the move
+    pub pv: Vec<Move>,
+}
+
+/// Scores every legal move in the position, or only the search moves if any are given, best
+/// first. The position after each move is searched one ply less deeply than the position itself,
+/// but at least one ply deep, so at depth 1 each move is searched as if to depth 2. The time and
+/// node limits are shared between the moves, so this is much slower than finding the best move
+/// with search.
+#[allow(dead_code)]
+pub fn analyze(
+    position: &Position,
+    search_params: SearchParams,
+    history: &[RepetitionKey],
+) -> Vec<MoveAnalysis> {
+    let transposition_table = TranspositionTable::new(SEARCH_HASH_SIZE_MB);
+    let history = [history, &[RepetitionKey::new(position)]].concat();
+    let legal_moves: Vec<(Move, Position)> = move_gen::generate_moves(position)
+        .into_iter()
+        .filter(|mov| {
+            search_params.search_moves.is_empty() || search_params.search_moves.contains(mov)
+        })
+        .filter_map(|mov| {
+            let mut next_position = *position;
+            next_position.make_move(&mov).map(|_| (mov, next_position))
+        })
+        .collect();
+    let share = |limit: usize| {
+        if limit == usize::MAX {
+            limit
+        } else {
+            limit / legal_moves.len().max(1)
+        }
+    };
+    let move_search_params = SearchParams {
+        allocated_time_millis: share(search_params.allocated_time_millis),
+        max_depth: search_params.max_depth.saturating_sub(1).max(1),
+        max_nodes: share(search_params.max_nodes),
+        search_moves: vec![],
+    };
+
+    let mut analysis: Vec<MoveAnalysis> = legal_moves
+        .iter()
+        .map(|(mov, next_position)| {
+            let search_results = search_using_transposition_table(
+                next_position,
+                move_search_params.clone(),
+                &history,
+                &transposition_table,
+            );
+            // a mate is one ply further away from this position than from the next one
+            let score = -search_results.score;
+            let score = if Search::is_mating_score(score) { score - score.signum() } else { score };
+            MoveAnalysis { mov: *mov, score, pv: [vec![*mov], search_results.pv].concat() }
+        })
+        .collect();
+    analysis.sort_by_key(|move_analysis| -move_analysis.score);
+    analysis
+}
+
+fn search_using_transposition_table(
+    position: &Position,
+    search_params: SearchParams,
+    history: &[RepetitionKey],
+    transposition_table: &TranspositionTable,
+) -> SearchResults {
+    let mut position = *position;
+    let repetition_keys = [history, &[RepetitionKey::new(&position)]].concat();
+    let mut search = Search::new(
+        &mut position,
+        transposition_table,
+        search_params,
+        Arc::new(AtomicBool::new(false)),
+        repetition_keys,
+        MoveOrderer::new(),
+        0,
+    );
+    search.go()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::piece::PieceColor;
+    use crate::eval::evaluation;
+    use crate::eval::evaluation::GameStatus;
+
+    #[test]
+    fn test_analyze_ranks_the_best_move_first() {
+        let position = Position::from("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        let analysis = analyze(&position, SearchParams::new_by_depth(3), &[]);
+        let legal_move_count = move_gen::generate_moves(&position)
+            .iter()
+            .filter(|mov| {
+                let mut next_position = position;
+                next_position.make_move(mov).is_some()
+            })
+            .count();
+        assert_eq!(analysis.len(), legal_move_count);
+        let best = &analysis[0];
+        assert_eq!(best.mov.to_string(), "d2xd5");
+        assert_eq!(best.pv[0], best.mov);
+        assert!(best.score > 500);
+        assert!(analysis.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+        let mate = &analyze(
+            &Position::from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1"),
+            SearchParams::new_by_depth(2),
+            &[],
+        )[0];
+        assert_eq!(mate.mov.to_string(), "a1-a8");
+        assert_eq!(Search::mate_in_moves(mate.score), Some(1));
+    }
+
+    #[test]
+    fn test_search_after_moves() {
+        let mut board = *Position::new_game().board();
+        board.remove_piece(sq!("d8"));
+        let position = Position::new(board, PieceColor::White, "KQkq".to_string(), None, 10, 1);
+        let knight_moves = [
+            (sq!("g1"), sq!("f3")),
+            (sq!("g8"), sq!("f6")),
+            (sq!("f3"), sq!("g1")),
+            (sq!("f6"), sq!("g8")),
+            (sq!("g1"), sq!("f3")),
+            (sq!("g8"), sq!("f6")),
+            (sq!("f3"), sq!("g1")),
+        ]
+        .map(|(from, to)| RawMove::new(from, to, None));
+
+        // black is a queen down but can draw by repeating the position a third time
+        let search_results =
+            search_after_moves(&position, &knight_moves, SearchParams::new_by_depth(3)).unwrap();
+        assert_eq!(search_results.score, 0);
+        assert_eq!(search_results.pv[0].to_string(), "f6-g8");
+        assert_eq!(search_results.game_status, GameStatus::DrawnByThreefoldRepetition);
+
+        let without_history = search(&search_results.position, SearchParams::new_by_depth(3), &[]);
+        assert!(without_history.score < -500);
+
+        let illegal_move = RawMove::new(sq!("g1"), sq!("g3"), None);
+        assert!(
+            search_after_moves(&position, &[illegal_move], SearchParams::new_by_depth(1)).is_none()
+        );
+    }
+
+    #[test]
+    fn test_kings_only_position_is_a_draw() {
+        let position = Position::from("8/8/3k4/8/8/4K3/8/8 w - - 0 1");
+        assert_eq!(evaluation::evaluate(&position, 0, &[]), 0);
+        let search_results = search(&position, SearchParams::new_by_depth(5), &[]);
+        assert_eq!(search_results.game_status, GameStatus::DrawnByInsufficientMaterial);
+        assert_eq!(search_results.score, 0);
+        assert!(analyze(&position, SearchParams::new_by_depth(2), &[])
+            .iter()
+            .all(|move_analysis| move_analysis.score == 0));
+    }
+}
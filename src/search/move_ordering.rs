@@ -3,17 +3,40 @@ use crate::core::position::Position;
 use crate::core::r#move::Move;
 use crate::eval::evaluation::PIECE_SCORES;
 use crate::search::negamax::MAXIMUM_SEARCH_DEPTH;
+use crate::uci::config;
 use arrayvec::ArrayVec;
 
 // Constants for move scoring
 const HASH_MOVE_SCORE: i32 = 20000;
 const CAPTURE_SCORE_BASE: i32 = 10000;
 const KILLER_FIRST_SLOT_SCORE: i32 = 9000;
-const KILLER_SECOND_SLOT_SCORE: i32 = 8000;
+// the second killer is scored this much below the first
+const KILLER_SECOND_SLOT_DECREMENT: i32 = 1000;
 const PROMOTION_SCORE: i32 = 7500;
 const COUNTERMOVE_SCORE: i32 = 7000;
 const CASTLING_SCORE: i32 = 6000;
 
+/// The priorities of the kinds of move that can be adjusted at runtime for tuning. A quiet move
+/// that is none of these is scored by its history, scaled by the history percentage.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MoveOrderingWeights {
+    pub hash_move_score: i32,
+    pub capture_score_base: i32,
+    pub killer_score: i32,
+    pub history_percentage: i32,
+}
+
+impl Default for MoveOrderingWeights {
+    fn default() -> Self {
+        MoveOrderingWeights {
+            hash_move_score: HASH_MOVE_SCORE,
+            capture_score_base: CAPTURE_SCORE_BASE,
+            killer_score: KILLER_FIRST_SLOT_SCORE,
+            history_percentage: 100,
+        }
+    }
+}
+
 // Maximum number of killer moves to store per ply
 const MAX_KILLER_MOVES: usize = 2;
 
@@ -30,6 +53,7 @@ pub struct MoveOrderer {
     killer_moves: KillerMoves,
     history_table: HistoryTable,
     counter_moves: CounterMoveTable,
+    weights: MoveOrderingWeights,
 }
 
 impl Default for MoveOrderer {
@@ -40,10 +64,15 @@ impl Default for MoveOrderer {
 
 impl MoveOrderer {
     pub fn new() -> Self {
+        Self::new_using_weights(config::get_move_ordering_weights())
+    }
+
+    pub fn new_using_weights(weights: MoveOrderingWeights) -> Self {
         MoveOrderer {
             killer_moves: [[None; MAX_KILLER_MOVES]; MAXIMUM_SEARCH_DEPTH],
             history_table: [[[0; 64]; 64]; 2],
             counter_moves: [[None; 64]; 12],
+            weights,
         }
     }
 
@@ -162,7 +191,7 @@ impl MoveOrderer {
         // Hash move gets highest priority
         if let Some(hash_move) = hash_move {
             if *mov == hash_move {
-                return HASH_MOVE_SCORE;
+                return self.weights.hash_move_score;
             }
         }
 
@@ -170,7 +199,7 @@ impl MoveOrderer {
 
         // Captures are scored by MVV-LVA
         if base_move.capture {
-            let mut score = CAPTURE_SCORE_BASE;
+            let mut score = self.weights.capture_score_base;
 
             // Add MVV-LVA score
             if let Some(victim) = position.board().get_piece(base_move.to as usize) {
@@ -196,13 +225,13 @@ impl MoveOrderer {
         // Check if move is a killer move
         if let Some(killer1) = self.killer_moves[ply as usize][0] {
             if killer1 == *mov {
-                return KILLER_FIRST_SLOT_SCORE;
+                return self.weights.killer_score;
             }
         }
 
         if let Some(killer2) = self.killer_moves[ply as usize][1] {
             if killer2 == *mov {
-                return KILLER_SECOND_SLOT_SCORE;
+                return self.weights.killer_score - KILLER_SECOND_SLOT_DECREMENT;
             }
         }
 
@@ -221,6 +250,8 @@ impl MoveOrderer {
         // Use history score for quiet moves
         let side = position.side_to_move() as usize;
         self.history_table[side][base_move.from as usize][base_move.to as usize]
+            * self.weights.history_percentage
+            / 100
     }
 
     // Sort moves based on scores (highest first)
@@ -229,7 +260,7 @@ impl MoveOrderer {
         T: AsMut<[(Move, i32)]>,
     {
         let moves_slice = moves.as_mut();
-        moves_slice.sort_by(|a, b| b.1.cmp(&a.1));
+        moves_slice.sort_by_key(|b| std::cmp::Reverse(b.1));
     }
 
     // For SEE (Static Exchange Evaluation)
@@ -265,7 +296,7 @@ impl MoveOrderer {
 // Functions for move ordering
 pub fn order_moves(
     position: &Position,
-    moves: &mut Vec<Move>,
+    moves: &mut [Move],
     move_orderer: &MoveOrderer,
     ply: u8,
     hash_move: Option<Move>,
@@ -285,8 +316,9 @@ pub fn order_moves(
     MoveOrderer::sort_moves(&mut scored_moves);
 
     // Update the original moves list with sorted moves
-    moves.clear();
-    moves.extend(scored_moves.iter().map(|(m, _)| *m));
+    for (mov, (scored_move, _)) in moves.iter_mut().zip(scored_moves.iter()) {
+        *mov = *scored_move;
+    }
 }
 
 // Specialized capture ordering for quiescence search
@@ -299,7 +331,7 @@ pub fn order_quiescence_moves(position: &Position, moves: &mut Vec<Move>) {
     scored_moves.extend(moves.iter().map(|m| (*m, MoveOrderer::mvv_lva_score(position, m))));
 
     // Sort by score
-    scored_moves.sort_by(|a, b| b.1.cmp(&a.1));
+    scored_moves.sort_by_key(|b| std::cmp::Reverse(b.1));
 
     // Update the original moves list with sorted moves
     moves.clear();
@@ -309,7 +341,9 @@ pub fn order_quiescence_moves(position: &Position, moves: &mut Vec<Move>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::move_gen::generate_moves;
     use crate::core::r#move::BaseMove;
+    use crate::utils::util;
 
     #[test]
     fn test_killer_move_handling() {
@@ -396,4 +430,47 @@ mod tests {
         // Quiet score should be equal to history score
         assert_eq!(quiet_score, 9); // 3*3=9
     }
+
+    #[test]
+    fn test_default_move_ordering_priorities() {
+        let position = Position::from("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        let hash_move = util::find_generated_move(
+            generate_moves(&position),
+            &util::parse_move("e1f1".to_string()).unwrap(),
+        )
+        .unwrap();
+        let winning_capture = util::find_generated_move(
+            generate_moves(&position),
+            &util::parse_move("d2d5".to_string()).unwrap(),
+        )
+        .unwrap();
+        let killer_move = util::find_generated_move(
+            generate_moves(&position),
+            &util::parse_move("d2d3".to_string()).unwrap(),
+        )
+        .unwrap();
+        let mut move_orderer = MoveOrderer::new_using_weights(MoveOrderingWeights::default());
+        move_orderer.add_killer_move(killer_move, 1);
+
+        let mut moves = generate_moves(&position);
+        order_moves(&position, &mut moves, &move_orderer, 1, Some(hash_move), &None);
+        assert_eq!(moves[..3], [hash_move, winning_capture, killer_move]);
+
+        let weights =
+            MoveOrderingWeights { killer_score: CAPTURE_SCORE_BASE + 1000, ..Default::default() };
+        let mut move_orderer = MoveOrderer::new_using_weights(weights);
+        move_orderer.add_killer_move(killer_move, 1);
+        order_moves(&position, &mut moves, &move_orderer, 1, Some(hash_move), &None);
+        assert_eq!(moves[..3], [hash_move, killer_move, winning_capture]);
+    }
+
+    #[test]
+    fn test_history_percentage() {
+        let position = Position::new_game();
+        let quiet_move = Move::Basic { base_move: BaseMove { from: 11, to: 27, capture: false } };
+        let weights = MoveOrderingWeights { history_percentage: 50, ..Default::default() };
+        let mut move_orderer = MoveOrderer::new_using_weights(weights);
+        move_orderer.update_history_score(&position, &quiet_move, 4);
+        assert_eq!(move_orderer.score_move(&position, &quiet_move, None, 0, None), 8);
+    }
 }
@@ -1,13 +1,16 @@
+use crate::core::move_gen::MOVE_LIST_LENGTH;
+use crate::core::piece::PieceColor;
 use crate::core::position::Position;
 use crate::core::r#move::Move;
 use crate::core::{move_gen, r#move};
 use crate::eval::evaluation;
-use crate::eval::evaluation::GameStatus;
-use crate::eval::evaluation::GameStatus::DrawnByThreefoldRepetition;
+use crate::eval::evaluation::{EvaluationWeights, GameStatus};
+use crate::eval::psq::PieceSquareTables;
 use crate::search::move_ordering;
 use crate::search::move_ordering::MoveOrderer;
-use crate::search::transposition_table::{BoundType, TranspositionTable};
-use crate::uci::uci_util;
+use crate::search::quiescence;
+use crate::search::transposition_table::{BoundType, TTEntry, TranspositionTable};
+use crate::uci::{config, uci_util};
 use crate::utils::move_formatter;
 use crate::utils::move_formatter::FormatMove;
 use crate::utils::node_counter::{NodeCountStats, NodeCounter};
@@ -28,13 +31,74 @@ pub const MAXIMUM_SCORE: i32 = 100000;
 
 pub const DRAW_SCORE: i32 = 0;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+pub const REVERSE_FUTILITY_PRUNING_MAX_DEPTH: i32 = 3;
+
+pub const REVERSE_FUTILITY_MARGIN_PER_PLY: i32 = 150;
+
+pub const ASPIRATION_WINDOW: i32 = 50;
+
+const ASPIRATION_WINDOW_MIN_DEPTH: u8 = 4;
+
+const LOSING_CAPTURE_REDUCTION: u8 = 1;
+
+const CUTOFF_STATISTICS_MOVE_COUNT: usize = 5;
+
+/// The search parameters that can be adjusted at runtime for tuning
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SearchTuning {
+    pub aspiration_window: i32,
+    pub reverse_futility_margin: i32,
+    pub reverse_futility_max_depth: i32,
+    pub quiescence_see_threshold: i32,
+    pub quiescence_stand_pat_margin: i32,
+}
+
+impl Default for SearchTuning {
+    fn default() -> Self {
+        SearchTuning {
+            aspiration_window: ASPIRATION_WINDOW,
+            reverse_futility_margin: REVERSE_FUTILITY_MARGIN_PER_PLY,
+            reverse_futility_max_depth: REVERSE_FUTILITY_PRUNING_MAX_DEPTH,
+            quiescence_see_threshold: quiescence::QUIESCENCE_SEE_THRESHOLD,
+            quiescence_stand_pat_margin: quiescence::QUIESCENCE_STAND_PAT_MARGIN,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct SearchResults {
     pub position: Position,
     pub score: i32,
     pub depth: u8,
     pub pv: Vec<Move>,
     pub game_status: GameStatus,
+    pub node_count: usize,
+    pub elapsed_millis: usize,
+}
+
+// the node count and elapsed time describe how the results were found rather than the results
+impl PartialEq for SearchResults {
+    fn eq(&self, other: &Self) -> bool {
+        self.position == other.position
+            && self.score == other.score
+            && self.depth == other.depth
+            && self.pv == other.pv
+            && self.game_status == other.game_status
+    }
+}
+
+impl Eq for SearchResults {}
+
+impl SearchResults {
+    /// The side that wins by the checkmate at the end of the principal variation, if there is one
+    pub fn winner(&self) -> Option<PieceColor> {
+        let side_to_move = if self.pv.len().is_multiple_of(2) {
+            self.position.side_to_move()
+        } else {
+            self.position.opposing_side()
+        };
+        self.game_status.winner(side_to_move)
+    }
 }
 
 impl Display for SearchResults {
@@ -58,6 +122,8 @@ pub struct SearchParams {
     pub allocated_time_millis: usize,
     pub max_depth: u8,
     pub max_nodes: usize,
+    /// The root moves to search, all of them if empty
+    pub search_moves: Vec<Move>,
 }
 
 impl Display for SearchParams {
@@ -71,12 +137,21 @@ impl Display for SearchParams {
 }
 impl SearchParams {
     pub fn new(allocated_time_millis: usize, max_depth: isize, max_nodes: usize) -> SearchParams {
-        SearchParams { allocated_time_millis, max_depth: max_depth.try_into().unwrap(), max_nodes }
+        SearchParams {
+            allocated_time_millis,
+            max_depth: max_depth.try_into().unwrap(),
+            max_nodes,
+            search_moves: vec![],
+        }
     }
 
     pub fn new_by_depth(max_depth: isize) -> SearchParams {
         SearchParams::new(usize::MAX, max_depth, usize::MAX)
     }
+
+    pub fn is_time_limited(&self) -> bool {
+        self.allocated_time_millis != usize::MAX
+    }
 }
 pub struct Search<'a> {
     pub position: &'a mut Position,
@@ -86,8 +161,23 @@ pub struct Search<'a> {
     pub stop_flag: Arc<AtomicBool>,
     pub repetition_key_stack: Vec<RepetitionKey>,
     pub number_of_game_positions: usize,
+    pub max_quiescence_depth: u8,
+    pub reverse_futility_pruning: bool,
+    pub prune_under_promotions: bool,
+    pub reduce_losing_captures: bool,
+    pub use_quiescence: bool,
+    /// The number of best lines reported, each one found by searching the root moves again
+    /// without the best moves of the lines found before it
+    pub multi_pv: usize,
+    pub tuning: SearchTuning,
+    pub(crate) evaluation_weights: EvaluationWeights,
+    piece_square_tables: Arc<PieceSquareTables>,
     move_orderer: MoveOrderer,
     max_depth: u8,
+    last_info: String,
+    excluded_root_moves: Vec<Move>,
+    multi_pv_results: Vec<SearchResults>,
+    cutoff_counts: Vec<usize>,
 }
 
 impl<'a> Search<'a> {
@@ -107,11 +197,35 @@ impl<'a> Search<'a> {
             stop_flag,
             repetition_key_stack: repetition_keys.clone(),
             number_of_game_positions: repetition_keys.len(),
+            max_quiescence_depth: config::get_max_quiescence_depth(),
+            reverse_futility_pruning: true,
+            prune_under_promotions: true,
+            reduce_losing_captures: config::get_reduce_losing_captures(),
+            use_quiescence: config::get_use_quiescence(),
+            multi_pv: config::get_multi_pv(),
+            tuning: config::get_search_tuning(),
+            evaluation_weights: config::get_evaluation_weights(),
+            piece_square_tables: config::get_piece_square_tables(),
             node_counter: NodeCounter::new(),
             move_orderer,
             max_depth,
+            last_info: String::new(),
+            excluded_root_moves: Vec::new(),
+            multi_pv_results: Vec::new(),
+            cutoff_counts: Vec::new(),
         }
     }
+
+    /// The static evaluation of the current position using the weights and tables read when the
+    /// search was created
+    pub(crate) fn score_position(&self) -> i32 {
+        evaluation::score_position_using(
+            self.position,
+            &self.evaluation_weights,
+            &self.piece_square_tables,
+        )
+    }
+
     fn stop_search_requested(&self) -> bool {
         self.stop_flag.load(Ordering::Relaxed)
     }
@@ -120,9 +234,15 @@ impl<'a> Search<'a> {
         self.stop_flag.store(true, Ordering::Relaxed);
     }
 
+    fn used_allocated_nodes(&self) -> bool {
+        self.node_counter.node_count() > self.search_params.max_nodes
+    }
+
+    // depth limited searches never read the clock so that they are reproducible
     fn used_allocated_move_time(&self) -> bool {
-        self.node_counter.stats().elapsed_time.as_millis()
-            > self.search_params.allocated_time_millis as u128
+        self.search_params.is_time_limited()
+            && self.node_counter.stats().elapsed_time.as_millis()
+                > self.search_params.allocated_time_millis as u128
     }
 }
 
@@ -147,18 +267,64 @@ impl RepetitionKey {
 
 impl Search<'_> {
     pub fn go(&mut self) -> SearchResults {
+        if self.search_params.max_depth == 0 {
+            let search_results = self.evaluation_only_search_results();
+            self.send_bound_info(&search_results, BoundType::Exact);
+            return search_results;
+        }
+        self.transposition_table.new_search();
+        if let Some(search_results) = self.known_mate_from_t_table() {
+            info!(
+                "Playing the mating move {} already held in the transposition table",
+                search_results.pv[0]
+            );
+            self.send_bound_info(&search_results, BoundType::Exact);
+            return search_results;
+        }
         let mut search_results: Option<SearchResults> = None;
-        for iteration_max_depth in 1..=self.search_params.max_depth {
+        // the principal variation can't be longer than the maximum search depth
+        let max_depth = self.search_params.max_depth.min(MAXIMUM_SEARCH_DEPTH as u8);
+        for iteration_max_depth in 1..=max_depth {
             self.move_orderer._clear();
             self.max_depth = iteration_max_depth;
-            let mut pv: ArrayVec<Move, MAXIMUM_SEARCH_DEPTH> = ArrayVec::new();
-            let score = self.negamax(
-                &mut ArrayVec::new(),
-                &mut pv,
-                iteration_max_depth,
-                -MAXIMUM_SCORE,
-                MAXIMUM_SCORE,
-            );
+            let (mut alpha, mut beta) =
+                self.aspiration_window_bounds(iteration_max_depth, search_results.as_ref());
+            let (score, pv) = loop {
+                let mut pv: ArrayVec<Move, MAXIMUM_SEARCH_DEPTH> = ArrayVec::new();
+                let score =
+                    self.negamax(&mut ArrayVec::new(), &mut pv, iteration_max_depth, alpha, beta);
+                if self.stop_search_requested() {
+                    break (score, pv);
+                }
+                match Search::root_bound_type(score, alpha, beta) {
+                    BoundType::UpperBound => {
+                        // the line found after a fail low can't be trusted so report the move from
+                        // the last completed iteration until the re-search finds a better one
+                        if let Some(previous_search_results) = &search_results {
+                            self.send_bound_info(
+                                &SearchResults {
+                                    score,
+                                    depth: iteration_max_depth,
+                                    ..previous_search_results.clone()
+                                },
+                                BoundType::UpperBound,
+                            );
+                        }
+                        alpha = -MAXIMUM_SCORE;
+                    }
+                    BoundType::LowerBound => {
+                        let fail_high_search_results = self.create_search_results(
+                            self.position,
+                            score,
+                            iteration_max_depth,
+                            &pv,
+                        );
+                        self.send_bound_info(&fail_high_search_results, BoundType::LowerBound);
+                        beta = MAXIMUM_SCORE;
+                    }
+                    BoundType::Exact => break (score, pv),
+                }
+            };
             if !self.stop_search_requested() {
                 let iteration_search_results =
                     self.create_search_results(self.position, score, iteration_max_depth, &pv);
@@ -168,29 +334,245 @@ impl Search<'_> {
                     iteration_max_depth,
                     iteration_search_results.clone()
                 );
-                uci_util::send_to_gui(
-                    Search::format_uci_info(
-                        self.position,
-                        &iteration_search_results,
-                        &self.node_counter.stats(),
-                    )
-                    .as_str(),
+                self.send_bound_info(
+                    &iteration_search_results,
+                    Search::root_bound_type(score, alpha, beta),
                 );
+                self.search_other_lines(&iteration_search_results);
                 if Search::is_mating_score(iteration_search_results.score) {
                     info!(
                         "Found checkmate at depth {} with score {} - stopping search",
                         iteration_max_depth, iteration_search_results.score
                     );
-                    if Self::is_mating_score(iteration_search_results.score) {
-                        self.transposition_table.clear();
-                    }
                     break;
                 }
-            } else if search_results.is_some() {
+            } else {
+                if let Some(partial_search_results) = self.partial_iteration_search_results(
+                    score,
+                    alpha,
+                    beta,
+                    &pv,
+                    iteration_max_depth,
+                ) {
+                    search_results = Some(partial_search_results);
+                }
+                break;
+            }
+        }
+        let search_results = search_results.unwrap_or_else(|| self.fallback_search_results());
+        if self.stop_search_requested() {
+            // the last info sent may describe the aborted iteration rather than the move played
+            self.send_bound_info(&search_results, BoundType::Exact);
+        }
+        self.send_quiescence_node_info();
+        search_results
+    }
+
+    /// The best lines found by the last completed iteration, best first. The first is the line
+    /// returned by go and there are up to multi_pv of them.
+    #[allow(dead_code)]
+    pub fn multi_pv_results(&self) -> &[SearchResults] {
+        &self.multi_pv_results
+    }
+
+    /// Finds the lines after the best one by searching the root moves again without the first
+    /// moves of the lines already found. The lines are only kept if they are all found before the
+    /// search is stopped.
+    fn search_other_lines(&mut self, best_line: &SearchResults) {
+        let depth = best_line.depth;
+        let line_count = self.multi_pv.min(self.root_move_count()).max(1);
+        let mut lines = vec![best_line.clone()];
+        while lines.len() < line_count {
+            self.excluded_root_moves.push(lines.last().unwrap().pv[0]);
+            let mut pv: ArrayVec<Move, MAXIMUM_SEARCH_DEPTH> = ArrayVec::new();
+            let score =
+                self.negamax(&mut ArrayVec::new(), &mut pv, depth, -MAXIMUM_SCORE, MAXIMUM_SCORE);
+            if self.stop_search_requested() || pv.is_empty() {
                 break;
             }
+            let line = self.create_search_results(self.position, score, depth, &pv);
+            self.send_line_info(&line, lines.len() + 1);
+            lines.push(line);
+        }
+        self.excluded_root_moves.clear();
+        if lines.len() == line_count {
+            self.multi_pv_results = lines;
+        }
+    }
+
+    /// The number of legal root moves that may be searched
+    fn root_move_count(&self) -> usize {
+        move_gen::generate_moves(self.position)
+            .into_iter()
+            .filter(|mv| self.is_search_move(mv) && move_gen::is_legal(self.position, mv))
+            .count()
+    }
+
+    fn is_search_move(&self, mv: &Move) -> bool {
+        self.search_params.search_moves.is_empty() || self.search_params.search_moves.contains(mv)
+    }
+
+    /// The root moves are restricted by the go searchmoves and by the lines already found
+    fn is_restricted_root(&self, ply: u8) -> bool {
+        ply == 0
+            && !(self.search_params.search_moves.is_empty() && self.excluded_root_moves.is_empty())
+    }
+
+    /// The share of the nodes searched by the quiescence search helps when tuning its pruning
+    fn send_quiescence_node_info(&self) {
+        let stats = self.node_counter.stats();
+        uci_util::send_to_gui(&format!(
+            "info string quiescence nodes {} of {} ({}%)",
+            stats.quiescence_node_count,
+            stats.node_count,
+            stats.quiescence_node_percentage()
+        ));
+    }
+
+    /// A mate for the side to move found by an earlier search can be played without searching again
+    fn known_mate_from_t_table(&self) -> Option<SearchResults> {
+        if self.is_restricted_root(0) || self.multi_pv > 1 {
+            return None;
+        }
+        let entry = self.probe_t_table(0)?;
+        let best_move = entry.best_move?;
+        if entry.bound_type == BoundType::Exact
+            && entry.score > 0
+            && Search::is_mating_score(entry.score)
+        {
+            Some(self.create_search_results(self.position, entry.score, entry.depth, &[best_move]))
+        } else {
+            None
+        }
+    }
+
+    /// Probes the transposition table for the current position. An entry whose best move is not
+    /// legal here must belong to a different position with the same hash code, so it is ignored
+    /// rather than being trusted for a cutoff or for move ordering. The score of the entry returned
+    /// counts mates from the root as the scores of the search do.
+    fn probe_t_table(&self, ply: u8) -> Option<TTEntry> {
+        let entry = self.transposition_table.probe(self.position.hash_code())?;
+        match entry.best_move {
+            Some(best_move) if !move_gen::is_legal(self.position, &best_move) => {
+                debug!("Ignoring transposition table entry with illegal best move {best_move}");
+                None
+            }
+            _ => Some(TTEntry { score: Search::score_from_t_table(entry.score, ply), ..entry }),
         }
-        search_results.unwrap()
+    }
+
+    /// Mate scores count the plies from the root, but the table is shared by searches from
+    /// different roots, so it holds them counting the plies from the node instead
+    fn score_to_t_table(score: i32, ply: u8) -> i32 {
+        match Search::mate_in_moves(score) {
+            Some(_) if score > 0 => score + ply as i32,
+            Some(_) => score - ply as i32,
+            None => score,
+        }
+    }
+
+    fn score_from_t_table(score: i32, ply: u8) -> i32 {
+        match Search::mate_in_moves(score) {
+            Some(_) if score > 0 => score - ply as i32,
+            Some(_) => score + ply as i32,
+            None => score,
+        }
+    }
+
+    /// Searches a narrow window around the score of the previous iteration once the score is stable
+    fn aspiration_window_bounds(
+        &self,
+        depth: u8,
+        previous_search_results: Option<&SearchResults>,
+    ) -> (i32, i32) {
+        match previous_search_results {
+            Some(previous_search_results)
+                if depth >= ASPIRATION_WINDOW_MIN_DEPTH
+                    && !Search::is_mating_score(previous_search_results.score) =>
+            {
+                (
+                    (previous_search_results.score - self.tuning.aspiration_window)
+                        .max(-MAXIMUM_SCORE),
+                    (previous_search_results.score + self.tuning.aspiration_window)
+                        .min(MAXIMUM_SCORE),
+                )
+            }
+            _ => (-MAXIMUM_SCORE, MAXIMUM_SCORE),
+        }
+    }
+
+    fn send_bound_info(&mut self, search_results: &SearchResults, bound_type: BoundType) {
+        self.last_info = Search::format_uci_info(
+            self.position,
+            search_results,
+            &self.node_counter.stats(),
+            bound_type,
+            (self.multi_pv > 1).then_some(1),
+        );
+        uci_util::send_to_gui(&self.last_info);
+    }
+
+    fn send_line_info(&self, search_results: &SearchResults, multi_pv_index: usize) {
+        uci_util::send_to_gui(&Search::format_uci_info(
+            self.position,
+            search_results,
+            &self.node_counter.stats(),
+            BoundType::Exact,
+            Some(multi_pv_index),
+        ));
+    }
+
+    /// The root moves searched before the search was stopped have final scores, and the previous
+    /// best move is searched first, so a move that beat alpha is the best move found so far even
+    /// though the iteration is incomplete
+    fn partial_iteration_search_results(
+        &self,
+        score: i32,
+        alpha: i32,
+        beta: i32,
+        pv: &[Move],
+        iteration_max_depth: u8,
+    ) -> Option<SearchResults> {
+        if pv.is_empty() || Search::root_bound_type(score, alpha, beta) == BoundType::UpperBound {
+            return None;
+        }
+        let search_results =
+            self.create_search_results(self.position, score, iteration_max_depth - 1, pv);
+        info!(
+            "Search stopped during the depth {} iteration - playing the best move found so far {}",
+            iteration_max_depth, search_results.pv[0]
+        );
+        Some(search_results)
+    }
+
+    /// A depth 0 search evaluates the position without searching any moves
+    fn evaluation_only_search_results(&self) -> SearchResults {
+        let score = evaluation::evaluate(self.position, 0, &self.repetition_key_stack);
+        self.create_search_results(self.position, score, 0, &[])
+    }
+
+    /// Used when the search is stopped before the first iteration completes
+    fn fallback_search_results(&self) -> SearchResults {
+        let pv: Vec<Move> = self
+            .search_params
+            .search_moves
+            .first()
+            .copied()
+            .or_else(|| move_gen::get_first_legal_move(self.position))
+            .into_iter()
+            .collect();
+        let score = if pv.is_empty() && move_gen::is_check(self.position) {
+            -MAXIMUM_SCORE
+        } else if pv.is_empty() {
+            DRAW_SCORE
+        } else {
+            self.score_position()
+        };
+        info!(
+            "Search stopped before completing an iteration - using fallback move {:?}",
+            pv.first()
+        );
+        self.create_search_results(self.position, score, 0, &pv)
     }
 
     fn negamax(
@@ -202,28 +584,31 @@ impl Search<'_> {
         mut beta: i32,
     ) -> i32 {
         self.node_counter.increment();
-        let ply = self.max_depth - depth;
+        // not max_depth - depth because reduced moves skip depths
+        let ply = current_line.len() as u8;
         let alpha_original = alpha;
         let beta_original = beta;
 
-        if self.used_allocated_move_time() {
+        if self.used_allocated_move_time() || self.used_allocated_nodes() {
             self.request_stop_search();
             return 0;
         }
 
-        if self.position.is_drawn_by_fifty_moves_rule() || self.position_occurrence_count() >= 3 {
-            return DRAW_SCORE;
-        } else if evaluation::has_insufficient_material(self.position) {
-            self.insert_into_t_table(depth, alpha_original, beta_original, 0, None);
+        if self.is_drawn_by_fifty_moves_rule()
+            || self.is_repetition_draw(ply)
+            || evaluation::has_insufficient_material(self.position)
+        {
             return DRAW_SCORE;
         }
 
-        let t_table_entry = self.transposition_table.probe(self.position.hash_code());
-        if let Some(ref entry) = t_table_entry {
+        let t_table_entry = self.probe_t_table(ply);
+        // the root entry may have been stored with a different game history so its score could be
+        // a repetition draw that doesn't apply now (or vice versa) - it is only used for its move
+        if let Some(ref entry) = t_table_entry.filter(|_| ply > 0) {
             if entry.depth >= depth {
                 let position_occurrence_count = self.position_occurrence_count();
-                let skip_tt_for_repetition = position_occurrence_count >= 2
-                    && evaluation::score_position(self.position) > -100;
+                let skip_tt_for_repetition =
+                    position_occurrence_count >= 2 && self.score_position() > -100;
 
                 if !skip_tt_for_repetition && entry.depth >= depth {
                     match entry.bound_type {
@@ -255,19 +640,34 @@ impl Search<'_> {
         if depth == 0 {
             let score = {
                 if move_gen::has_legal_move(self.position) {
-                    self.quiescence_search(ply + 1, alpha, beta)
+                    if self.use_quiescence {
+                        self.quiescence_search(ply, alpha, beta)
+                    } else {
+                        self.score_position()
+                    }
                 } else if move_gen::is_check(self.position) {
                     -MAXIMUM_SCORE + ply as i32
                 } else {
                     DRAW_SCORE
                 }
             };
-            if score != DRAW_SCORE {
-                self.insert_into_t_table(depth, alpha_original, beta_original, score, None);
-            }
+            self.insert_into_t_table(ply, depth, alpha_original, beta_original, score, None);
             score
         } else {
-            let mut moves = move_gen::generate_moves(self.position);
+            if self.can_prune_by_reverse_futility(ply, depth, beta) {
+                return beta;
+            }
+            let mut moves: ArrayVec<Move, MOVE_LIST_LENGTH> = ArrayVec::new();
+            move_gen::generate_moves_into(self.position, &mut moves);
+            let is_restricted_root = self.is_restricted_root(ply);
+            if is_restricted_root {
+                moves
+                    .retain(|mv| self.is_search_move(mv) && !self.excluded_root_moves.contains(mv));
+            } else if self.prune_under_promotions {
+                // a queen promotion to the same square is always legal too so these are never the
+                // only legal moves, and they are almost never better than it
+                moves.retain(|mv| !mv.is_rook_or_bishop_promotion());
+            }
             let hash_move = t_table_entry.and_then(|entry| entry.best_move);
             let last_move = &current_line.last().cloned();
             move_ordering::order_moves(
@@ -280,17 +680,44 @@ impl Search<'_> {
             );
             let mut best_score = -MAXIMUM_SCORE;
             let mut best_move = None;
+            let mut move_index = 0;
+            let is_check = move_gen::is_check(self.position);
             for mv in moves {
+                let reduction = if hash_move == Some(mv) {
+                    0
+                } else {
+                    self.losing_capture_reduction(&mv, depth, is_check)
+                };
                 if let Some(undo_move_info) = self.position.make_move(&mv) {
                     self.repetition_key_stack.push(RepetitionKey::new(self.position));
                     if self.search_tree_position_occurance_count() <= 3 {
                         let mut child_pv: ArrayVec<Move, MAXIMUM_SEARCH_DEPTH> = ArrayVec::new();
                         current_line.push(mv);
-                        let next_score =
-                            -self.negamax(current_line, &mut child_pv, depth - 1, -beta, -alpha);
+                        let mut next_score = -self.negamax(
+                            current_line,
+                            &mut child_pv,
+                            depth - 1 - reduction,
+                            -beta,
+                            -alpha,
+                        );
+                        if reduction > 0 && next_score > alpha {
+                            // the capture may not lose after all so it gets a full depth search
+                            child_pv.clear();
+                            next_score = -self.negamax(
+                                current_line,
+                                &mut child_pv,
+                                depth - 1,
+                                -beta,
+                                -alpha,
+                            );
+                        }
                         self.repetition_key_stack.pop();
                         current_line.pop();
                         self.position.unmake_move(&undo_move_info);
+                        if ply == 0 && self.stop_search_requested() {
+                            // the root move's search didn't finish so its score means nothing
+                            break;
+                        }
                         if next_score > best_score || best_move.is_none() {
                             best_score = next_score;
                             best_move = Some(mv);
@@ -301,8 +728,10 @@ impl Search<'_> {
                         alpha = alpha.max(next_score);
                         if alpha >= beta {
                             self.move_orderer.add_killer_move(mv, ply);
+                            self.count_cutoff(move_index);
                             break; // beta cutoff
                         }
+                        move_index += 1;
                         if depth >= 2 && self.stop_search_requested() {
                             break;
                         }
@@ -319,14 +748,110 @@ impl Search<'_> {
                     DRAW_SCORE
                 };
             }
-            self.insert_into_t_table(depth, alpha_original, beta_original, best_score, best_move);
+            // the best of the restricted root moves may not be the best move in the position
+            if !is_restricted_root {
+                self.insert_into_t_table(
+                    ply,
+                    depth,
+                    alpha_original,
+                    beta_original,
+                    best_score,
+                    best_move,
+                );
+            }
             best_score
         }
     }
 
-    fn insert_into_t_table(&self, depth: u8, alpha: i32, beta: i32, score: i32, mov: Option<Move>) {
-        if !self.stop_search_requested() {
-            self.transposition_table.insert(self.position, depth, alpha, beta, score, mov);
+    fn count_cutoff(&mut self, move_index: usize) {
+        if self.cutoff_counts.len() <= move_index {
+            self.cutoff_counts.resize(move_index + 1, 0);
+        }
+        self.cutoff_counts[move_index] += 1;
+    }
+
+    /// The number of beta cutoffs caused by the move at each index in the ordered moves searched
+    /// by a node, which measures how well the moves are ordered
+    pub fn cutoff_counts(&self) -> &[usize] {
+        &self.cutoff_counts
+    }
+
+    /// Describes the share of the beta cutoffs caused by each of the first few moves searched
+    pub fn format_cutoff_statistics(cutoff_counts: &[usize]) -> String {
+        let total: usize = cutoff_counts.iter().sum();
+        if total == 0 {
+            return "info string no beta cutoffs".to_string();
+        }
+        let shown_indexes = cutoff_counts.len().min(CUTOFF_STATISTICS_MOVE_COUNT);
+        let percentage = |count: usize| count * 100 / total;
+        let mut statistics: Vec<String> = cutoff_counts[..shown_indexes]
+            .iter()
+            .enumerate()
+            .map(|(index, &count)| format!("{}:{count} ({}%)", index + 1, percentage(count)))
+            .collect();
+        let later_count: usize = cutoff_counts[shown_indexes..].iter().sum();
+        if later_count > 0 {
+            statistics.push(format!(
+                "{}+:{later_count} ({}%)",
+                shown_indexes + 1,
+                percentage(later_count)
+            ));
+        }
+        format!("info string beta cutoffs {total} by move {}", statistics.join(" "))
+    }
+
+    /// A checkmate delivered by the move that reaches the fifty move limit is still a checkmate
+    fn is_drawn_by_fifty_moves_rule(&self) -> bool {
+        self.position.is_drawn_by_fifty_moves_rule()
+            && (!move_gen::is_check(self.position) || move_gen::has_legal_move(self.position))
+    }
+
+    /// Prunes quiet nodes near the leaves whose static evaluation is already well above beta
+    fn can_prune_by_reverse_futility(&self, ply: u8, depth: u8, beta: i32) -> bool {
+        self.reverse_futility_pruning
+            && ply > 0
+            && depth as i32 <= self.tuning.reverse_futility_max_depth
+            && !Search::is_mating_score(beta)
+            && !move_gen::is_check(self.position)
+            && self.score_position() - self.tuning.reverse_futility_margin * depth as i32 >= beta
+    }
+
+    /// Captures that lose material by static exchange evaluation are searched less deeply
+    fn losing_capture_reduction(&self, mv: &Move, depth: u8, is_check: bool) -> u8 {
+        let is_capture = matches!(mv, Move::Basic { base_move } if base_move.capture);
+        if self.reduce_losing_captures
+            && depth > LOSING_CAPTURE_REDUCTION + 1
+            && is_capture
+            && !is_check
+            && self.static_exchange_evaluation(mv) < 0
+        {
+            LOSING_CAPTURE_REDUCTION
+        } else {
+            0
+        }
+    }
+
+    /// A draw score may come from a repetition of the positions leading to this one, so it could be
+    /// wrong when the position is reached another way and isn't stored. The bounds are converted
+    /// with the score so that the bound type is unchanged.
+    fn insert_into_t_table(
+        &self,
+        ply: u8,
+        depth: u8,
+        alpha: i32,
+        beta: i32,
+        score: i32,
+        mov: Option<Move>,
+    ) {
+        if !self.stop_search_requested() && score != DRAW_SCORE {
+            self.transposition_table.insert(
+                self.position,
+                depth,
+                Search::score_to_t_table(alpha, ply),
+                Search::score_to_t_table(beta, ply),
+                Search::score_to_t_table(score, ply),
+                mov,
+            );
         }
     }
 
@@ -342,67 +867,106 @@ impl Search<'_> {
             Search::extend_principal_variation(
                 self.transposition_table,
                 position,
+                score,
                 &pv_with_positions,
                 max_depth,
             )
         } else {
             pv_with_positions
         };
-        let last_position = final_pv.last().map_or(position, |(p, _)| p);
-        let pv_repetition_keys: Vec<RepetitionKey> =
-            final_pv.iter().map(|(p, _)| RepetitionKey::new(p)).collect();
-        let repetition_keys = [self.repetition_key_stack.clone(), pv_repetition_keys].concat();
-        let game_status = evaluation::get_game_status(last_position, &repetition_keys);
+        let (final_pv, game_status) = self.end_principal_variation_at_draw(position, final_pv);
         let (_, moves): (Vec<Position>, Vec<Move>) = final_pv.into_iter().unzip();
-        let is_draw_50 = game_status == DrawnByThreefoldRepetition; // todo
-        let score = if is_draw_50 { 0 } else { score };
-        SearchResults { position: *position, score, depth: max_depth, pv: moves, game_status }
+        let score = if game_status.is_draw() { DRAW_SCORE } else { score };
+        let stats = self.node_counter.stats();
+        SearchResults {
+            position: *position,
+            score,
+            depth: max_depth,
+            pv: moves,
+            game_status,
+            node_count: stats.node_count,
+            elapsed_millis: stats.elapsed_time.as_millis() as usize,
+        }
     }
 
+    /// Cuts the principal variation at the first position that is drawn by rule so that the
+    /// reported game status names the rule that ended the line
+    fn end_principal_variation_at_draw(
+        &self,
+        position: &Position,
+        mut pv: Vec<(Position, Move)>,
+    ) -> (Vec<(Position, Move)>, GameStatus) {
+        let mut repetition_keys = self.repetition_key_stack.clone();
+        for (index, (pv_position, _)) in pv.iter().enumerate() {
+            repetition_keys.push(RepetitionKey::new(pv_position));
+            if let Some(draw_status) = evaluation::is_draw_now(pv_position, &repetition_keys) {
+                pv.truncate(index + 1);
+                return (pv, draw_status);
+            }
+        }
+        let last_position = pv.last().map_or(position, |(p, _)| p);
+        let game_status = evaluation::get_game_status(last_position, &repetition_keys);
+        (pv, game_status)
+    }
+
+    /// Lengthens the principal variation using exact transposition table entries. Each move is
+    /// checked to be legal, and the line stops at an entry whose score doesn't match the root
+    /// score so that the reported line always explains the reported score
     fn extend_principal_variation(
         transposition_table: &TranspositionTable,
         position: &Position,
+        score: i32,
         current_pv: &[(Position, Move)],
         max_depth: u8,
     ) -> Vec<(Position, Move)> {
         let mut result_pv = current_pv.to_owned();
-        let last_position = current_pv.last().map_or(position, |(p, _)| p);
-        let mut current_position = *last_position;
+        let mut current_position = *current_pv.last().map_or(position, |(p, _)| p);
 
         let mut visited_positions = HashSet::new();
         let mut num_missing_moves = max_depth as usize - current_pv.len();
 
         while let Some(entry) = transposition_table.probe(current_position.hash_code()) {
+            let expected_score = if result_pv.len().is_multiple_of(2) { score } else { -score };
             if num_missing_moves == 0
                 || (entry.depth as usize) < num_missing_moves
                 || entry.bound_type != BoundType::Exact
+                || (!Search::is_mating_score(score) && entry.score != expected_score)
             {
                 break;
             }
-
-            if let Some(best_mv) = entry.best_move {
-                if current_position.make_move(&best_mv).is_some() {
-                    if visited_positions.contains(&current_position.hash_code()) {
-                        break;
-                    }
-                    result_pv.push((*last_position, best_mv));
-                    num_missing_moves -= 1;
-                    visited_positions.insert(current_position.hash_code());
-                } else {
-                    break;
-                }
-            } else {
+            let Some(best_mv) = entry
+                .best_move
+                .filter(|mv| move_gen::generate_moves(&current_position).contains(mv))
+            else {
+                break;
+            };
+            if current_position.make_move(&best_mv).is_none()
+                || !visited_positions.insert(current_position.hash_code())
+            {
                 break;
             }
+            result_pv.push((current_position, best_mv));
+            num_missing_moves -= 1;
         }
         debug!("PV extended from length {} to length {}", current_pv.len(), result_pv.len());
         result_pv
     }
 
+    /// A score on the edge of the full search window is a real (mate) score rather than a bound
+    fn root_bound_type(score: i32, alpha: i32, beta: i32) -> BoundType {
+        match BoundType::from_score(score, alpha, beta) {
+            BoundType::UpperBound if alpha <= -MAXIMUM_SCORE => BoundType::Exact,
+            BoundType::LowerBound if beta >= MAXIMUM_SCORE => BoundType::Exact,
+            bound_type => bound_type,
+        }
+    }
+
     fn format_uci_info(
         position: &Position,
         search_results: &SearchResults,
         node_counter_stats: &NodeCountStats,
+        bound_type: BoundType,
+        multi_pv_index: Option<usize>,
     ) -> String {
         let moves_string = search_results
             .pv
@@ -420,10 +984,20 @@ impl Search<'_> {
             );
         }
 
+        let bound_suffix = match bound_type {
+            BoundType::Exact => "",
+            BoundType::LowerBound => " lowerbound",
+            BoundType::UpperBound => " upperbound",
+        };
+
+        let multi_pv = multi_pv_index.map_or(String::new(), |index| format!(" multipv {index}"));
+
         format!(
-            "info depth {} score cp {} time {} nodes {} nps {} pv {}",
+            "info depth {}{} score {}{} time {} nodes {} nps {} pv {}",
             search_results.depth,
-            search_results.score,
+            multi_pv,
+            Search::format_uci_score(search_results.score),
+            bound_suffix,
             node_counter_stats.elapsed_time.as_millis(),
             node_counter_stats.node_count,
             node_counter_stats.nodes_per_second,
@@ -431,12 +1005,35 @@ impl Search<'_> {
         )
     }
 
+    fn format_uci_score(score: i32) -> String {
+        match Search::mate_in_moves(score) {
+            Some(moves_to_mate) => format!("mate {moves_to_mate}"),
+            None => format!("cp {score}"),
+        }
+    }
+
     pub fn search_tree_position_occurance_count(&self) -> usize {
         Search::position_occurrence_count_static(
             &self.repetition_key_stack[self.number_of_game_positions..],
         )
     }
 
+    /// A threefold repetition counting the game history is a draw. A position repeated within the
+    /// search is also scored as a draw straight away because the side that chose to repeat it can
+    /// repeat it again, which finds perpetual checks sooner.
+    fn is_repetition_draw(&self, ply: u8) -> bool {
+        self.position_occurrence_count() >= 3
+            || (ply > 0 && self.position_occurrence_count_since_root() >= 2)
+    }
+
+    /// The number of times the current position has occurred since the root position, counting the
+    /// root itself
+    fn position_occurrence_count_since_root(&self) -> usize {
+        Search::position_occurrence_count_static(
+            &self.repetition_key_stack[self.number_of_game_positions.saturating_sub(1)..],
+        )
+    }
+
     pub fn position_occurrence_count(&self) -> usize {
         Search::position_occurrence_count_static(&self.repetition_key_stack)
     }
@@ -453,12 +1050,38 @@ impl Search<'_> {
     }
 
     pub fn is_mating_score(score: i32) -> bool {
-        score.abs() >= MAXIMUM_SCORE - MAXIMUM_SEARCH_DEPTH as i32
+        Search::mate_in_moves(score).is_some()
+    }
+
+    /// Converts an internal mate score, MAXIMUM_SCORE less the number of plies to the mate, into
+    /// the number of moves reported to the GUI. The result is positive when the side to move is
+    /// mating and negative when it is being mated, so mate in 1 ply is 1 and mated in 2 plies is -1.
+    /// Returns None for scores that aren't mate scores.
+    pub fn mate_in_moves(score: i32) -> Option<i32> {
+        let plies_to_mate = MAXIMUM_SCORE - score.abs();
+        if plies_to_mate > MAXIMUM_SEARCH_DEPTH as i32 {
+            None
+        } else if score > 0 {
+            Some((plies_to_mate + 1) / 2)
+        } else {
+            Some(-(plies_to_mate / 2))
+        }
+    }
+
+    /// The inverse of mate_in_moves
+    #[allow(dead_code)]
+    pub fn mate_score(moves_to_mate: i32) -> i32 {
+        if moves_to_mate > 0 {
+            MAXIMUM_SCORE - (2 * moves_to_mate - 1)
+        } else {
+            -MAXIMUM_SCORE - 2 * moves_to_mate
+        }
     }
 }
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::r#move::BaseMove;
     use crate::uci::config;
 
     fn setup() {
@@ -500,9 +1123,9 @@ mod tests {
         let fen = "4k3/8/1P1Q4/R7/2n5/4N3/1B6/4K3 b - - 0 1";
         let mut position: Position = Position::from(fen);
         let search_results = create_search(&mut position, &TranspositionTable::new(1), 1).go();
-        assert_eq!(search_results.score, -1020);
+        assert_eq!(search_results.score, -1021);
         let pv = move_formatter::LONG_FORMATTER
-            .format_move_list(&mut position, &search_results.pv)
+            .format_move_list(&position, &search_results.pv)
             .unwrap()
             .join(", ");
         assert_eq!(pv, "♞c4xd6");
@@ -522,6 +1145,7 @@ mod tests {
                 depth: 1,
                 pv: vec![],
                 game_status: GameStatus::Checkmate,
+                ..Default::default()
             }
         );
     }
@@ -540,6 +1164,7 @@ mod tests {
                 depth: 1,
                 pv: vec![],
                 game_status: GameStatus::Stalemate,
+                ..Default::default()
             }
         );
     }
@@ -559,6 +1184,7 @@ mod tests {
                 depth: 1,
                 pv: vec![],
                 game_status: GameStatus::Checkmate,
+                ..Default::default()
             },
         );
     }
@@ -578,6 +1204,7 @@ mod tests {
                 depth: 1,
                 pv: vec![],
                 game_status: GameStatus::Checkmate,
+                ..Default::default()
             },
         );
     }
@@ -600,6 +1227,7 @@ mod tests {
                 depth: 3,
                 pv: vec![],
                 game_status: GameStatus::Checkmate,
+                ..Default::default()
             },
         );
     }
@@ -622,6 +1250,7 @@ mod tests {
                 depth: 5,
                 pv: vec![],
                 game_status: GameStatus::Checkmate,
+                ..Default::default()
             },
         );
     }
@@ -644,6 +1273,7 @@ mod tests {
                 depth: 7,
                 pv: vec![],
                 game_status: GameStatus::Checkmate,
+                ..Default::default()
             },
         );
     }
@@ -656,7 +1286,7 @@ mod tests {
         let search_results = create_search(&mut position, &TranspositionTable::new(1), 5).go();
         assert_eq!(
             move_formatter::format_move_list(&position, &search_results),
-            "♗f1-c4,♚e4-e5,♕d1-d5+,♚e5-f6,♕d5-g5#"
+            "♗f1-c4,♚e4-f5,♕d1-f3+,♚f5-e5,♕f3-f4#"
         );
         test_eq(
             &search_results,
@@ -666,6 +1296,7 @@ mod tests {
                 depth: 5,
                 pv: vec![],
                 game_status: GameStatus::Checkmate,
+                ..Default::default()
             },
         );
     }
@@ -703,7 +1334,7 @@ mod tests {
         let fen = "4k3/8/R7/7n/7r/8/8/4K3 b - - 98 76";
         let original_position: Position = Position::from(fen);
 
-        let mut in_progress_position: Position = original_position.clone();
+        let mut in_progress_position: Position = original_position;
         let in_progress_search_results =
             create_search(&mut in_progress_position, &TranspositionTable::new(1), 1).go();
         assert_eq!(in_progress_search_results.pv_moves_as_string(), "h5-f4".to_string());
@@ -711,14 +1342,15 @@ mod tests {
             &in_progress_search_results,
             &SearchResults {
                 position: in_progress_position,
-                score: 316,
+                score: 318,
                 depth: 1,
                 pv: vec![],
                 game_status: GameStatus::InProgress,
+                ..Default::default()
             },
         );
 
-        let mut drawn_position: Position = original_position.clone();
+        let mut drawn_position: Position = original_position;
         drawn_position.make_raw_move(&r#move::RawMove::new(sq!("h5"), sq!("f4"), None)).unwrap();
         let drawn_position_search_results =
             create_search(&mut drawn_position, &TranspositionTable::new(1), 1).go();
@@ -731,6 +1363,7 @@ mod tests {
                 depth: 1,
                 pv: vec![],
                 game_status: GameStatus::DrawnByFiftyMoveRule,
+                ..Default::default()
             },
         );
     }
@@ -752,20 +1385,22 @@ mod tests {
                 depth: 1,
                 pv: vec![],
                 game_status: GameStatus::DrawnByThreefoldRepetition,
+                ..Default::default()
             },
         );
 
         let win_search_results =
             uci_util::run_uci_position(go_for_win_uci_position_str, go_options_str);
-        assert_eq!(win_search_results.pv_moves_as_string(), "e7-e6".to_string());
+        assert_eq!(win_search_results.pv_moves_as_string(), "b8-c6".to_string());
         test_eq(
             &win_search_results,
             &SearchResults {
                 position: win_search_results.position,
-                score: 976,
+                score: 991,
                 depth: 1,
                 pv: vec![],
                 game_status: GameStatus::InProgress,
+                ..Default::default()
             },
         );
     }
@@ -884,10 +1519,11 @@ mod tests {
             &drawn_search_results,
             &SearchResults {
                 position: drawn_search_results.position,
-                score: -581,
+                score: -584,
                 depth: 2,
                 pv: vec![],
                 game_status: GameStatus::InProgress,
+                ..Default::default()
             },
         );
     }
@@ -906,10 +1542,309 @@ mod tests {
                 depth: 4,
                 pv: vec![],
                 game_status: GameStatus::DrawnByThreefoldRepetition,
+                ..Default::default()
             },
         );
     }
 
+    #[test]
+    fn test_perpetual_check_is_recognised_after_one_repetition() {
+        setup();
+        let fen = "r1b5/ppp2Bpk/3p2Np/4p3/4P2q/3P1n1P/PPP2bP1/R1B4K w - - 10 1";
+        for depth in 4..=6 {
+            let search_results = uci_util::run_uci_position(
+                &format!("position fen {fen}"),
+                &format!("depth {depth}"),
+            );
+            assert_eq!(search_results.score, 0, "depth {depth}");
+            assert!(search_results.pv_moves_as_string().starts_with("g6-f8,h7-h8"));
+        }
+    }
+
+    const REPEATED_MOVE_ORDER: &str = "position fen rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 10 1 moves g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1";
+    const DIRECT_MOVE_ORDER: &str =
+        "position fen rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 10 1 moves g1f3 g8f6 f3g1";
+
+    #[test]
+    fn test_repetition_draw_in_t_table_not_reused_after_transposition() {
+        setup();
+        let transposition_table = TranspositionTable::new(10);
+        let drawn_search_results = uci_util::run_uci_position_using_t_table(
+            REPEATED_MOVE_ORDER,
+            "depth 3",
+            &transposition_table,
+        );
+        assert_eq!(drawn_search_results.score, 0);
+        assert_eq!(drawn_search_results.pv_moves_as_string(), "f6-g8");
+
+        let search_results = uci_util::run_uci_position_using_t_table(
+            DIRECT_MOVE_ORDER,
+            "depth 3",
+            &transposition_table,
+        );
+        assert_eq!(search_results.position.hash_code(), drawn_search_results.position.hash_code());
+        assert!(search_results.score < -500);
+        assert_eq!(search_results.game_status, GameStatus::InProgress);
+    }
+
+    #[test]
+    fn test_t_table_score_not_reused_when_repetition_draw_available() {
+        setup();
+        let transposition_table = TranspositionTable::new(10);
+        let search_results = uci_util::run_uci_position_using_t_table(
+            DIRECT_MOVE_ORDER,
+            "depth 3",
+            &transposition_table,
+        );
+        assert!(search_results.score < -500);
+
+        let drawn_search_results = uci_util::run_uci_position_using_t_table(
+            REPEATED_MOVE_ORDER,
+            "depth 3",
+            &transposition_table,
+        );
+        assert_eq!(drawn_search_results.score, 0);
+        assert_eq!(drawn_search_results.pv_moves_as_string(), "f6-g8");
+        assert_eq!(drawn_search_results.game_status, GameStatus::DrawnByThreefoldRepetition);
+    }
+
+    #[test]
+    fn test_checkmate_winner() {
+        setup();
+        // white has just been checkmated
+        let mut position: Position =
+            Position::from("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        assert_eq!(
+            position.result(&[RepetitionKey::new(&position)]).and_then(|result| result.winner()),
+            Some(PieceColor::Black)
+        );
+        let transposition_table = TranspositionTable::new(1);
+        let search_results = create_search(&mut position, &transposition_table, 3).go();
+        assert_eq!(search_results.game_status, GameStatus::Checkmate);
+        assert_eq!(search_results.winner(), Some(PieceColor::Black));
+
+        // white mates in one so the mated side is black at the end of the principal variation
+        let mut position: Position = Position::from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let transposition_table = TranspositionTable::new(1);
+        let search_results = create_search(&mut position, &transposition_table, 3).go();
+        assert_eq!(search_results.pv_moves_as_string(), "a1-a8");
+        assert_eq!(search_results.winner(), Some(PieceColor::White));
+
+        let mut position: Position = Position::new_game();
+        let search_results = create_search(&mut position, &transposition_table, 2).go();
+        assert_eq!(search_results.winner(), None);
+    }
+
+    #[test]
+    fn test_checkmate_on_the_fiftieth_move_is_not_a_draw() {
+        setup();
+        let mut position: Position = Position::from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80");
+        let transposition_table = TranspositionTable::new(1);
+        let search_results = create_search(&mut position, &transposition_table, 3).go();
+        assert_eq!(search_results.pv_moves_as_string(), "a1-a8");
+        assert_eq!(Search::mate_in_moves(search_results.score), Some(1));
+        assert_eq!(search_results.game_status, GameStatus::Checkmate);
+    }
+
+    #[test]
+    fn test_fifty_move_line_reports_fifty_move_rule() {
+        setup();
+        let mut position: Position = Position::from("8/8/8/4k3/8/8/8/R3K3 b - - 99 80");
+        let transposition_table = TranspositionTable::new(1);
+        let search_results = create_search(&mut position, &transposition_table, 3).go();
+        assert_eq!(search_results.score, 0);
+        assert_eq!(search_results.pv.len(), 1);
+        assert_eq!(search_results.game_status, GameStatus::DrawnByFiftyMoveRule);
+    }
+
+    /// Plays the search moves from the end of the uci position and returns whether each position
+    /// reached is scored as a repetition draw
+    fn repetition_draws_in_search(uci_position_str: &str, search_moves: &str) -> Vec<bool> {
+        let uci_position = uci_util::parse_position(uci_position_str).unwrap();
+        let mut position = uci_position.end_position;
+        let transposition_table = TranspositionTable::new(1);
+        let mut search = Search::new(
+            &mut position,
+            &transposition_table,
+            SearchParams::new_by_depth(1),
+            Arc::new(AtomicBool::new(false)),
+            uci_position.repetition_keys.clone(),
+            MoveOrderer::new(),
+            1,
+        );
+        let moves =
+            util::create_move_list(&uci_position.end_position, search_moves.to_string()).unwrap();
+        util::replay_moves(&uci_position.end_position, &moves)
+            .unwrap()
+            .iter()
+            .enumerate()
+            .map(|(index, (next_position, _))| {
+                search.repetition_key_stack.push(RepetitionKey::new(next_position));
+                search.is_repetition_draw(index as u8 + 1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_position_seen_once_in_the_game_is_drawn_on_its_second_recurrence_in_search() {
+        // the starting position occurs once in the game history before the root
+        let history = "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 10 1 moves g1f3 g8f6 f3g1";
+        // returning to it makes only a twofold repetition, returning again makes it threefold
+        assert_eq!(
+            repetition_draws_in_search(history, "f6g8 b1c3 b8c6 c3b1 c6b8"),
+            vec![false, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn test_root_position_seen_once_in_the_game_is_drawn_on_its_first_repetition_in_search() {
+        // the root position has already occurred once in the game history so returning to it is a
+        // threefold repetition
+        let history = "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 10 1 moves g1f3 g8f6 f3g1 f6g8";
+        assert_eq!(
+            repetition_draws_in_search(history, "g1f3 g8f6 f3g1 f6g8"),
+            vec![false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn test_position_not_in_the_game_history_is_drawn_when_repeated_in_search() {
+        let history = "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 10 1";
+        assert_eq!(
+            repetition_draws_in_search(history, "g1f3 g8f6 f3g1 f6g8 g1f3"),
+            vec![false, false, false, true, true]
+        );
+    }
+
+    #[test]
+    fn test_repetition_line_reports_threefold_repetition() {
+        setup();
+        let drawn_search_results = uci_util::run_uci_position(REPEATED_MOVE_ORDER, "depth 4");
+        assert_eq!(drawn_search_results.score, 0);
+        assert_eq!(drawn_search_results.pv_moves_as_string(), "f6-g8");
+        assert_eq!(drawn_search_results.game_status, GameStatus::DrawnByThreefoldRepetition);
+    }
+
+    #[test]
+    fn test_extended_principal_variation_is_legal_and_consistent_with_score() {
+        setup();
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+        let transposition_table = TranspositionTable::new(10);
+        let mut position: Position = Position::from(fen);
+        let search_results = create_search(&mut position, &transposition_table, 5).go();
+
+        let extended_pv = Search::extend_principal_variation(
+            &transposition_table,
+            &position,
+            search_results.score,
+            &[],
+            5,
+        );
+        assert!(extended_pv.len() > 1);
+        let moves: Vec<Move> = extended_pv.iter().map(|(_, mv)| *mv).collect();
+        assert_eq!(util::replay_moves(&position, &moves).unwrap(), extended_pv);
+        let mut line_position = position;
+        for (ply, (next_position, mv)) in extended_pv.iter().enumerate() {
+            let entry = transposition_table.probe(line_position.hash_code()).unwrap();
+            let expected_score =
+                if ply.is_multiple_of(2) { search_results.score } else { -search_results.score };
+            assert_eq!(entry.score, expected_score);
+            assert_eq!(entry.best_move, Some(*mv));
+            line_position = *next_position;
+        }
+
+        // a hash move that isn't legal in the position ends the line
+        let (second_position, _) = extended_pv[0];
+        let entry = transposition_table.probe(second_position.hash_code()).unwrap();
+        let illegal_move = Move::Basic { base_move: BaseMove { from: 0, to: 56, capture: false } };
+        transposition_table.new_search();
+        transposition_table.insert(
+            &second_position,
+            entry.depth,
+            -MAXIMUM_SCORE,
+            MAXIMUM_SCORE,
+            entry.score,
+            Some(illegal_move),
+        );
+        let truncated_pv = Search::extend_principal_variation(
+            &transposition_table,
+            &position,
+            search_results.score,
+            &[],
+            5,
+        );
+        assert_eq!(truncated_pv, extended_pv[..1]);
+    }
+
+    #[test]
+    fn test_second_search_reuses_t_table_entries() {
+        setup();
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+        let transposition_table = TranspositionTable::new(10);
+        let mut position: Position = Position::from(fen);
+        let mut first_search = create_search(&mut position, &transposition_table, 4);
+        let first_search_results = first_search.go();
+        let first_node_count = first_search.node_counter.node_count();
+
+        let mut position: Position = Position::from(fen);
+        let mut second_search = create_search(&mut position, &transposition_table, 4);
+        let second_search_results = second_search.go();
+        assert_eq!(second_search_results.depth, first_search_results.depth);
+        assert!(second_search.node_counter.node_count() < first_node_count);
+        assert_eq!(transposition_table.generation(), 2);
+    }
+
+    #[test]
+    fn test_aspiration_window_failures_still_yield_legal_best_move() {
+        setup();
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+        let mut position: Position = Position::from(fen);
+        let transposition_table = TranspositionTable::new(10);
+        let mut search = create_search(&mut position, &transposition_table, 5);
+        search.tuning.aspiration_window = 1;
+        let search_results = search.go();
+        assert_eq!(search_results.depth, 5);
+        let best_move = search_results.pv.first().unwrap();
+        assert!(Position::from(fen).make_move(best_move).is_some());
+
+        let mut position: Position = Position::from(fen);
+        let transposition_table = TranspositionTable::new(10);
+        let mut search = create_search(&mut position, &transposition_table, 5);
+        search.tuning.aspiration_window = MAXIMUM_SCORE;
+        assert_eq!(search.go().score, search_results.score);
+    }
+
+    #[test]
+    fn test_aspiration_window_bounds() {
+        setup();
+        let mut position = Position::new_game();
+        let transposition_table = TranspositionTable::new(1);
+        let search = create_search(&mut position, &transposition_table, 5);
+        let previous_search_results = SearchResults {
+            position: Position::new_game(),
+            score: 30,
+            depth: 3,
+            pv: vec![],
+            game_status: GameStatus::InProgress,
+            ..Default::default()
+        };
+        assert_eq!(
+            search.aspiration_window_bounds(3, Some(&previous_search_results)),
+            (-MAXIMUM_SCORE, MAXIMUM_SCORE)
+        );
+        assert_eq!(
+            search.aspiration_window_bounds(4, Some(&previous_search_results)),
+            (30 - ASPIRATION_WINDOW, 30 + ASPIRATION_WINDOW)
+        );
+        assert_eq!(search.aspiration_window_bounds(4, None), (-MAXIMUM_SCORE, MAXIMUM_SCORE));
+        let mating_search_results =
+            SearchResults { score: MAXIMUM_SCORE - 3, ..previous_search_results };
+        assert_eq!(
+            search.aspiration_window_bounds(4, Some(&mating_search_results)),
+            (-MAXIMUM_SCORE, MAXIMUM_SCORE)
+        );
+    }
+
     #[test]
     fn test_is_mating_score() {
         setup();
@@ -943,50 +1878,655 @@ mod tests {
 
     #[test]
     fn test_position_occurrence_count() {
-        assert_eq!(Search::position_occurrence_count_static(&vec!()), 0);
+        assert_eq!(Search::position_occurrence_count_static(&[]), 0);
 
         let k1 = || RepetitionKey { zobrist_hash: 1, half_move_clock: 100 };
         let k2 = || RepetitionKey { zobrist_hash: 2, half_move_clock: 100 };
         let k3 = || RepetitionKey { zobrist_hash: 3, half_move_clock: 0 };
-        assert_eq!(Search::position_occurrence_count_static(&vec![k1()]), 1);
-        assert_eq!(Search::position_occurrence_count_static(&vec![k2(), k1()]), 1);
-        assert_eq!(Search::position_occurrence_count_static(&vec![k2(), k2(), k1()]), 1);
-        assert_eq!(Search::position_occurrence_count_static(&vec![k2(), k2(), k2(), k1()]), 1);
+        assert_eq!(Search::position_occurrence_count_static(&[k1()]), 1);
+        assert_eq!(Search::position_occurrence_count_static(&[k2(), k1()]), 1);
+        assert_eq!(Search::position_occurrence_count_static(&[k2(), k2(), k1()]), 1);
+        assert_eq!(Search::position_occurrence_count_static(&[k2(), k2(), k2(), k1()]), 1);
+        assert_eq!(Search::position_occurrence_count_static(&[k1(), k2(), k2(), k2(), k1()]), 2);
         assert_eq!(
-            Search::position_occurrence_count_static(&vec![k1(), k2(), k2(), k2(), k1()]),
+            Search::position_occurrence_count_static(&[k2(), k1(), k2(), k2(), k2(), k1()]),
             2
         );
         assert_eq!(
-            Search::position_occurrence_count_static(&vec![k2(), k1(), k2(), k2(), k2(), k1()]),
-            2
-        );
-        assert_eq!(
-            Search::position_occurrence_count_static(&vec![k1(), k1(), k2(), k2(), k1(), k1()]),
+            Search::position_occurrence_count_static(&[k1(), k1(), k2(), k2(), k1(), k1()]),
             4
         );
         assert_eq!(
-            Search::position_occurrence_count_static(&vec![
-                k1(),
-                k3(),
-                k1(),
-                k2(),
-                k2(),
-                k1(),
-                k1()
-            ]),
+            Search::position_occurrence_count_static(&[k1(), k3(), k1(), k2(), k2(), k1(), k1()]),
             3
         );
         assert_eq!(
-            Search::position_occurrence_count_static(&vec![
-                k1(),
-                k2(),
-                k1(),
-                k2(),
-                k2(),
-                k2(),
-                k1()
-            ]),
+            Search::position_occurrence_count_static(&[k1(), k2(), k1(), k2(), k2(), k2(), k1()]),
             3
         );
     }
+
+    #[test]
+    fn test_root_bound_type() {
+        assert_eq!(Search::root_bound_type(10, -MAXIMUM_SCORE, MAXIMUM_SCORE), BoundType::Exact);
+        assert_eq!(
+            Search::root_bound_type(-MAXIMUM_SCORE, -MAXIMUM_SCORE, MAXIMUM_SCORE),
+            BoundType::Exact
+        );
+        assert_eq!(Search::root_bound_type(-50, -25, 25), BoundType::UpperBound);
+        assert_eq!(Search::root_bound_type(50, -25, 25), BoundType::LowerBound);
+        assert_eq!(Search::root_bound_type(0, -25, 25), BoundType::Exact);
+    }
+
+    fn search_lines(position: &Position, search_moves: &[&str], multi_pv: usize) -> Vec<String> {
+        let mut search_position = *position;
+        let transposition_table = TranspositionTable::new(1);
+        let mut search = create_search(&mut search_position, &transposition_table, 3);
+        search.search_params.search_moves = search_moves
+            .iter()
+            .map(|mv| util::create_move_list(position, mv.to_string()).unwrap()[0])
+            .collect();
+        search.multi_pv = multi_pv;
+        let search_results = search.go();
+        let lines = search.multi_pv_results();
+        assert_eq!(lines[0], search_results);
+        assert!(lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        assert!(lines.iter().all(|line| line.depth == 3));
+        lines.iter().map(|line| line.pv[0].to_string()).collect()
+    }
+
+    #[test]
+    fn test_multi_pv_reports_the_best_of_the_search_moves() {
+        setup();
+        // the queen, the rook and the pawn can each be taken safely
+        let position = Position::from("4k3/r7/8/3q4/8/4B2p/8/3RK1N1 w - - 0 1");
+        let search_moves = ["g1h3", "e3a7", "d1d5"];
+        assert_eq!(search_lines(&position, &search_moves, 2), vec!["d1xd5", "e3xa7"]);
+        // there can't be more lines than search moves
+        assert_eq!(search_lines(&position, &search_moves, 5), vec!["d1xd5", "e3xa7", "g1xh3"]);
+        assert_eq!(search_lines(&position, &search_moves[..1], 1), vec!["g1xh3"]);
+        assert_eq!(search_lines(&position, &[], 2).len(), 2);
+    }
+
+    #[test]
+    fn test_most_cutoffs_are_caused_by_the_first_move_searched() {
+        setup();
+        let mut position = Position::new_game();
+        let transposition_table = TranspositionTable::new(10);
+        let mut search = create_search(&mut position, &transposition_table, 6);
+        search.go();
+        let cutoff_counts = search.cutoff_counts();
+        let total: usize = cutoff_counts.iter().sum();
+        assert!(total > 0);
+        assert!(cutoff_counts[0] * 100 / total >= 75, "{cutoff_counts:?}");
+    }
+
+    #[test]
+    fn test_format_cutoff_statistics() {
+        assert_eq!(Search::format_cutoff_statistics(&[]), "info string no beta cutoffs");
+        assert_eq!(
+            Search::format_cutoff_statistics(&[90, 6, 4]),
+            "info string beta cutoffs 100 by move 1:90 (90%) 2:6 (6%) 3:4 (4%)"
+        );
+        assert_eq!(
+            Search::format_cutoff_statistics(&[180, 10, 4, 2, 2, 1, 0, 1]),
+            "info string beta cutoffs 200 by move 1:180 (90%) 2:10 (5%) 3:4 (2%) 4:2 (1%) 5:2 (1%) 6+:2 (1%)"
+        );
+    }
+
+    #[test]
+    fn test_format_uci_info_bound_suffix() {
+        let position = Position::new_game();
+        let search_results = SearchResults {
+            position,
+            score: 25,
+            depth: 1,
+            pv: vec![util::replay_move_string(&position, "e2e4".to_string()).unwrap()[0].1],
+            game_status: GameStatus::InProgress,
+            ..Default::default()
+        };
+        let stats = NodeCounter::new().stats();
+        let format = |bound_type| {
+            Search::format_uci_info(&position, &search_results, &stats, bound_type, None)
+        };
+        assert!(format(BoundType::Exact).starts_with("info depth 1 score cp 25 time "));
+        assert!(
+            format(BoundType::LowerBound).starts_with("info depth 1 score cp 25 lowerbound time ")
+        );
+        assert!(
+            format(BoundType::UpperBound).starts_with("info depth 1 score cp 25 upperbound time ")
+        );
+        assert!(format(BoundType::Exact).ends_with(" pv e2e4"));
+        assert!(Search::format_uci_info(
+            &position,
+            &search_results,
+            &stats,
+            BoundType::Exact,
+            Some(2)
+        )
+        .starts_with("info depth 1 multipv 2 score cp 25 time "));
+    }
+
+    #[test]
+    fn test_format_uci_score() {
+        assert_eq!(Search::format_uci_score(0), "cp 0");
+        assert_eq!(Search::format_uci_score(-150), "cp -150");
+        assert_eq!(Search::format_uci_score(MAXIMUM_SCORE - 1), "mate 1");
+        assert_eq!(Search::format_uci_score(MAXIMUM_SCORE - 3), "mate 2");
+        assert_eq!(Search::format_uci_score(MAXIMUM_SCORE - 5), "mate 3");
+        assert_eq!(Search::format_uci_score(-MAXIMUM_SCORE + 2), "mate -1");
+        assert_eq!(Search::format_uci_score(-MAXIMUM_SCORE + 4), "mate -2");
+        assert_eq!(Search::format_uci_score(-MAXIMUM_SCORE), "mate 0");
+    }
+
+    #[test]
+    fn test_mate_in_moves() {
+        assert_eq!(Search::mate_in_moves(150), None);
+        assert_eq!(Search::mate_in_moves(MAXIMUM_SCORE - MAXIMUM_SEARCH_DEPTH as i32 - 1), None);
+        assert_eq!(Search::mate_in_moves(MAXIMUM_SCORE), Some(0));
+        assert_eq!(Search::mate_in_moves(-MAXIMUM_SCORE), Some(0));
+        for moves_to_mate in 1..=4 {
+            let plies_to_mate = 2 * moves_to_mate - 1;
+            assert_eq!(Search::mate_in_moves(MAXIMUM_SCORE - plies_to_mate), Some(moves_to_mate));
+            assert_eq!(Search::mate_score(moves_to_mate), MAXIMUM_SCORE - plies_to_mate);
+            assert_eq!(
+                Search::mate_in_moves(-MAXIMUM_SCORE + plies_to_mate + 1),
+                Some(-moves_to_mate)
+            );
+            assert_eq!(Search::mate_score(-moves_to_mate), -MAXIMUM_SCORE + plies_to_mate + 1);
+        }
+    }
+
+    #[test]
+    fn test_known_mate_in_t_table_is_played_without_searching() {
+        setup();
+        let fen = "r2qk2r/pb4pp/1n2Pb2/2B2Q2/p1p5/2P5/2B2PPP/RN2R1K1 w - - 1 0";
+        let mut position: Position = Position::from(fen);
+        let mating_move = util::create_move_list(&position, "f5g6".to_string()).unwrap()[0];
+        let transposition_table = TranspositionTable::new(1);
+        let mate_score = Search::mate_score(2);
+        transposition_table.insert(
+            &position,
+            3,
+            -MAXIMUM_SCORE,
+            MAXIMUM_SCORE,
+            mate_score,
+            Some(mating_move),
+        );
+        let mut search = create_search(&mut position, &transposition_table, 10);
+        let search_results = search.go();
+        assert_eq!(search.node_counter.node_count(), 0);
+        assert_eq!(search_results.pv.first(), Some(&mating_move));
+        assert_eq!(search_results.score, mate_score);
+
+        let illegal_move = util::create_move_list(&position, "f5g6".to_string()).unwrap()[0];
+        let mut other_position: Position = Position::from("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        transposition_table.insert(
+            &other_position,
+            3,
+            -MAXIMUM_SCORE,
+            MAXIMUM_SCORE,
+            mate_score,
+            Some(illegal_move),
+        );
+        let mut search = create_search(&mut other_position, &transposition_table, 2);
+        let search_results = search.go();
+        assert!(search.node_counter.node_count() > 0);
+        assert_ne!(search_results.pv.first(), Some(&illegal_move));
+    }
+
+    #[test]
+    fn test_mate_found_by_a_search_is_played_by_the_next_search() {
+        setup();
+        let fen = "r2qk2r/pb4pp/1n2Pb2/2B2Q2/p1p5/2P5/2B2PPP/RN2R1K1 w - - 1 0";
+        let mut position: Position = Position::from(fen);
+        let transposition_table = TranspositionTable::new(1);
+        let search_results = create_search(&mut position, &transposition_table, 10).go();
+        assert_eq!(search_results.score, Search::mate_score(2));
+
+        let mut search = create_search(&mut position, &transposition_table, 10);
+        let second_search_results = search.go();
+        assert_eq!(search.node_counter.node_count(), 0);
+        assert_eq!(second_search_results.pv.first(), search_results.pv.first());
+        assert_eq!(second_search_results.score, Search::mate_score(2));
+
+        // the entries stored two plies from the root of the first search now score mate in one
+        let mut position_after_reply = position;
+        for mv in &search_results.pv[..2] {
+            position_after_reply.make_move(mv).unwrap();
+        }
+        let search_results =
+            create_search(&mut position_after_reply, &transposition_table, 10).go();
+        assert_eq!(search_results.score, Search::mate_score(1));
+        assert_eq!(search_results.pv.len(), 1);
+    }
+
+    #[test]
+    fn test_t_table_entry_with_illegal_best_move_is_ignored() {
+        setup();
+        let mut position: Position = Position::from("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        let transposition_table = TranspositionTable::new(1);
+        let bogus_move = util::create_move_list(
+            &Position::from("r2qk2r/pb4pp/1n2Pb2/2B2Q2/p1p5/2P5/2B2PPP/RN2R1K1 w - - 1 0"),
+            "f5g6".to_string(),
+        )
+        .unwrap()[0];
+        // an entry claiming that black is mated after e2-e4 as if it had been stored by a colliding
+        // position, whose best move can't be played here
+        let mut next_position = position;
+        next_position.make_move(&util::create_move_list(&position, "e2e4".to_string()).unwrap()[0]);
+        transposition_table.insert(
+            &next_position,
+            10,
+            -MAXIMUM_SCORE,
+            MAXIMUM_SCORE,
+            -Search::mate_score(1),
+            Some(bogus_move),
+        );
+        let mut search = create_search(&mut position, &transposition_table, 3);
+        let search_results = search.go();
+        assert!(!Search::is_mating_score(search_results.score));
+        assert!(position.legal_moves().contains(&search_results.pv[0]));
+        assert!(!search_results.pv.contains(&bogus_move));
+    }
+
+    #[test]
+    fn test_reported_mate_in_one_to_four() {
+        setup();
+        for (fen, depth, moves_to_mate) in [
+            ("rnbqkbnr/p2p1ppp/1p6/2p1p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 0 4", 1, 1),
+            ("r2qk2r/pb4pp/1n2Pb2/2B2Q2/p1p5/2P5/2B2PPP/RN2R1K1 w - - 1 0", 3, 2),
+            ("r5rk/5p1p/5R2/4B3/8/8/7P/7K w - - 1 1", 5, 3),
+            ("4R3/5ppk/7p/3BpP2/3b4/1P4QP/r5PK/3q4 w - - 0 1", 7, 4),
+        ] {
+            let mut position: Position = Position::from(fen);
+            let search_results =
+                create_search(&mut position, &TranspositionTable::new(1), depth).go();
+            assert_eq!(search_results.score, Search::mate_score(moves_to_mate));
+            assert_eq!(Search::mate_in_moves(search_results.score), Some(moves_to_mate));
+            assert_eq!(
+                Search::format_uci_score(search_results.score),
+                format!("mate {moves_to_mate}")
+            );
+        }
+    }
+
+    #[test]
+    fn test_mate_in_three_reported_as_uci_mate_score() {
+        setup();
+        let fen = "r5rk/5p1p/5R2/4B3/8/8/7P/7K w - - 1 1";
+        let mut position: Position = Position::from(fen);
+        let search_results = create_search(&mut position, &TranspositionTable::new(1), 5).go();
+        let uci_info = Search::format_uci_info(
+            &position,
+            &search_results,
+            &NodeCounter::new().stats(),
+            BoundType::Exact,
+            None,
+        );
+        assert!(uci_info.starts_with("info depth 5 score mate 3 time "));
+    }
+
+    #[test]
+    fn test_immediate_stop_returns_legal_move() {
+        setup();
+        let mut position = Position::new_game();
+        let transposition_table = TranspositionTable::new(1);
+        let mut search = create_search(&mut position, &transposition_table, 10);
+        search.stop_flag.store(true, Ordering::Relaxed);
+        let search_results = search.go();
+        assert_eq!(search_results.depth, 0);
+        assert_eq!(search_results.pv.len(), 1);
+        assert!(move_gen::generate_moves(&position).contains(&search_results.pv[0]));
+    }
+
+    #[test]
+    fn test_immediate_stop_when_checkmated() {
+        setup();
+        let mut position = Position::from("k6R/8/1K6/8/8/8/8/8 b - - 0 1");
+        let transposition_table = TranspositionTable::new(1);
+        let mut search = create_search(&mut position, &transposition_table, 10);
+        search.stop_flag.store(true, Ordering::Relaxed);
+        let search_results = search.go();
+        assert!(search_results.pv.is_empty());
+        assert_eq!(search_results.score, -MAXIMUM_SCORE);
+        assert_eq!(search_results.game_status, GameStatus::Checkmate);
+    }
+
+    #[test]
+    fn test_fixed_depth_search_is_deterministic() {
+        setup();
+        assert!(!SearchParams::new_by_depth(6).is_time_limited());
+        assert!(SearchParams::new(1000, 6, usize::MAX).is_time_limited());
+        let fen = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+        let run_search = || {
+            let mut position: Position = Position::from(fen);
+            let transposition_table = TranspositionTable::new(1);
+            let mut search = create_search(&mut position, &transposition_table, 6);
+            let search_results = search.go();
+            // the time is still measured for display
+            let reported_value = |name: &str| {
+                let mut words = search.last_info.split_whitespace();
+                words.find(|word| *word == name).and_then(|_| words.next()?.parse::<usize>().ok())
+            };
+            assert!(reported_value("time").unwrap() > 0, "{}", search.last_info);
+            assert!(reported_value("nps").unwrap() > 0, "{}", search.last_info);
+            (search.node_counter.node_count(), search_results.pv, search_results.score)
+        };
+        assert_eq!(run_search(), run_search());
+    }
+
+    #[test]
+    fn test_depth_zero_search_evaluates_the_position() {
+        setup();
+        let fen = "r2qk2r/pb4pp/1n2Pb2/2B2Q2/p1p5/2P5/2B2PPP/RN2R1K1 w - - 1 1";
+        let mut position: Position = Position::from(fen);
+        let transposition_table = TranspositionTable::new(1);
+        let mut search = create_search(&mut position, &transposition_table, 0);
+        let search_results = search.go();
+        assert_eq!(search_results.depth, 0);
+        assert!(search_results.pv.is_empty());
+        assert_eq!(search_results.score, evaluation::score_position(&Position::from(fen)));
+        assert_eq!(search_results.game_status, GameStatus::InProgress);
+        assert_eq!(search.node_counter.node_count(), 0);
+
+        let mut checkmated: Position = Position::from("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1");
+        let search_results = create_search(&mut checkmated, &transposition_table, 0).go();
+        assert_eq!(search_results.score, -MAXIMUM_SCORE);
+        assert_eq!(search_results.game_status, GameStatus::Checkmate);
+
+        let search_results =
+            uci_util::run_uci_position(&format!("position fen {fen}"), "go depth 0");
+        assert_eq!(search_results.depth, 0);
+        assert!(search_results.pv.is_empty());
+    }
+
+    #[test]
+    fn test_last_info_matches_the_best_move_when_the_search_is_stopped() {
+        setup();
+        let mut position = Position::new_game();
+        let transposition_table = TranspositionTable::new(1);
+        let mut search = Search::new(
+            &mut position,
+            &transposition_table,
+            SearchParams::new(usize::MAX, MAXIMUM_SEARCH_DEPTH as isize, 5000),
+            Arc::new(AtomicBool::new(false)),
+            vec![],
+            MoveOrderer::new(),
+            0,
+        );
+        let search_results = search.go();
+        assert!(search.stop_search_requested());
+        let best_move = r#move::convert_move_to_raw(search_results.pv[0]).to_string();
+        assert!(search.last_info.starts_with(&format!("info depth {} ", search_results.depth)));
+        assert!(search.last_info.contains(&format!(" pv {best_move}")), "{}", search.last_info);
+        assert!(!search.last_info.contains("bound"));
+    }
+
+    #[test]
+    fn test_refutation_found_in_a_stopped_iteration_changes_the_best_move() {
+        setup();
+        // taking the knight looks best at depth 1 but loses to Re1# at depth 2
+        let fen = "4r1k1/5ppp/8/n7/8/8/5PPP/R5K1 w - - 0 1";
+        let rook_takes_knight =
+            Move::Basic { base_move: BaseMove { from: sq!("a1"), to: sq!("a5"), capture: true } };
+        let search_using_nodes = |max_nodes: usize| {
+            let mut position: Position = Position::from(fen);
+            let transposition_table = TranspositionTable::new(1);
+            Search::new(
+                &mut position,
+                &transposition_table,
+                SearchParams::new(usize::MAX, 2, max_nodes),
+                Arc::new(AtomicBool::new(false)),
+                vec![],
+                MoveOrderer::new(),
+                0,
+            )
+            .go()
+        };
+        let completed_search_results = search_using_nodes(usize::MAX);
+        assert_eq!(completed_search_results.depth, 2);
+        assert_ne!(completed_search_results.pv[0], rook_takes_knight);
+        // the depth 2 iteration is stopped before it has found the refutation
+        let before_refutation_search_results = search_using_nodes(50);
+        assert_eq!(before_refutation_search_results.depth, 1);
+        assert_eq!(before_refutation_search_results.pv[0], rook_takes_knight);
+        // the depth 2 iteration is stopped after finding the refutation and a safe move
+        let after_refutation_search_results = search_using_nodes(250);
+        assert_eq!(after_refutation_search_results.depth, 1);
+        assert_ne!(after_refutation_search_results.pv[0], rook_takes_knight);
+        assert!(!Search::is_mating_score(-after_refutation_search_results.score));
+    }
+
+    #[test]
+    fn test_repetition_draw_is_not_reused_where_it_is_not_a_draw() {
+        setup();
+        // Qe5+ forces Kg8, which repeats the position for the third time in this game
+        let uci_position = uci_util::parse_position(
+            "position fen q5k1/7p/8/4Q3/7K/8/8/8 w - - 0 1 moves e5e1 g8h8 e1e5 h8g8 e5e1 g8h8",
+        )
+        .unwrap();
+        let search_from = |transposition_table: &TranspositionTable, repetition_keys| {
+            let mut position = uci_position.end_position;
+            Search::new(
+                &mut position,
+                transposition_table,
+                SearchParams::new_by_depth(3),
+                Arc::new(AtomicBool::new(false)),
+                repetition_keys,
+                MoveOrderer::new(),
+                0,
+            )
+            .go()
+        };
+        let transposition_table = TranspositionTable::new(1);
+        let search_results =
+            search_from(&transposition_table, uci_position.repetition_keys.clone());
+        assert_eq!(search_results.score, DRAW_SCORE);
+
+        // the same position without the game history isn't a draw
+        let fresh_search_results = search_from(&TranspositionTable::new(1), vec![]);
+        assert!(fresh_search_results.score < DRAW_SCORE);
+        assert_eq!(search_from(&transposition_table, vec![]).score, fresh_search_results.score);
+    }
+
+    #[test]
+    fn test_search_depth_is_capped_at_maximum_search_depth() {
+        setup();
+        let mut position: Position = Position::from("8/8/3k4/8/8/4K3/8/8 w - - 0 1");
+        let transposition_table = TranspositionTable::new(1);
+        let search_results = create_search(&mut position, &transposition_table, 64).go();
+        assert_eq!(search_results.depth, MAXIMUM_SEARCH_DEPTH as u8);
+    }
+
+    #[test]
+    fn test_search_stops_at_node_limit() {
+        setup();
+        let mut position: Position = Position::new_game();
+        let transposition_table = TranspositionTable::new(1);
+        let mut search = Search::new(
+            &mut position,
+            &transposition_table,
+            SearchParams::new(usize::MAX, 20, 5000),
+            Arc::new(AtomicBool::new(false)),
+            vec![],
+            MoveOrderer::new(),
+            0,
+        );
+        let search_results = search.go();
+        assert!(!search_results.pv.is_empty());
+        assert!(search_results.depth < 20);
+        assert!(search.node_counter.node_count() < 10_000);
+    }
+
+    #[test]
+    fn test_knight_promotion_fork_is_found() {
+        setup();
+        let fen = "8/2k1P1q1/8/8/8/8/P7/7K w - - 0 1";
+        for prune_under_promotions in [true, false] {
+            let mut position: Position = Position::from(fen);
+            let transposition_table = TranspositionTable::new(1);
+            let mut search = create_search(&mut position, &transposition_table, 4);
+            search.prune_under_promotions = prune_under_promotions;
+            let search_results = search.go();
+            assert_eq!(
+                search_results.pv.first().map(|mv| r#move::convert_move_to_raw(*mv).to_string()),
+                Some("e7e8n".to_string())
+            );
+            assert!(search_results.score > 200);
+        }
+    }
+
+    #[test]
+    fn test_search_results_include_node_count_and_elapsed_time() {
+        setup();
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+        let mut position: Position = Position::from(fen);
+        let transposition_table = TranspositionTable::new(1);
+        let start_time = std::time::Instant::now();
+        let mut search = create_search(&mut position, &transposition_table, 4);
+        let search_results = search.go();
+        assert!(search_results.node_count > 0);
+        assert_eq!(search_results.node_count, search.node_counter.node_count());
+        assert!(search_results.elapsed_millis <= start_time.elapsed().as_millis() as usize);
+
+        let repeated_search_results =
+            SearchResults { node_count: 0, elapsed_millis: 1000, ..search_results.clone() };
+        assert_eq!(repeated_search_results, search_results);
+    }
+
+    #[test]
+    fn test_quiescence_node_fraction() {
+        setup();
+        let quiescence_node_percentage = |fen: &str| {
+            let mut position: Position = Position::from(fen);
+            let transposition_table = TranspositionTable::new(1);
+            let mut search = create_search(&mut position, &transposition_table, 3);
+            search.go();
+            let stats = search.node_counter.stats();
+            assert!(stats.quiescence_node_count < stats.node_count);
+            stats.quiescence_node_percentage()
+        };
+        // many pieces can be captured in the first position but no captures are possible in the
+        // blocked pawn ending
+        let capture_heavy = quiescence_node_percentage(
+            "r1b1k2r/ppp2ppp/2nq1n2/1B1pp3/1b1PP3/2NQ1N2/PPP2PPP/R1B1K2R w KQkq - 0 1",
+        );
+        let quiet = quiescence_node_percentage("4k3/8/8/1p1p1p2/1P1P1P2/8/8/4K3 w - - 0 1");
+        assert!(capture_heavy > 50, "{capture_heavy}");
+        assert!(quiet < capture_heavy, "{quiet}");
+    }
+
+    #[test]
+    fn test_losing_capture_reduction() {
+        setup();
+        let mut position: Position = Position::from("4k3/8/4p3/3p4/2P5/8/8/3QK3 w - - 0 1");
+        let transposition_table = TranspositionTable::new(1);
+        let losing_capture =
+            Move::Basic { base_move: BaseMove { from: sq!("d1"), to: sq!("d5"), capture: true } };
+        let winning_capture =
+            Move::Basic { base_move: BaseMove { from: sq!("c4"), to: sq!("d5"), capture: true } };
+        let mut search = create_search(&mut position, &transposition_table, 4);
+        search.reduce_losing_captures = true;
+        assert_eq!(search.losing_capture_reduction(&losing_capture, 4, false), 1);
+        assert_eq!(search.losing_capture_reduction(&winning_capture, 4, false), 0);
+        assert_eq!(search.losing_capture_reduction(&losing_capture, 2, false), 0);
+        assert_eq!(search.losing_capture_reduction(&losing_capture, 4, true), 0);
+        let search_results = search.go();
+        assert_eq!(search_results.pv[0], winning_capture);
+
+        search.reduce_losing_captures = false;
+        assert_eq!(search.losing_capture_reduction(&losing_capture, 4, false), 0);
+    }
+
+    #[test]
+    fn test_leaves_are_scored_statically_without_quiescence() {
+        setup();
+        let fen = "4k3/8/4p3/3p4/2P5/8/8/3QK3 w - - 0 1";
+        let static_leaf_score = Position::from(fen)
+            .legal_moves()
+            .iter()
+            .map(|mv| {
+                let mut position = Position::from(fen);
+                position.make_move(mv).unwrap();
+                -evaluation::score_position(&position)
+            })
+            .max()
+            .unwrap();
+
+        let mut position: Position = Position::from(fen);
+        let transposition_table = TranspositionTable::new(1);
+        let mut search = create_search(&mut position, &transposition_table, 1);
+        search.use_quiescence = false;
+        assert_eq!(search.go().score, static_leaf_score);
+
+        let mut position: Position = Position::from(fen);
+        let transposition_table = TranspositionTable::new(1);
+        let search_results = create_search(&mut position, &transposition_table, 1).go();
+        assert!(search_results.score < static_leaf_score);
+    }
+
+    #[test]
+    fn test_extreme_futility_margin_keeps_mates() {
+        setup();
+        let mut default_node_counts = 0;
+        let mut node_counts = 0;
+        for (fen, depth, moves_to_mate) in [
+            ("r2qk2r/pb4pp/1n2Pb2/2B2Q2/p1p5/2P5/2B2PPP/RN2R1K1 w - - 1 0", 3, 2),
+            ("r5rk/5p1p/5R2/4B3/8/8/7P/7K w - - 1 1", 5, 3),
+            ("4R3/5ppk/7p/3BpP2/3b4/1P4QP/r5PK/3q4 w - - 0 1", 7, 4),
+        ] {
+            let search_with_margin = |reverse_futility_margin: i32| {
+                let mut position: Position = Position::from(fen);
+                let transposition_table = TranspositionTable::new(1);
+                let mut search = create_search(&mut position, &transposition_table, depth);
+                search.tuning.reverse_futility_margin = reverse_futility_margin;
+                let search_results = search.go();
+                (search.node_counter.node_count(), search_results.score, search_results.pv[0])
+            };
+            let (default_node_count, default_score, default_move) =
+                search_with_margin(REVERSE_FUTILITY_MARGIN_PER_PLY);
+            let (node_count, score, best_move) = search_with_margin(0);
+            default_node_counts += default_node_count;
+            node_counts += node_count;
+            assert_eq!(score, default_score);
+            assert_eq!(Search::mate_in_moves(score), Some(moves_to_mate));
+            assert_eq!(best_move, default_move);
+        }
+        assert_ne!(node_counts, default_node_counts);
+    }
+
+    #[test]
+    fn test_reverse_futility_pruning_reduces_node_count() {
+        setup();
+        let fen = "r3k3/ppp2ppp/2n5/8/8/2N5/PPPQ1PPP/R3K3 w - - 0 1";
+        let search_node_count = |reverse_futility_pruning: bool| {
+            let mut position: Position = Position::from(fen);
+            let transposition_table = TranspositionTable::new(1);
+            let mut search = create_search(&mut position, &transposition_table, 5);
+            search.reverse_futility_pruning = reverse_futility_pruning;
+            let search_results = search.go();
+            assert!(search_results.score > 500);
+            search.node_counter.node_count()
+        };
+        let pruned_node_count = search_node_count(true);
+        let unpruned_node_count = search_node_count(false);
+        assert!(
+            pruned_node_count < unpruned_node_count,
+            "pruned: {pruned_node_count} unpruned: {unpruned_node_count}"
+        );
+    }
+
+    #[test]
+    fn test_reverse_futility_pruning_skipped_for_mating_scores() {
+        setup();
+        let mut position: Position =
+            Position::from("r3k3/ppp2ppp/2n5/8/8/2N5/PPPQ1PPP/R3K3 w - - 0 1");
+        let transposition_table = TranspositionTable::new(1);
+        let search = create_search(&mut position, &transposition_table, 5);
+        assert!(search.can_prune_by_reverse_futility(1, 1, 500));
+        assert!(!search.can_prune_by_reverse_futility(0, 1, 500));
+        assert!(!search.can_prune_by_reverse_futility(1, 4, 500));
+        assert!(!search.can_prune_by_reverse_futility(1, 1, -MAXIMUM_SCORE + 10));
+    }
 }
@@ -1,8 +1,8 @@
 use crate::core::move_gen;
+use crate::core::move_gen::MOVE_LIST_LENGTH;
 use crate::core::piece::{PieceColor, PieceType};
 use crate::core::position::Position;
 use crate::core::r#move::Move;
-use crate::eval::evaluation::{score_position, PIECE_SCORES};
 use crate::search::move_ordering::order_quiescence_moves;
 use crate::search::negamax::{Search, MAXIMUM_SCORE, MAXIMUM_SEARCH_DEPTH};
 use crate::utils::util;
@@ -11,18 +11,36 @@ use strum::IntoEnumIterator;
 
 include!("../utils/generated_macro.rs");
 
+/// Mates found by the quiescence search are scored below the mate range because it only searches
+/// captures for the mating side, so the mate is left for negamax to prove with its full line
 pub const QUIESCENCE_MAXIMUM_SCORE: i32 = MAXIMUM_SCORE / 2;
 
+pub const MAXIMUM_QUIESCENCE_DEPTH: u8 = 100;
+
+pub const QUIESCENCE_SEE_THRESHOLD: i32 = 0;
+
+pub const QUIESCENCE_STAND_PAT_MARGIN: i32 = 0;
+
 impl Search<'_> {
+    /// Searches captures until the position is quiet. The ply is that of the position in the
+    /// search tree so that mates found here are scored like those found by negamax.
+    ///
+    /// When not in check the side to move can stand pat, so the static evaluation plus the stand
+    /// pat margin is a lower bound on the score. When in check it can't, so every evasion is
+    /// searched and the score starts from being mated at this ply, which is the score returned
+    /// if there are no evasions. Being mated is scored from QUIESCENCE_MAXIMUM_SCORE so that
+    /// shorter mates still score higher.
     pub fn quiescence_search(&mut self, ply: u8, alpha: i32, beta: i32) -> i32 {
-        if ply > 100 {
-            return 0;
+        if ply > self.max_quiescence_depth {
+            return self.score_position();
         }
-        self.node_counter.increment();
+        self.node_counter.increment_quiescence();
         if move_gen::is_check(self.position) {
-            // If in check: must respond with evasions
             let mut best_score = -QUIESCENCE_MAXIMUM_SCORE + ply as i32;
-            for mov in move_gen::generate_moves(self.position) {
+            let mut alpha = alpha;
+            let mut moves: ArrayVec<Move, MOVE_LIST_LENGTH> = ArrayVec::new();
+            move_gen::generate_moves_into(self.position, &mut moves);
+            for mov in moves {
                 if let Some(undo_move_info) = self.position.make_move(&mov) {
                     let score = -self.quiescence_search(ply + 1, -beta, -alpha);
                     self.position.unmake_move(&undo_move_info);
@@ -30,13 +48,13 @@ impl Search<'_> {
                     if best_score >= beta {
                         break;
                     }
+                    alpha = alpha.max(best_score);
                 }
             }
             return best_score;
         }
 
-        // Static evaluation when not in check
-        let stand_pat = score_position(self.position);
+        let stand_pat = self.score_position() + self.tuning.quiescence_stand_pat_margin;
         if stand_pat >= beta {
             return stand_pat;
         }
@@ -46,7 +64,7 @@ impl Search<'_> {
         let captures = Search::generate_sorted_quiescence_moves(self.position);
 
         for mov in captures {
-            if matches!(mov, Move::Basic { .. }) && !Search::good_capture(self.position, &mov) {
+            if matches!(mov, Move::Basic { .. }) && !self.good_capture(&mov) {
                 continue; // Skip bad captures by SEE
             }
             if let Some(undo_move_info) = self.position.make_move(&mov) {
@@ -79,19 +97,33 @@ impl Search<'_> {
         alpha
     }
 
-    fn good_capture(position: &Position, mov: &Move) -> bool {
-        Search::static_exchange_evaluation(position, mov) >= 0
+    /// A capture is searched if the static exchange evaluation doesn't lose more than the threshold
+    fn good_capture(&self, mov: &Move) -> bool {
+        self.static_exchange_evaluation(mov) >= self.tuning.quiescence_see_threshold
+    }
+
+    /// Uses the piece values read when the search was created
+    pub(crate) fn static_exchange_evaluation(&self, mov: &Move) -> i32 {
+        Search::static_exchange_evaluation_using_values(
+            self.position,
+            mov,
+            &self.evaluation_weights.piece_values,
+        )
     }
 
     // with delta pruning
-    fn static_exchange_evaluation(position: &Position, mv: &Move) -> i32 {
+    fn static_exchange_evaluation_using_values(
+        position: &Position,
+        mv: &Move,
+        piece_values: &[i32; 6],
+    ) -> i32 {
         let attacked_square = mv.get_base_move().to as usize;
         let attacking_square = mv.get_base_move().from as usize;
         let attacking_piece = Search::piece_on(position, attacking_square);
 
         let mut gain: ArrayVec<i32, MAXIMUM_SEARCH_DEPTH> = ArrayVec::new();
         let mut attacked_piece = Search::piece_on(position, attacked_square);
-        gain.push(PIECE_SCORES[attacked_piece as usize]);
+        gain.push(piece_values[attacked_piece as usize]);
 
         let mut occupied = position.board().bitboard_all_pieces();
         let mut attackers = Search::attackers_to(position, attacked_square, occupied);
@@ -127,7 +159,7 @@ impl Search<'_> {
 
             depth += 1;
             let last_gain = gain[depth - 1];
-            gain.push(PIECE_SCORES[attacked_piece as usize] - last_gain);
+            gain.push(piece_values[attacked_piece as usize] - last_gain);
 
             // **Delta pruning: early abort**
             // if side_to_move == position.side_to_move() {
@@ -170,7 +202,7 @@ impl Search<'_> {
     }
 
     fn piece_on(position: &Position, source_square: usize) -> PieceType {
-        position.board().get_piece(source_square).unwrap().piece_type
+        position.board().piece_at(source_square).unwrap().1
     }
 
     fn attackers_to(position: &Position, target_index: usize, occupied: u64) -> [u64; 2] {
@@ -249,6 +281,7 @@ mod tests {
     use super::*;
     use crate::core::piece::PieceColor::{Black, White};
     use crate::core::r#move::BaseMove;
+    use crate::eval::evaluation::{score_position, EvaluationWeights};
 
     #[test]
     fn test_generate_sorted_captures() {
@@ -300,31 +333,57 @@ mod tests {
         assert_eq!(square_index, Some(sq!("b7")));
     }
 
+    #[test]
+    fn test_static_exchange_evaluation_using_values() {
+        let fen = "4k3/1p6/2n5/1B6/8/8/8/4K3 w - - 1 1";
+        let position: Position = Position::from(fen);
+        let mov =
+            Move::Basic { base_move: BaseMove { from: sq!("b5"), to: sq!("c6"), capture: true } };
+        let mut piece_values = EvaluationWeights::default().piece_values;
+        assert_eq!(
+            Search::static_exchange_evaluation_using_values(&position, &mov, &piece_values),
+            0
+        );
+        piece_values[PieceType::Bishop as usize] = 325;
+        assert_eq!(
+            Search::static_exchange_evaluation_using_values(&position, &mov, &piece_values),
+            -25
+        );
+    }
+
+    fn static_exchange_evaluation(position: &Position, mov: &Move) -> i32 {
+        Search::static_exchange_evaluation_using_values(
+            position,
+            mov,
+            &EvaluationWeights::default().piece_values,
+        )
+    }
+
     #[test]
     fn test_static_exchange_evaluation() {
         let fen = "4k3/8/2n5/1P6/8/8/8/4K3 w - - 1 1";
         let position: Position = Position::from(fen);
         let mov =
             Move::Basic { base_move: BaseMove { from: sq!("b5"), to: sq!("c6"), capture: true } };
-        assert_eq!(Search::static_exchange_evaluation(&position, &mov), 300);
+        assert_eq!(static_exchange_evaluation(&position, &mov), 300);
 
         let fen = "4k3/1p6/2p5/1B6/8/8/8/4K3 w - - 1 1";
         let position: Position = Position::from(fen);
         let mov =
             Move::Basic { base_move: BaseMove { from: sq!("b5"), to: sq!("c6"), capture: true } };
-        assert_eq!(Search::static_exchange_evaluation(&position, &mov), -200);
+        assert_eq!(static_exchange_evaluation(&position, &mov), -200);
 
         let fen = "4k3/1p6/2b5/1B6/8/8/8/4K3 w - - 1 1";
         let position: Position = Position::from(fen);
         let mov =
             Move::Basic { base_move: BaseMove { from: sq!("b5"), to: sq!("c6"), capture: true } };
-        assert_eq!(Search::static_exchange_evaluation(&position, &mov), 0);
+        assert_eq!(static_exchange_evaluation(&position, &mov), 0);
 
         let fen = "4k3/1p6/2b5/1B1P4/8/8/8/4K3 w - - 1 1";
         let position: Position = Position::from(fen);
         let mov =
             Move::Basic { base_move: BaseMove { from: sq!("d5"), to: sq!("c6"), capture: true } };
-        assert_eq!(Search::static_exchange_evaluation(&position, &mov), 300);
+        assert_eq!(static_exchange_evaluation(&position, &mov), 300);
     }
 
     #[test]
@@ -334,34 +393,34 @@ mod tests {
         let position: Position = Position::from(fen);
         let mov =
             Move::Basic { base_move: BaseMove { from: sq!("d2"), to: sq!("d8"), capture: true } };
-        assert_eq!(Search::static_exchange_evaluation(&position, &mov), 300);
+        assert_eq!(static_exchange_evaluation(&position, &mov), 300);
 
         // undoubling the rooks produces the correct result
         let fen = "R2r4/4bk2/8/8/8/8/3R4/4K3 w - - 0 1";
         let position: Position = Position::from(fen);
         let mov =
             Move::Basic { base_move: BaseMove { from: sq!("d2"), to: sq!("d8"), capture: true } };
-        assert_eq!(Search::static_exchange_evaluation(&position, &mov), 300);
+        assert_eq!(static_exchange_evaluation(&position, &mov), 300);
 
         // a losing capture because SEE misses the doubled rooks
         let fen = "3r4/4bk2/3P4/8/8/8/3R4/3RK3 b - - 0 1";
         let position: Position = Position::from(fen);
         let mov =
             Move::Basic { base_move: BaseMove { from: sq!("e7"), to: sq!("d6"), capture: true } };
-        assert_eq!(Search::static_exchange_evaluation(&position, &mov), -200);
+        assert_eq!(static_exchange_evaluation(&position, &mov), -200);
 
         // a winning capture because SE
         let fen = "3r4/4bk2/3P4/8/8/8/8/3RK3 b - - 0 1";
         let position: Position = Position::from(fen);
         let mov =
             Move::Basic { base_move: BaseMove { from: sq!("e7"), to: sq!("d6"), capture: true } };
-        assert_eq!(Search::static_exchange_evaluation(&position, &mov), 100);
+        assert_eq!(static_exchange_evaluation(&position, &mov), 100);
 
         let fen = "3r4/3br3/7k/8/3R4/3R4/8/3QK3 w - - 0 1";
         let position: Position = Position::from(fen);
         let mov =
             Move::Basic { base_move: BaseMove { from: sq!("d4"), to: sq!("d7"), capture: true } };
-        assert_eq!(Search::static_exchange_evaluation(&position, &mov), 300);
+        assert_eq!(static_exchange_evaluation(&position, &mov), 300);
     }
 
     #[test]
@@ -419,7 +478,7 @@ mod tests {
             Search::new(
                 position,
                 transposition_table,
-                SearchParams { allocated_time_millis: 0, max_depth: 0, max_nodes: 0 },
+                SearchParams::new(0, 0, 0),
                 Arc::new(Default::default()),
                 vec![],
                 MoveOrderer::new(),
@@ -444,7 +503,7 @@ mod tests {
             let transposition_table = &mut TranspositionTable::new_using_config();
             let mut search = create_search_context(&mut position, transposition_table);
             let score = search.quiescence_search(0, -MAXIMUM_SCORE, MAXIMUM_SCORE);
-            assert_eq!(score, 903);
+            assert_eq!(score, 904);
         }
 
         #[test]
@@ -454,7 +513,7 @@ mod tests {
             let transposition_table = &mut TranspositionTable::new_using_config();
             let mut search = create_search_context(&mut position, transposition_table);
             let score = search.quiescence_search(0, -MAXIMUM_SCORE, MAXIMUM_SCORE);
-            assert_eq!(score, 961);
+            assert_eq!(score, 964);
         }
 
         #[test]
@@ -474,7 +533,7 @@ mod tests {
             let transposition_table = &mut TranspositionTable::new_using_config();
             let mut search = create_search_context(&mut position, transposition_table);
             let score = search.quiescence_search(0, -MAXIMUM_SCORE, MAXIMUM_SCORE);
-            assert_eq!(score, -14);
+            assert_eq!(score, -13);
         }
 
         #[test]
@@ -484,7 +543,26 @@ mod tests {
             let transposition_table = &mut TranspositionTable::new_using_config();
             let mut search = create_search_context(&mut position, transposition_table);
             let score = search.quiescence_search(0, -MAXIMUM_SCORE, MAXIMUM_SCORE);
-            assert_eq!(score, 788);
+            assert_eq!(score, 769);
+        }
+
+        #[test]
+        fn test_see_threshold() {
+            let fen = "4k3/1p6/2b5/1B6/8/8/8/4K3 w - - 1 1";
+            let equal_capture =
+                Basic { base_move: BaseMove { from: sq!("b5"), to: sq!("c6"), capture: true } };
+            let mut node_counts = vec![];
+            for (see_threshold, is_searched) in [(1, false), (0, true), (-100, true)] {
+                let mut position: Position = Position::from(fen);
+                let transposition_table = &mut TranspositionTable::new(1);
+                let mut search = create_search_context(&mut position, transposition_table);
+                search.tuning.quiescence_see_threshold = see_threshold;
+                assert_eq!(search.good_capture(&equal_capture), is_searched);
+                search.quiescence_search(0, -MAXIMUM_SCORE, MAXIMUM_SCORE);
+                node_counts.push(search.node_counter.node_count());
+            }
+            assert_eq!(node_counts[0], 1);
+            assert!(node_counts[1] > 1);
         }
 
         #[test]
@@ -597,5 +675,68 @@ mod tests {
             assert_eq!(quiescence_moves[14], move_14);
             assert_eq!(MoveOrderer::mvv_lva_score(&position, &move_14), -2);
         }
+
+        #[test]
+        fn test_mate_found_by_quiescence_is_scored_below_the_mate_range() {
+            // the capture on d8 is mate
+            let fen = "3r2k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1";
+            let mut position: Position = Position::from(fen);
+            let transposition_table = &mut TranspositionTable::new(1);
+            let mut search = create_search_context(&mut position, transposition_table);
+            let score = search.quiescence_search(0, -MAXIMUM_SCORE, MAXIMUM_SCORE);
+            assert_eq!(score, QUIESCENCE_MAXIMUM_SCORE - 1);
+            assert!(!Search::is_mating_score(score));
+
+            let score = search.quiescence_search(4, -MAXIMUM_SCORE, MAXIMUM_SCORE);
+            assert_eq!(score, QUIESCENCE_MAXIMUM_SCORE - 5);
+        }
+
+        #[test]
+        fn test_in_check_is_scored_from_the_positions_after_the_evasions() {
+            // white is in check and a rook down with only king moves to escape
+            let fen = "4r1k1/8/8/8/4r3/8/8/4K2R w - - 0 1";
+            let mut position: Position = Position::from(fen);
+            let transposition_table = &mut TranspositionTable::new(1);
+            let mut search = create_search_context(&mut position, transposition_table);
+            let score = search.quiescence_search(0, -MAXIMUM_SCORE, MAXIMUM_SCORE);
+            assert!(!Search::is_mating_score(score));
+            assert!(score < -300 && score > -700, "{score}");
+
+            // in check the only evasion captures the queen so the score is the quiet position's
+            let fen = "4k3/8/8/8/8/8/3q4/4K3 w - - 0 1";
+            let mut position: Position = Position::from(fen);
+            let mut search = create_search_context(&mut position, transposition_table);
+            let score = search.quiescence_search(0, -MAXIMUM_SCORE, MAXIMUM_SCORE);
+            assert!(score.abs() < 100, "{score}");
+        }
+
+        #[test]
+        fn test_stand_pat_margin() {
+            let fen = "4k3/8/8/3p4/8/8/3R4/4K3 w - - 0 1";
+            let mut position: Position = Position::from(fen);
+            let static_score = score_position(&position);
+            let transposition_table = &mut TranspositionTable::new(1);
+            let mut search = create_search_context(&mut position, transposition_table);
+            search.tuning.quiescence_stand_pat_margin = 1000;
+            let score = search.quiescence_search(0, -MAXIMUM_SCORE, MAXIMUM_SCORE);
+            assert_eq!(score, static_score + 1000);
+        }
+
+        #[test]
+        fn test_depth_cap_returns_static_evaluation() {
+            // a long chain of captures on d5 with white a queen up
+            let fen = "3rk3/3r4/2n1n3/3p4/4P3/2N1NQ2/3R4/3RK3 w - - 0 1";
+            let mut position: Position = Position::from(fen);
+            let static_score = score_position(&position);
+            let transposition_table = &mut TranspositionTable::new_using_config();
+            let mut search = create_search_context(&mut position, transposition_table);
+            search.max_quiescence_depth = 2;
+            let score = search.quiescence_search(3, -MAXIMUM_SCORE, MAXIMUM_SCORE);
+            assert_eq!(score, static_score);
+            assert_ne!(score, 0);
+
+            let score = search.quiescence_search(0, -MAXIMUM_SCORE, MAXIMUM_SCORE);
+            assert!(score > 500);
+        }
     }
 }
@@ -4,7 +4,9 @@ use crate::core::position::Position;
 use crate::core::r#move::{BaseMove, Move};
 pub use crate::search::negamax::MAXIMUM_SCORE;
 use crate::uci::config;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+const GENERATION_MASK: u8 = 0x1F;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BoundType {
@@ -13,6 +15,18 @@ pub enum BoundType {
     UpperBound,
 }
 
+impl BoundType {
+    pub fn from_score(score: i32, alpha: i32, beta: i32) -> BoundType {
+        if score <= alpha {
+            BoundType::UpperBound
+        } else if score >= beta {
+            BoundType::LowerBound
+        } else {
+            BoundType::Exact
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct TTEntry {
     pub zobrist: u64,
@@ -20,12 +34,14 @@ pub struct TTEntry {
     pub depth: u8,
     pub score: i32,
     pub bound_type: BoundType,
+    pub generation: u8,
 }
 
 pub struct TranspositionTable {
     table: Vec<AtomicU64>,
     size: usize,
     size_in_mb: usize,
+    generation: AtomicU8,
 }
 
 impl TranspositionTable {
@@ -41,7 +57,7 @@ impl TranspositionTable {
             Self::bytes_to_gib(table_size_in_bytes)
         );
         let table = (0..actual_num_entries * 2).map(|_| AtomicU64::new(0)).collect(); // Using 2 u64 per entry
-        Self { table, size: actual_num_entries, size_in_mb }
+        Self { table, size: actual_num_entries, size_in_mb, generation: AtomicU8::new(0) }
     }
 
     pub fn new_using_config() -> Self {
@@ -57,16 +73,11 @@ impl TranspositionTable {
         score: i32,
         mov: Option<Move>,
     ) {
-        let bound_type = if score <= alpha {
-            BoundType::UpperBound
-        } else if score >= beta {
-            BoundType::LowerBound
-        } else {
-            BoundType::Exact
-        };
+        let bound_type = BoundType::from_score(score, alpha, beta);
         let do_store = {
             if let Some(current_entry) = self.probe(position.hash_code()) {
-                depth > current_entry.depth
+                current_entry.generation != self.generation()
+                    || depth > current_entry.depth
                     || (depth == current_entry.depth
                         && ((bound_type == BoundType::Exact
                             && current_entry.bound_type != BoundType::Exact)
@@ -86,10 +97,22 @@ impl TranspositionTable {
                 assert_eq!(entry.depth, depth);
                 assert_eq!(entry.score, score);
                 assert_eq!(entry.bound_type, bound_type);
+                assert_eq!(entry.generation, self.generation());
             }
         }
     }
 
+    /// Starts a new search so entries stored by earlier searches are replaced in preference to
+    /// entries from the current one
+    pub fn new_search(&self) {
+        let generation = (self.generation() + 1) & GENERATION_MASK;
+        self.generation.store(generation, Ordering::Relaxed);
+    }
+
+    pub fn generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
     pub fn size(&self) -> usize {
         self.size
     }
@@ -106,7 +129,7 @@ impl TranspositionTable {
         bound: BoundType,
     ) {
         let index = (zobrist as usize) % self.size;
-        let packed = Self::pack_entry(zobrist, best_move, depth, score, bound);
+        let packed = Self::pack_entry(zobrist, best_move, depth, score, bound, self.generation());
         self.table[index * 2].store(packed.0, Ordering::Relaxed);
         self.table[index * 2 + 1].store(packed.1, Ordering::Relaxed);
     }
@@ -148,6 +171,7 @@ impl TranspositionTable {
         for atomic in &self.table {
             atomic.store(0, Ordering::Relaxed);
         }
+        self.generation.store(0, Ordering::Relaxed);
     }
 
     fn pack_move(best_move: Move) -> u64 {
@@ -217,12 +241,14 @@ impl TranspositionTable {
         depth: u8,
         score: i32,
         bound: BoundType,
+        generation: u8,
     ) -> (u64, u64) {
         let packed1 = zobrist;
         let packed2 = if let Some(best_move) = best_move { Self::pack_move(best_move) } else { 0 }
             | ((depth as u64) << 21)
             | (((score + MAXIMUM_SCORE) as u64 & 0x0FFFFFFF) << 29)
-            | ((bound as u64) << 57);
+            | ((bound as u64) << 57)
+            | (((generation & GENERATION_MASK) as u64) << 59);
         (packed1, packed2)
     }
 
@@ -232,13 +258,14 @@ impl TranspositionTable {
         let best_move = if has_move { Some(Self::unpack_mv(packed2)) } else { None };
         let depth = ((packed2 >> 21) & 0xFF) as u8;
         let score = ((packed2 >> 29) & 0x0FFFFFFF) as i32 - MAXIMUM_SCORE;
-        let bound = match (packed2 >> 57) & 0x0F {
+        let bound = match (packed2 >> 57) & 0x03 {
             0 => BoundType::Exact,
             1 => BoundType::LowerBound,
             2 => BoundType::UpperBound,
             _ => panic!("Invalid bound"),
         };
-        Some(TTEntry { zobrist, best_move, depth, score, bound_type: bound })
+        let generation = ((packed2 >> 59) as u8) & GENERATION_MASK;
+        Some(TTEntry { zobrist, best_move, depth, score, bound_type: bound, generation })
     }
 }
 
@@ -335,6 +362,7 @@ mod tests {
                 2,
                 21,
                 Exact,
+                GENERATION_MASK,
             );
             let unpacked = TranspositionTable::unpack_entry(packed.0, packed.1).unwrap();
             assert_eq!(unpacked.zobrist, packed1);
@@ -345,18 +373,20 @@ mod tests {
             assert_eq!(unpacked.depth, 2);
             assert_eq!(unpacked.score, 21);
             assert_eq!(unpacked.bound_type, Exact);
+            assert_eq!(unpacked.generation, GENERATION_MASK);
         }
 
         #[test]
         fn test_pack_unpack_without_move() {
             let zobrist: u64 = 123456;
-            let packed = TranspositionTable::pack_entry(zobrist, None, 2, -21, Exact);
+            let packed = TranspositionTable::pack_entry(zobrist, None, 2, -21, Exact, 3);
             let unpacked = TranspositionTable::unpack_entry(packed.0, packed.1).unwrap();
             assert_eq!(unpacked.zobrist, zobrist);
             assert_eq!(unpacked.best_move, None);
             assert_eq!(unpacked.depth, 2);
             assert_eq!(unpacked.score, -21);
             assert_eq!(unpacked.bound_type, Exact);
+            assert_eq!(unpacked.generation, 3);
         }
 
         mod move_packing {
@@ -405,4 +435,32 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_stale_entries_are_replaced() {
+        let t_table = TranspositionTable::new(1);
+        let position = Position::new_game();
+        t_table.insert(&position, 8, -100, 100, 50, None);
+        t_table.insert(&position, 2, -100, 100, 25, None);
+        assert_eq!(t_table.probe(position.hash_code()).unwrap().depth, 8);
+
+        t_table.new_search();
+        t_table.insert(&position, 2, -100, 100, 25, None);
+        let entry = t_table.probe(position.hash_code()).unwrap();
+        assert_eq!(entry.depth, 2);
+        assert_eq!(entry.score, 25);
+        assert_eq!(entry.generation, 1);
+    }
+
+    #[test]
+    fn test_generation_wraps() {
+        let t_table = TranspositionTable::new(1);
+        for _ in 0..=GENERATION_MASK {
+            t_table.new_search();
+        }
+        assert_eq!(t_table.generation(), 0);
+        t_table.new_search();
+        t_table.clear();
+        assert_eq!(t_table.generation(), 0);
+    }
 }
@@ -1,8 +1,14 @@
+use crate::eval::evaluation::EvaluationWeights;
+use crate::eval::psq::PieceSquareTables;
+use crate::search::move_ordering::MoveOrderingWeights;
+use crate::search::negamax::SearchTuning;
+use crate::search::quiescence::MAXIMUM_QUIESCENCE_DEPTH;
+use crate::uci::uci_util::DEFAULT_MOVE_OVERHEAD_MILLIS;
 use clap::{value_parser, Arg, ArgAction, ArgGroup, Command, Parser};
 use dotenv::dotenv;
 use log::LevelFilter;
 use once_cell::sync::Lazy;
-use std::sync::{Mutex, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
 pub const NAME: &str = env!("CARGO_PKG_NAME");
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -13,12 +19,23 @@ pub const GIT_HASH: &str = env!("GIT_HASH");
 pub static FULL_VERSION: Lazy<String> =
     Lazy::new(|| format!("{NAME} {VERSION} (git {GIT_HASH}, {BUILD_DATE})"));
 
+/// The name reported in the UCI `id name` line, identifying the exact build.
+pub static ENGINE_ID: Lazy<String> = Lazy::new(|| format!("{NAME} {VERSION}-{GIT_HASH}"));
+
 pub fn get_log_file() -> String {
-    CONFIG.log_file.clone()
+    RUNTIME_CONFIG.log_file.read().unwrap().clone().unwrap_or_else(|| CONFIG.log_file.clone())
+}
+
+pub fn set_log_file(log_file: &str) {
+    *RUNTIME_CONFIG.log_file.write().unwrap() = Some(log_file.to_string());
 }
 
 pub fn get_log_level() -> LevelFilter {
-    CONFIG.log_level
+    RUNTIME_CONFIG.log_level.read().unwrap().unwrap_or(CONFIG.log_level)
+}
+
+pub fn set_log_level(log_level: LevelFilter) {
+    *RUNTIME_CONFIG.log_level.write().unwrap() = Some(log_level);
 }
 
 pub fn get_version() -> bool {
@@ -33,6 +50,10 @@ pub fn get_uci_commands() -> Option<Vec<String>> {
     CONFIG.uci_commands.clone()
 }
 
+pub fn get_piece_square_tables_file() -> Option<String> {
+    CONFIG.piece_square_tables_file.clone()
+}
+
 pub fn get_own_book() -> bool {
     RUNTIME_CONFIG.own_book.read().unwrap().unwrap_or(CONFIG.own_book)
 }
@@ -49,14 +70,128 @@ pub fn set_book_depth(max_book_depth: usize) {
     *RUNTIME_CONFIG.book_depth.write().unwrap() = Some(max_book_depth);
 }
 
+/// The contempt set is kept in analyse mode and applies again when analyse mode is turned off
 pub fn get_contempt() -> i32 {
-    RUNTIME_CONFIG.contempt.read().unwrap().unwrap_or(0)
+    contempt_in_mode(RUNTIME_CONFIG.contempt.read().unwrap().unwrap_or(0), get_analyse_mode())
+}
+
+/// Analysis wants objective scores so there is no contempt in analyse mode
+pub fn contempt_in_mode(contempt: i32, analyse_mode: bool) -> i32 {
+    if analyse_mode {
+        0
+    } else {
+        contempt
+    }
 }
 
 pub fn set_contempt(contempt: i32) {
     *RUNTIME_CONFIG.contempt.write().unwrap() = Some(contempt);
 }
 
+pub fn get_move_overhead() -> usize {
+    RUNTIME_CONFIG.move_overhead.read().unwrap().unwrap_or(DEFAULT_MOVE_OVERHEAD_MILLIS)
+}
+
+pub fn set_move_overhead(move_overhead: usize) {
+    *RUNTIME_CONFIG.move_overhead.write().unwrap() = Some(move_overhead);
+}
+
+pub fn get_reduce_losing_captures() -> bool {
+    RUNTIME_CONFIG.reduce_losing_captures.read().unwrap().unwrap_or(false)
+}
+
+pub fn set_reduce_losing_captures(reduce_losing_captures: bool) {
+    *RUNTIME_CONFIG.reduce_losing_captures.write().unwrap() = Some(reduce_losing_captures);
+}
+
+pub fn get_use_quiescence() -> bool {
+    RUNTIME_CONFIG.use_quiescence.read().unwrap().unwrap_or(true)
+}
+
+pub fn set_use_quiescence(use_quiescence: bool) {
+    *RUNTIME_CONFIG.use_quiescence.write().unwrap() = Some(use_quiescence);
+}
+
+pub fn get_ponder() -> bool {
+    RUNTIME_CONFIG.ponder.read().unwrap().unwrap_or(false)
+}
+
+pub fn set_ponder(ponder: bool) {
+    *RUNTIME_CONFIG.ponder.write().unwrap() = Some(ponder);
+}
+
+pub fn get_analyse_mode() -> bool {
+    RUNTIME_CONFIG.analyse_mode.read().unwrap().unwrap_or(false)
+}
+
+pub fn set_analyse_mode(analyse_mode: bool) {
+    *RUNTIME_CONFIG.analyse_mode.write().unwrap() = Some(analyse_mode);
+}
+
+pub fn get_multi_pv() -> usize {
+    RUNTIME_CONFIG.multi_pv.read().unwrap().unwrap_or(1)
+}
+
+pub fn set_multi_pv(multi_pv: usize) {
+    *RUNTIME_CONFIG.multi_pv.write().unwrap() = Some(multi_pv);
+}
+
+/// The depth searched by a go with no limits. Zero searches until stop as if go infinite was sent.
+pub fn get_go_depth() -> u8 {
+    RUNTIME_CONFIG.go_depth.read().unwrap().unwrap_or(0)
+}
+
+pub fn set_go_depth(go_depth: u8) {
+    *RUNTIME_CONFIG.go_depth.write().unwrap() = Some(go_depth);
+}
+
+pub fn get_max_quiescence_depth() -> u8 {
+    RUNTIME_CONFIG.max_quiescence_depth.read().unwrap().unwrap_or(MAXIMUM_QUIESCENCE_DEPTH)
+}
+
+pub fn set_max_quiescence_depth(max_quiescence_depth: u8) {
+    *RUNTIME_CONFIG.max_quiescence_depth.write().unwrap() = Some(max_quiescence_depth);
+}
+
+pub fn get_evaluation_weights() -> EvaluationWeights {
+    RUNTIME_CONFIG.evaluation_weights.read().unwrap().unwrap_or_default()
+}
+
+pub fn set_evaluation_weights(evaluation_weights: EvaluationWeights) {
+    *RUNTIME_CONFIG.evaluation_weights.write().unwrap() = Some(evaluation_weights);
+}
+
+pub fn get_search_tuning() -> SearchTuning {
+    RUNTIME_CONFIG.search_tuning.read().unwrap().unwrap_or_default()
+}
+
+pub fn set_search_tuning(search_tuning: SearchTuning) {
+    *RUNTIME_CONFIG.search_tuning.write().unwrap() = Some(search_tuning);
+}
+
+pub fn get_move_ordering_weights() -> MoveOrderingWeights {
+    RUNTIME_CONFIG.move_ordering_weights.read().unwrap().unwrap_or_default()
+}
+
+pub fn set_move_ordering_weights(move_ordering_weights: MoveOrderingWeights) {
+    *RUNTIME_CONFIG.move_ordering_weights.write().unwrap() = Some(move_ordering_weights);
+}
+
+/// The tables are shared rather than copied because they are read by every evaluation
+pub fn get_piece_square_tables() -> Arc<PieceSquareTables> {
+    RUNTIME_CONFIG
+        .piece_square_tables
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| Arc::clone(&DEFAULT_PIECE_SQUARE_TABLES))
+}
+
+/// None restores the compiled in tables
+pub fn set_piece_square_tables(piece_square_tables: Option<PieceSquareTables>) {
+    *RUNTIME_CONFIG.piece_square_tables.write().unwrap() = piece_square_tables.map(Arc::new);
+}
+
 pub fn get_hash_size() -> usize {
     RUNTIME_CONFIG.hash_size.read().unwrap().unwrap_or(CONFIG.hash_size)
 }
@@ -75,6 +210,18 @@ pub fn get_config_as_string() -> String {
         book_depth: usize,
         hash_size: usize,
         contempt: i32,
+        move_overhead: usize,
+        reduce_losing_captures: bool,
+        use_quiescence: bool,
+        ponder: bool,
+        multi_pv: usize,
+        analyse_mode: bool,
+        go_depth: u8,
+        max_quiescence_depth: u8,
+        evaluation_weights: EvaluationWeights,
+        search_tuning: SearchTuning,
+        move_ordering_weights: MoveOrderingWeights,
+        custom_piece_square_tables: bool,
     }
     let configuration = DynamicConfig {
         log_file: get_log_file(),
@@ -83,6 +230,18 @@ pub fn get_config_as_string() -> String {
         book_depth: get_book_depth(),
         hash_size: get_hash_size(),
         contempt: get_contempt(),
+        move_overhead: get_move_overhead(),
+        reduce_losing_captures: get_reduce_losing_captures(),
+        use_quiescence: get_use_quiescence(),
+        ponder: get_ponder(),
+        multi_pv: get_multi_pv(),
+        analyse_mode: get_analyse_mode(),
+        go_depth: get_go_depth(),
+        max_quiescence_depth: get_max_quiescence_depth(),
+        evaluation_weights: get_evaluation_weights(),
+        search_tuning: get_search_tuning(),
+        move_ordering_weights: get_move_ordering_weights(),
+        custom_piece_square_tables: RUNTIME_CONFIG.piece_square_tables.read().unwrap().is_some(),
     };
     format!("{configuration:?}")
 }
@@ -97,28 +256,59 @@ pub struct Config {
     pub version: bool,
     pub perft: bool,
     pub uci_commands: Option<Vec<String>>,
+    pub piece_square_tables_file: Option<String>,
 }
 
 #[derive(Debug, Default)]
 struct RuntimeConfig {
+    pub log_file: RwLock<Option<String>>,
+    pub log_level: RwLock<Option<LevelFilter>>,
     pub own_book: RwLock<Option<bool>>,
     pub book_depth: RwLock<Option<usize>>,
     pub hash_size: RwLock<Option<usize>>,
     pub contempt: RwLock<Option<i32>>,
+    pub move_overhead: RwLock<Option<usize>>,
+    pub reduce_losing_captures: RwLock<Option<bool>>,
+    pub use_quiescence: RwLock<Option<bool>>,
+    pub ponder: RwLock<Option<bool>>,
+    pub multi_pv: RwLock<Option<usize>>,
+    pub analyse_mode: RwLock<Option<bool>>,
+    pub go_depth: RwLock<Option<u8>>,
+    pub max_quiescence_depth: RwLock<Option<u8>>,
+    pub evaluation_weights: RwLock<Option<EvaluationWeights>>,
+    pub search_tuning: RwLock<Option<SearchTuning>>,
+    pub move_ordering_weights: RwLock<Option<MoveOrderingWeights>>,
+    pub piece_square_tables: RwLock<Option<Arc<PieceSquareTables>>>,
 }
 
 impl RuntimeConfig {
     pub fn reset(&self) {
+        *self.log_file.write().unwrap() = None;
+        *self.log_level.write().unwrap() = None;
         *self.own_book.write().unwrap() = None;
         *self.book_depth.write().unwrap() = None;
         *self.hash_size.write().unwrap() = None;
         *self.contempt.write().unwrap() = None;
+        *self.move_overhead.write().unwrap() = None;
+        *self.reduce_losing_captures.write().unwrap() = None;
+        *self.use_quiescence.write().unwrap() = None;
+        *self.ponder.write().unwrap() = None;
+        *self.multi_pv.write().unwrap() = None;
+        *self.analyse_mode.write().unwrap() = None;
+        *self.go_depth.write().unwrap() = None;
+        *self.max_quiescence_depth.write().unwrap() = None;
+        *self.evaluation_weights.write().unwrap() = None;
+        *self.search_tuning.write().unwrap() = None;
+        *self.move_ordering_weights.write().unwrap() = None;
+        *self.piece_square_tables.write().unwrap() = None;
     }
 }
 
 static CONFIG: Lazy<Config> = Lazy::new(load_config);
 static RUNTIME_CONFIG: Lazy<RuntimeConfig> = Lazy::new(RuntimeConfig::default);
 static CONFIG_OVERRIDE: Lazy<Mutex<Option<Config>>> = Lazy::new(|| Mutex::new(None));
+static DEFAULT_PIECE_SQUARE_TABLES: Lazy<Arc<PieceSquareTables>> =
+    Lazy::new(|| Arc::new(PieceSquareTables::default()));
 
 fn load_config() -> Config {
     dotenv().ok();
@@ -169,7 +359,7 @@ fn load_config() -> Config {
                     .required(false)
                     .default_value(DEFAULT_BOOK_DEPTH)
                     .value_parser(value_parser!(u16).range(1..))
-                    .help("The maximum full move number of a position that will be considered for the opening book")
+                    .help("The number of plies from the start of the game that are played from the opening book")
                     .env("NATTO_BOOK_DEPTH")
                 )
                 .arg(Arg::new("hash-size").short('s').long("hash-size").action(ArgAction::Set)
@@ -179,6 +369,11 @@ fn load_config() -> Config {
                     .help("the size of the hash table in megabytes - must be a power of two")
                     .env("NATTO_HASH_SIZE")
                 )
+                .arg(Arg::new("pst-file").long("pst-file").action(ArgAction::Set)
+                    .required(false)
+                    .help("A JSON file of piece square tables to use instead of the compiled in tables")
+                    .env("NATTO_PST_FILE")
+                )
                 .arg(Arg::new("perft").short('p').long("perft").action(ArgAction::SetTrue)
                     .required(false)
                     .default_value("false")
@@ -213,6 +408,7 @@ fn load_config() -> Config {
                 version: *matches.get_one::<bool>("version").unwrap_or(&false),
                 perft: matches.get_flag("perft"),
                 uci_commands: matches.get_many::<String>("uci").map(|values| values.cloned().collect()),
+                piece_square_tables_file: matches.get_one::<String>("pst-file").cloned(),
             }
         })
 }
@@ -235,6 +431,7 @@ fn is_power_of_two(s: &str) -> Result<String, String> {
 pub mod tests {
     use super::*;
     use ctor::ctor;
+    use serial_test::serial;
 
     #[cfg(test)]
     #[ctor]
@@ -252,6 +449,7 @@ pub mod tests {
             version: false,
             perft: false,
             uci_commands: None,
+            piece_square_tables_file: None,
         }
     }
 
@@ -267,7 +465,7 @@ pub mod tests {
 
     #[test]
     fn test_get_perft() {
-        assert_eq!(get_perft(), false);
+        assert!(!get_perft());
     }
 
     #[test]
@@ -277,18 +475,20 @@ pub mod tests {
 
     #[test]
     fn test_read_write_use_book() {
-        assert_eq!(get_own_book(), true);
+        assert!(get_own_book());
         set_own_book(false);
-        assert_eq!(get_own_book(), false);
+        assert!(!get_own_book());
         set_own_book(true);
-        assert_eq!(get_own_book(), true);
+        assert!(get_own_book());
     }
 
     #[test]
+    #[serial]
     fn test_read_write_max_book_depth() {
         assert_eq!(get_book_depth(), 10);
         set_book_depth(20);
         assert_eq!(get_book_depth(), 20);
+        set_book_depth(10);
     }
 
     #[test]
@@ -304,6 +504,29 @@ pub mod tests {
         assert_eq!(get_contempt(), 0);
         set_contempt(-50);
         assert_eq!(get_contempt(), -50);
-        set_contempt(10);
+        set_contempt(0);
+    }
+
+    #[test]
+    fn test_contempt_in_mode() {
+        assert_eq!(contempt_in_mode(50, false), 50);
+        assert_eq!(contempt_in_mode(50, true), 0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_read_write_max_quiescence_depth() {
+        assert_eq!(get_max_quiescence_depth(), MAXIMUM_QUIESCENCE_DEPTH);
+        set_max_quiescence_depth(20);
+        assert_eq!(get_max_quiescence_depth(), 20);
+        set_max_quiescence_depth(MAXIMUM_QUIESCENCE_DEPTH);
+    }
+
+    #[test]
+    fn test_read_write_log_level() {
+        assert_eq!(get_log_level(), LevelFilter::Info);
+        set_log_level(LevelFilter::Trace);
+        assert_eq!(get_log_level(), LevelFilter::Trace);
+        set_log_level(LevelFilter::Info);
     }
 }
@@ -1,6 +1,7 @@
 use crate::uci::config;
 use chrono::Local;
-use std::fs::File;
+use log::LevelFilter;
+use std::fs::{File, OpenOptions};
 use std::io;
 use std::io::Write;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -10,6 +11,12 @@ pub static LOG_ENABLED: AtomicBool = AtomicBool::new(true);
 
 pub fn configure_logging() -> Result<LoggerController, fern::InitError> {
     let logger_controller = LoggerController::new();
+    if let Err(err) = logger_controller.set_log_file(&config::get_log_file()) {
+        eprintln!(
+            "Failed to open log file {} - logging to stderr only: {err}",
+            config::get_log_file()
+        );
+    }
     setup_logging(&logger_controller)
         .map_err(|err| {
             eprintln!("Failed to initialize logging: {err:?}");
@@ -29,22 +36,47 @@ fn setup_logging(logger_controller: &LoggerController) -> Result<(), fern::InitE
                 message
             ))
         })
-        .level(config::get_log_level())
+        .level(LevelFilter::Trace)
         .chain(io::stderr())
-        .chain(fern::log_file(config::get_log_file().clone())?)
         .filter(|_| LOG_ENABLED.load(Ordering::Relaxed)); // runtime switch
 
-    logger_controller.chain_debug_file(base).apply()?;
+    logger_controller.chain_debug_file(logger_controller.chain_log_file(base)).apply()?;
+    // the dispatch accepts every level so the level can be changed at runtime by set_log_level
+    log::set_max_level(config::get_log_level());
     Ok(())
 }
+
+pub fn set_log_level(log_level: LevelFilter) {
+    config::set_log_level(log_level);
+    log::set_max_level(log_level);
+}
 #[derive(Clone)]
 pub struct LoggerController {
+    log_file: Arc<Mutex<Option<File>>>,
     debug_file: Arc<Mutex<Option<File>>>,
 }
 
 impl LoggerController {
     pub fn new() -> Self {
-        Self { debug_file: Arc::new(Mutex::new(None)) }
+        Self { log_file: Arc::new(Mutex::new(None)), debug_file: Arc::new(Mutex::new(None)) }
+    }
+
+    /// Redirects logging to the given file. If it can't be opened logging continues to stderr only.
+    pub fn set_log_file(&self, path: &str) -> io::Result<()> {
+        let mut slot = self.log_file.lock().unwrap();
+        *slot = None;
+        *slot = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        config::set_log_file(path);
+        Ok(())
+    }
+
+    pub(crate) fn chain_log_file(&self, dispatch: fern::Dispatch) -> fern::Dispatch {
+        let slot = self.log_file.clone();
+        dispatch.chain(fern::Output::call(move |record| {
+            if let Some(ref mut file) = *slot.lock().unwrap() {
+                let _ = writeln!(file, "{}", record.args());
+            }
+        }))
     }
 
     pub fn set_debug_file(&self, path: &str) {
@@ -65,3 +97,36 @@ impl LoggerController {
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::{Level, Log, Record};
+    use std::fs;
+
+    #[test]
+    fn test_log_file_receives_log_lines() {
+        let path = std::env::temp_dir().join("natto_test_log_file.log");
+        let _ = fs::remove_file(&path);
+        let logger_controller = LoggerController::new();
+        logger_controller.set_log_file(path.to_str().unwrap()).unwrap();
+
+        let (_, logger) = logger_controller.chain_log_file(fern::Dispatch::new()).into_log();
+        logger.log(
+            &Record::builder()
+                .args(format_args!("routed to the log file"))
+                .level(Level::Info)
+                .build(),
+        );
+        logger.flush();
+        assert!(fs::read_to_string(&path).unwrap().contains("routed to the log file"));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_unopenable_log_file() {
+        let logger_controller = LoggerController::new();
+        assert!(logger_controller.set_log_file("/nonexistent/directory/natto.log").is_err());
+        assert!(logger_controller.log_file.lock().unwrap().is_none());
+    }
+}
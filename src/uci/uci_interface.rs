@@ -1,24 +1,31 @@
-use crate::book::lichess_book::LiChessOpeningBook;
+use crate::book;
+use crate::book::lichess_book::{self, LiChessOpeningBook};
 use crate::book::opening_book::OpeningBook;
 use crate::core::move_gen;
+use crate::core::piece::PieceType;
 use crate::core::r#move;
-use crate::search::negamax::Search;
+use crate::eval::evaluation::EvaluationWeights;
+use crate::eval::psq::PieceSquareTables;
+use crate::search::move_ordering::MoveOrderingWeights;
+use crate::search::negamax::{RepetitionKey, Search, SearchTuning};
 use crate::search::transposition_table::TranspositionTable;
-use crate::search::{move_ordering, negamax};
+use crate::search::{move_ordering, negamax, quiescence};
 use crate::uci::logging::LoggerController;
 use crate::uci::{config, logging, uci_util};
 use crate::utils;
-use crate::utils::fen;
+use crate::utils::{bench, fen};
 use dotenv::dotenv;
-use log::{debug, error, info};
+use log::{debug, error, info, warn, LevelFilter};
 use std::cell::RefCell;
 use std::io::BufRead;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Receiver, Sender};
-use std::sync::{mpsc, Arc};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread::JoinHandle;
 use std::{io, thread};
 
+const MAXIMUM_PIECE_VALUE: i32 = 2000;
+
 pub fn run() {
     dotenv().ok();
     let logger_controller = logging::configure_logging();
@@ -30,6 +37,9 @@ pub fn run() {
         utils::perf_t::perf_t();
     } else {
         info!("Starting uci");
+        if let Some(path) = config::get_piece_square_tables_file() {
+            Engine::load_piece_square_tables(&path);
+        }
         Engine::new(logger_controller.ok()).run();
         info!("Engine exited cleanly");
     }
@@ -40,11 +50,16 @@ enum UciCommand {
     SetOption(String),
     LogConfig,
     IsReady,
+    Register,
     UciNewGame,
     Position(String),
     Go(Option<String>),
+    PonderHit,
     Stop,
     Quit,
+    Bench,
+    Flip,
+    Stats,
     None,
 }
 impl UciCommand {
@@ -55,11 +70,16 @@ impl UciCommand {
             Some("setoption") => UciCommand::SetOption(input.to_string()),
             Some("logconfig") => UciCommand::LogConfig,
             Some("isready") => UciCommand::IsReady,
+            Some("register") => UciCommand::Register,
             Some("ucinewgame") => UciCommand::UciNewGame,
             Some("position") => UciCommand::Position(parts.next().unwrap().to_string()),
             Some("go") => UciCommand::Go(parts.next().map(|s| s.to_string())),
+            Some("ponderhit") => UciCommand::PonderHit,
             Some("stop") => UciCommand::Stop,
             Some("quit") => UciCommand::Quit,
+            Some("bench") => UciCommand::Bench,
+            Some("flip") => UciCommand::Flip,
+            Some("stats") => UciCommand::Stats,
             _ => UciCommand::None,
         }
     }
@@ -69,9 +89,12 @@ struct Engine {
     channel: (Sender<String>, Receiver<String>),
     search_stop_flag: Arc<AtomicBool>,
     main_loop_quit_flag: Arc<AtomicBool>,
-    opening_book: LiChessOpeningBook,
+    opening_book: Box<dyn OpeningBook>,
     transposition_table: RefCell<Arc<TranspositionTable>>,
+    pending_transposition_table: RefCell<Option<JoinHandle<TranspositionTable>>>,
     logger_controller: Option<LoggerController>,
+    /// The beta cutoff counts by move index of the last search, for the stats command
+    cutoff_counts: Arc<Mutex<Vec<usize>>>,
 }
 
 impl Engine {
@@ -80,9 +103,11 @@ impl Engine {
             channel: mpsc::channel(),
             search_stop_flag: Arc::new(AtomicBool::new(false)),
             main_loop_quit_flag: Arc::new(AtomicBool::new(false)),
-            opening_book: LiChessOpeningBook::new(),
+            opening_book: Box::new(LiChessOpeningBook::new()),
             transposition_table: RefCell::new(Arc::new(TranspositionTable::new_using_config())),
+            pending_transposition_table: RefCell::new(None),
             logger_controller,
+            cutoff_counts: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -144,8 +169,15 @@ impl Engine {
             UciCommand::SetOption(input) => self.uci_set_option(&input),
             UciCommand::LogConfig => println!("{}", config::get_config_as_string()),
             UciCommand::IsReady => self.uci_is_ready(),
+            UciCommand::Register => self.uci_register(),
+            UciCommand::PonderHit => self.uci_ponder_hit(search_handle),
             UciCommand::Stop => self.uci_stop(&self.search_stop_flag, search_handle),
-            UciCommand::Quit => self.uci_quit(&self.search_stop_flag, &self.main_loop_quit_flag),
+            UciCommand::Quit => {
+                self.uci_quit(&self.search_stop_flag, &self.main_loop_quit_flag, search_handle)
+            }
+            UciCommand::Bench => self.uci_bench(search_handle),
+            UciCommand::Flip => self.uci_flip(uci_position),
+            UciCommand::Stats => self.uci_stats(),
             UciCommand::UciNewGame => self.uci_new_game(uci_position, search_handle),
             UciCommand::Position(_position_str) => {
                 self.uci_set_position(&input.to_string(), uci_position)
@@ -180,7 +212,9 @@ impl Engine {
         info!("UCI new game command received");
         if search_handle.is_none() {
             *uci_position = None;
-            if self.transposition_table.borrow().size_in_mb() == config::get_hash_size() {
+            if self.install_pending_transposition_table() {
+                info!("Position cleared and resized transposition table installed");
+            } else if self.transposition_table.borrow().size_in_mb() == config::get_hash_size() {
                 self.transposition_table.borrow_mut().clear();
                 info!("Position and transposition table cleared");
             } else {
@@ -206,24 +240,42 @@ impl Engine {
         uci_position: &Option<uci_util::UciPosition>,
     ) {
         self.uci_stop(search_stop_flag, search_handle);
+        let uci_go_options: uci_util::UciGoOptions =
+            uci_util::parse_uci_go_options(Some(input.clone()))
+                .with_go_depth(config::get_go_depth());
+        if uci_go_options.ponder && !config::get_ponder() {
+            warn!("Ignoring go ponder because the Ponder option is off: {input}");
+            uci_util::send_to_gui("info string go ponder ignored because the Ponder option is off");
+            return;
+        }
         if let Some(uci_pos) = uci_position {
             if search_handle.is_none() {
+                // the book lookup blocks the main loop so a stop sent during it is handled after the
+                // book move has been sent, or after the search below has been started
                 if !self.play_move_from_opening_book(uci_pos) {
-                    let uci_go_options: uci_util::UciGoOptions =
-                        uci_util::parse_uci_go_options(Some(input.clone()));
                     debug!("go options = {uci_go_options:?}");
 
-                    let search_params = uci_util::create_search_params(&uci_go_options, uci_pos);
+                    let search_params = uci_util::create_search_params(
+                        &uci_go_options,
+                        uci_pos,
+                        config::get_move_overhead(),
+                    );
 
                     debug!("search params = {search_params:?}");
                     debug!("Starting search...");
                     search_stop_flag.store(false, Ordering::Relaxed); // Reset stop flag
+                    self.install_pending_transposition_table();
 
                     let stop_flag = Arc::clone(search_stop_flag);
                     let uci_pos_clone = uci_pos.clone();
                     let mut position = uci_pos_clone.end_position;
                     let transposition_table = Arc::clone(&self.transposition_table.borrow());
+                    let cutoff_counts = Arc::clone(&self.cutoff_counts);
+                    #[cfg(test)]
+                    let gui_output = uci_util::gui_output_capture();
                     *search_handle = Some(thread::spawn(move || {
+                        #[cfg(test)]
+                        uci_util::set_gui_output_capture(gui_output);
                         let mut search = Search::new(
                             &mut position,
                             &transposition_table,
@@ -235,6 +287,7 @@ impl Engine {
                         );
                         let search_results = search.go();
                         debug!("score: {} depth {}", search_results.score, search_results.depth);
+                        *cutoff_counts.lock().unwrap() = search.cutoff_counts().to_vec();
 
                         let best_move = search_results
                             .pv
@@ -271,73 +324,443 @@ impl Engine {
         uci_util::send_to_gui("readyok");
     }
 
+    fn uci_bench(&self, search_handle: &mut Option<JoinHandle<()>>) {
+        self.uci_stop(&self.search_stop_flag, search_handle);
+        let stats = bench::bench();
+        uci_util::send_to_gui(&format!(
+            "{} nodes {} nps",
+            stats.node_count, stats.nodes_per_second
+        ));
+    }
+
+    fn uci_stats(&self) {
+        uci_util::send_to_gui(&Search::format_cutoff_statistics(
+            &self.cutoff_counts.lock().unwrap(),
+        ));
+    }
+
+    fn uci_flip(&self, uci_position: &mut Option<uci_util::UciPosition>) {
+        let Some(uci_pos) = uci_position else {
+            uci_util::send_to_gui("info string flip needs a position to be set first");
+            return;
+        };
+        if move_gen::is_check(&uci_pos.end_position) {
+            uci_util::send_to_gui(
+                "info string flip is not possible when the side to move is in check",
+            );
+            return;
+        }
+        let mut flipped_position = uci_pos.end_position;
+        flipped_position.flip_side_to_move();
+        info!("Flipped side to move giving position [{}]", fen::write(&flipped_position));
+        *uci_position = Some(uci_util::UciPosition {
+            given_position: flipped_position,
+            end_position: flipped_position,
+            position_move_pairs: Some(vec![]),
+            repetition_keys: vec![RepetitionKey::new(&flipped_position)],
+        });
+    }
+
+    fn uci_register(&self) {
+        info!("Registration is not required");
+        uci_util::send_to_gui("register later");
+    }
+
+    // the table is allocated on a separate thread so that setoption returns straight away - it is
+    // installed by the next new game or search, whichever comes first
+    fn allocate_transposition_table(&self, size_in_mb: usize) {
+        self.pending_transposition_table
+            .replace(Some(thread::spawn(move || TranspositionTable::new(size_in_mb))));
+    }
+
+    fn install_pending_transposition_table(&self) -> bool {
+        if let Some(handle) = self.pending_transposition_table.take() {
+            let transposition_table = handle.join().unwrap();
+            info!("Installing transposition table of {} MiB", transposition_table.size_in_mb());
+            self.transposition_table.replace(Arc::new(transposition_table));
+            true
+        } else {
+            false
+        }
+    }
+
     fn uci_options() {
-        uci_util::send_to_gui(format!("id name {}", config::FULL_VERSION.as_str()).as_str());
-        uci_util::send_to_gui(format!("id author {}", config::AUTHORS).as_str());
-        uci_util::send_to_gui("option name Debug Log File type string default");
-        uci_util::send_to_gui("option name ownbook type check default true");
-        uci_util::send_to_gui("option name bookdepth type spin default 10 min 1 max 50");
-        uci_util::send_to_gui(&format!("option name hash type combo default {} var 64 var 128 var 256 var 512 var 1024 var 2048", config::get_hash_size()));
-        uci_util::send_to_gui("option name enablelog type check default true");
-        uci_util::send_to_gui("uciok");
+        for line in Self::uci_option_lines() {
+            uci_util::send_to_gui(&line);
+        }
+    }
+
+    fn uci_option_lines() -> Vec<String> {
+        let mut lines = vec![
+            format!("id name {}", config::ENGINE_ID.as_str()),
+            format!("id author {}", config::AUTHORS),
+            "option name Debug Log File type string default".to_string(),
+            "option name ownbook type check default true".to_string(),
+            "option name bookdepth type spin default 10 min 1 max 50".to_string(),
+            format!("option name hash type combo default {} var 64 var 128 var 256 var 512 var 1024 var 2048", config::get_hash_size()),
+            "option name enablelog type check default true".to_string(),
+            format!("option name UCI_LogFile type string default {}", config::get_log_file()),
+            format!(
+                "option name UCI_LogLevel type combo default {} var off var error var warn var info var debug var trace",
+                config::get_log_level().as_str().to_lowercase()
+            ),
+            format!(
+                "option name quiescencedepth type spin default {} min 1 max {}",
+                config::get_max_quiescence_depth(),
+                quiescence::MAXIMUM_QUIESCENCE_DEPTH
+            ),
+            "option name Clear Hash type button".to_string(),
+            "option name LosingCaptureReduction type check default false".to_string(),
+            "option name UseQuiescence type check default true".to_string(),
+            "option name Ponder type check default false".to_string(),
+            format!("option name MultiPV type spin default 1 min 1 max {}", uci_util::MAXIMUM_MULTI_PV),
+            "option name UCI_AnalyseMode type check default false".to_string(),
+            format!("option name GoDepth type spin default 0 min 0 max {}", negamax::MAXIMUM_SEARCH_DEPTH),
+            "option name PieceSquareTables type string default".to_string(),
+            format!(
+                "option name Move Overhead type spin default {} min 0 max {}",
+                uci_util::DEFAULT_MOVE_OVERHEAD_MILLIS,
+                uci_util::MAXIMUM_MOVE_OVERHEAD_MILLIS
+            ),
+        ];
+        let mut default_weights = EvaluationWeights::default();
+        for name in [
+            "bishoppair",
+            "passedpawn",
+            "kingsafety",
+            "doubledpawn",
+            "isolatedpawn",
+            "pawnvalue",
+            "knightvalue",
+            "bishopvalue",
+            "rookvalue",
+            "queenvalue",
+        ] {
+            let (default_value, min, max) = Self::evaluation_weight(&mut default_weights, name)
+                .map(|(weight, min, max)| (*weight, min, max))
+                .unwrap();
+            lines.push(format!(
+                "option name {name} type spin default {default_value} min {min} max {max}"
+            ));
+        }
+        let mut default_search_tuning = SearchTuning::default();
+        for name in [
+            "aspirationwindow",
+            "futilitymargin",
+            "futilitydepth",
+            "seethreshold",
+            "standpatmargin",
+        ] {
+            let (default_value, min, max) =
+                Self::search_tuning_parameter(&mut default_search_tuning, name)
+                    .map(|(parameter, min, max)| (*parameter, min, max))
+                    .unwrap();
+            lines.push(format!(
+                "option name {name} type spin default {default_value} min {min} max {max}"
+            ));
+        }
+        let mut default_move_ordering_weights = MoveOrderingWeights::default();
+        for name in ["hashmovescore", "capturescore", "killerscore", "historypercentage"] {
+            let (default_value, min, max) =
+                Self::move_ordering_weight(&mut default_move_ordering_weights, name)
+                    .map(|(weight, min, max)| (*weight, min, max))
+                    .unwrap();
+            lines.push(format!(
+                "option name {name} type spin default {default_value} min {min} max {max}"
+            ));
+        }
+        lines.push("uciok".to_string());
+        lines
     }
 
     fn parse_uci_option(input: &str) -> Option<(String, String)> {
-        let re = regex::Regex::new(r"^setoption name (.+?) value (.+)$").unwrap();
+        let re =
+            regex::Regex::new(r"(?i)^\s*setoption\s+name\s+(.+?)(?:\s+value(?:\s+(.*?))?)?\s*$")
+                .unwrap();
         if let Some(captures) = re.captures(input) {
-            let name = captures.get(1).unwrap().as_str();
-            let value = captures.get(2).unwrap().as_str();
-            Some((name.trim().to_string(), value.trim().to_string()))
+            let name = captures.get(1).unwrap().as_str().split_whitespace().collect::<Vec<_>>();
+            let value = captures.get(2).map_or("", |m| m.as_str());
+            Some((name.join(" "), value.trim().to_string()))
         } else {
             error!("Failed to parse UCI option: {input}");
             None
         }
     }
+    /// Uses the piece square tables in the file, or the compiled in tables if there is no file or
+    /// it can't be used
+    fn load_piece_square_tables(path: &str) {
+        if path.is_empty() {
+            info!("Using the default piece square tables");
+            config::set_piece_square_tables(None);
+            return;
+        }
+        match PieceSquareTables::from_file(path) {
+            Ok(piece_square_tables) => {
+                info!("Using the piece square tables from {path}");
+                config::set_piece_square_tables(Some(piece_square_tables));
+            }
+            Err(err) => {
+                error!("Failed to load the piece square tables from {path}: {err}");
+                config::set_piece_square_tables(None);
+                uci_util::send_to_gui(&format!(
+                    "info string Unable to load piece square tables from {path} ({err}) - using the default tables"
+                ));
+            }
+        }
+    }
+
     fn uci_set_option(&self, input: &str) {
         if let Some((name, value)) = Self::parse_uci_option(input) {
-            match name.to_lowercase().as_str() {
-                "debug log file" => {
-                    if let Some(logger_controller) = &self.logger_controller {
-                        info!("Setting debug log file to {value}");
-                        logger_controller.set_debug_file(value.as_str());
-                    }
+            if !self.set_option(&name, &value) {
+                uci_util::send_to_gui(&format!("info string unknown option {name}"));
+            }
+        }
+        info!("{}", config::get_config_as_string());
+    }
+
+    fn set_option(&self, name: &str, value: &str) -> bool {
+        match name.to_lowercase().as_str() {
+            "debug log file" => {
+                if let Some(logger_controller) = &self.logger_controller {
+                    info!("Setting debug log file to {value}");
+                    logger_controller.set_debug_file(value);
                 }
-                "hash" => {
-                    if let Ok(v) = value.parse::<usize>() {
-                        info!("Setting hash size to {value}");
-                        config::set_hash_size(v);
-                    }
+            }
+            "clear hash" => {
+                info!("Clearing the transposition table");
+                self.transposition_table.borrow().clear();
+            }
+            "hash" => {
+                if let Ok(v) = value.parse::<usize>() {
+                    info!("Setting hash size to {value}");
+                    config::set_hash_size(v);
+                    self.allocate_transposition_table(v);
                 }
-                "ownbook" => {
-                    if let Ok(v) = value.parse::<bool>() {
-                        info!("Setting own book to {v}");
-                        config::set_own_book(v);
-                    }
+            }
+            "ownbook" | "usebook" => {
+                if let Ok(v) = value.to_lowercase().parse::<bool>() {
+                    info!("Setting own book to {v}");
+                    config::set_own_book(v);
                 }
-                "bookdepth" => {
-                    if let Ok(v) = value.parse::<usize>() {
-                        info!("Setting book depth to {value}");
-                        config::set_book_depth(v);
-                    }
+            }
+            "bookdepth" => {
+                if let Ok(v) = value.parse::<usize>() {
+                    info!("Setting book depth to {value}");
+                    config::set_book_depth(v);
+                }
+            }
+            "quiescencedepth" => {
+                if let Ok(v) = value.parse::<u8>() {
+                    info!("Setting maximum quiescence depth to {value}");
+                    config::set_max_quiescence_depth(
+                        v.clamp(1, quiescence::MAXIMUM_QUIESCENCE_DEPTH),
+                    );
+                }
+            }
+            "losingcapturereduction" => {
+                if let Ok(v) = value.to_lowercase().parse::<bool>() {
+                    info!("Setting losing capture reduction to {v}");
+                    config::set_reduce_losing_captures(v);
+                }
+            }
+            "ponder" => {
+                if let Ok(v) = value.to_lowercase().parse::<bool>() {
+                    info!("Setting ponder to {v}");
+                    config::set_ponder(v);
+                }
+            }
+            "piecesquaretables" => Self::load_piece_square_tables(value),
+            "randomseed" => {
+                if let Ok(v) = value.parse::<u64>() {
+                    info!("Setting the opening book random seed to {v}");
+                    lichess_book::set_random_seed(v);
+                }
+            }
+            "uci_analysemode" => {
+                if let Ok(v) = value.to_lowercase().parse::<bool>() {
+                    info!("Setting analyse mode to {v}");
+                    config::set_analyse_mode(v);
+                }
+            }
+            "godepth" => {
+                if let Ok(v) = value.parse::<u8>() {
+                    info!("Setting go depth to {value}");
+                    config::set_go_depth(v.min(negamax::MAXIMUM_SEARCH_DEPTH as u8));
+                }
+            }
+            "multipv" => {
+                if let Ok(v) = value.parse::<usize>() {
+                    info!("Setting multi pv to {value}");
+                    config::set_multi_pv(v.clamp(1, uci_util::MAXIMUM_MULTI_PV));
+                }
+            }
+            "usequiescence" => {
+                if let Ok(v) = value.to_lowercase().parse::<bool>() {
+                    info!("Setting use quiescence to {v}");
+                    config::set_use_quiescence(v);
+                }
+            }
+            "move overhead" => {
+                if let Ok(v) = value.parse::<usize>() {
+                    info!("Setting move overhead to {value}ms");
+                    config::set_move_overhead(v.min(uci_util::MAXIMUM_MOVE_OVERHEAD_MILLIS));
                 }
-                "enablelog" => {
-                    if let Ok(v) = value.parse::<bool>() {
-                        info!("Setting enable logging to {value}");
-                        logging::LOG_ENABLED.store(v, Ordering::Relaxed);
+            }
+            "enablelog" => {
+                if let Ok(v) = value.to_lowercase().parse::<bool>() {
+                    info!("Setting enable logging to {value}");
+                    logging::LOG_ENABLED.store(v, Ordering::Relaxed);
+                }
+            }
+            "uci_logfile" => {
+                if let Some(logger_controller) = &self.logger_controller {
+                    info!("Setting log file to {value}");
+                    if let Err(err) = logger_controller.set_log_file(value) {
+                        error!("Failed to open log file {value}: {err}");
+                        uci_util::send_to_gui(&format!(
+                            "info string Unable to open log file {value} ({err}) - logging to stderr only"
+                        ));
                     }
                 }
-                _ => {
-                    uci_util::send_to_gui(&format!("info string Unknown option: {name}"));
+            }
+            "uci_loglevel" => {
+                if let Ok(v) = value.parse::<LevelFilter>() {
+                    info!("Setting log level to {v}");
+                    logging::set_log_level(v);
+                }
+            }
+            _ => {
+                let mut weights = config::get_evaluation_weights();
+                let mut search_tuning = config::get_search_tuning();
+                let mut move_ordering_weights = config::get_move_ordering_weights();
+                if let Some((weight, min, max)) =
+                    Self::evaluation_weight(&mut weights, name.to_lowercase().as_str())
+                {
+                    if let Some(v) = Self::parse_spin_value(name, value, min, max) {
+                        info!("Setting evaluation weight {name} to {v}");
+                        *weight = v;
+                        config::set_evaluation_weights(weights);
+                    }
+                } else if let Some((parameter, min, max)) =
+                    Self::search_tuning_parameter(&mut search_tuning, name.to_lowercase().as_str())
+                {
+                    if let Some(v) = Self::parse_spin_value(name, value, min, max) {
+                        info!("Setting search parameter {name} to {v}");
+                        *parameter = v;
+                        config::set_search_tuning(search_tuning);
+                    }
+                } else if let Some((weight, min, max)) = Self::move_ordering_weight(
+                    &mut move_ordering_weights,
+                    name.to_lowercase().as_str(),
+                ) {
+                    if let Some(v) = Self::parse_spin_value(name, value, min, max) {
+                        info!("Setting move ordering weight {name} to {v}");
+                        *weight = v;
+                        config::set_move_ordering_weights(move_ordering_weights);
+                    }
+                } else {
+                    return false;
                 }
             }
         }
-        info!("{}", config::get_config_as_string());
+        true
+    }
+
+    /// A spin value outside the advertised range is clamped to it
+    fn parse_spin_value(name: &str, value: &str, min: i32, max: i32) -> Option<i32> {
+        match value.parse::<i32>() {
+            Ok(v) => Some(v.clamp(min, max)),
+            Err(err) => {
+                error!("Invalid value {value} for option {name}: {err}");
+                uci_util::send_to_gui(&format!(
+                    "info string invalid value {value} for option {name}"
+                ));
+                None
+            }
+        }
+    }
+
+    /// Gives the weight with its advertised minimum and maximum values
+    fn evaluation_weight<'a>(
+        weights: &'a mut EvaluationWeights,
+        name: &str,
+    ) -> Option<(&'a mut i32, i32, i32)> {
+        match name {
+            "bishoppair" => Some((&mut weights.bishop_pair_bonus, 0, 1000)),
+            "passedpawn" => Some((&mut weights.passed_pawn_bonus, 0, 1000)),
+            "kingsafety" => Some((&mut weights.king_safety_percentage, 0, 1000)),
+            "doubledpawn" => Some((&mut weights.doubled_pawn_penalty, 0, 1000)),
+            "isolatedpawn" => Some((&mut weights.isolated_pawn_penalty, 0, 1000)),
+            "pawnvalue" => {
+                Some((&mut weights.piece_values[PieceType::Pawn as usize], 0, MAXIMUM_PIECE_VALUE))
+            }
+            "knightvalue" => Some((
+                &mut weights.piece_values[PieceType::Knight as usize],
+                0,
+                MAXIMUM_PIECE_VALUE,
+            )),
+            "bishopvalue" => Some((
+                &mut weights.piece_values[PieceType::Bishop as usize],
+                0,
+                MAXIMUM_PIECE_VALUE,
+            )),
+            "rookvalue" => {
+                Some((&mut weights.piece_values[PieceType::Rook as usize], 0, MAXIMUM_PIECE_VALUE))
+            }
+            "queenvalue" => {
+                Some((&mut weights.piece_values[PieceType::Queen as usize], 0, MAXIMUM_PIECE_VALUE))
+            }
+            _ => None,
+        }
     }
 
-    fn uci_quit(&self, search_stop_flag: &Arc<AtomicBool>, main_loop_quit_flag: &Arc<AtomicBool>) {
+    /// Gives the parameter with its advertised minimum and maximum values
+    fn search_tuning_parameter<'a>(
+        search_tuning: &'a mut SearchTuning,
+        name: &str,
+    ) -> Option<(&'a mut i32, i32, i32)> {
+        match name {
+            "aspirationwindow" => Some((&mut search_tuning.aspiration_window, 1, 1000)),
+            "futilitymargin" => Some((&mut search_tuning.reverse_futility_margin, 0, 1000)),
+            "futilitydepth" => Some((&mut search_tuning.reverse_futility_max_depth, 0, 10)),
+            "seethreshold" => Some((&mut search_tuning.quiescence_see_threshold, -1000, 1000)),
+            "standpatmargin" => Some((&mut search_tuning.quiescence_stand_pat_margin, -1000, 1000)),
+            _ => None,
+        }
+    }
+
+    /// Gives the weight with its advertised minimum and maximum values, which keep the move
+    /// ordering keys well inside the range of an i32
+    fn move_ordering_weight<'a>(
+        weights: &'a mut MoveOrderingWeights,
+        name: &str,
+    ) -> Option<(&'a mut i32, i32, i32)> {
+        match name {
+            "hashmovescore" => Some((&mut weights.hash_move_score, 0, 100000)),
+            "capturescore" => Some((&mut weights.capture_score_base, 0, 100000)),
+            "killerscore" => Some((&mut weights.killer_score, 0, 100000)),
+            "historypercentage" => Some((&mut weights.history_percentage, 0, 1000)),
+            _ => None,
+        }
+    }
+
+    fn uci_quit(
+        &self,
+        search_stop_flag: &Arc<AtomicBool>,
+        main_loop_quit_flag: &Arc<AtomicBool>,
+        search_handle: &mut Option<JoinHandle<()>>,
+    ) {
         info!("UCI Quit command received. Shutting down...");
-        search_stop_flag.store(true, Ordering::Relaxed);
+        self.uci_stop(search_stop_flag, search_handle);
         main_loop_quit_flag.store(true, Ordering::Relaxed);
+        log::logger().flush();
+    }
+
+    /// A ponder search runs until it is stopped, so when the opponent plays the expected move the
+    /// best move found while pondering is played straight away
+    fn uci_ponder_hit(&self, search_handle: &mut Option<JoinHandle<()>>) {
+        info!("Ponder hit - playing the best move found while pondering");
+        self.uci_stop(&self.search_stop_flag, search_handle);
     }
 
     fn uci_stop(
@@ -370,24 +793,19 @@ impl Engine {
         })
     }
 
+    /// The book isn't used in analyse mode because the user wants the engine's own analysis
     fn play_move_from_opening_book(&self, uci_pos: &uci_util::UciPosition) -> bool {
-        if config::get_own_book() {
-            if uci_pos.end_position.full_move_number() <= config::get_book_depth() {
-                info!(
-                    "getting opening book move for position: {}",
-                    fen::write(&uci_pos.end_position)
+        if config::get_own_book() && !config::get_analyse_mode() {
+            if let Some(opening_move) = book::probe_opening_book(
+                self.opening_book.as_ref(),
+                &uci_pos.end_position,
+                config::get_book_depth(),
+            ) {
+                debug!("got move {opening_move} from opening book");
+                uci_util::send_to_gui(
+                    format!("bestmove {}", r#move::convert_move_to_raw(opening_move)).as_str(),
                 );
-                let opening_move = self.opening_book.get_opening_move(&uci_pos.end_position);
-                if let Ok(opening_move) = opening_move {
-                    debug!("got move {opening_move} from opening book");
-                    uci_util::send_to_gui(format!("bestmove {opening_move}").as_str());
-                    return true;
-                } else {
-                    info!("Failed to retrieve opening book move: {}", opening_move.err().unwrap());
-                }
-            } else {
-                info!("Not playing move from opening book because the full move number {} exceeds the maximum allowed {}",
-                    uci_pos.end_position.full_move_number(), config::get_book_depth());
+                return true;
             }
         }
         false
@@ -397,6 +815,10 @@ impl Engine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::book::opening_book::ErrorKind;
+    use crate::core::position::Position;
+    use crate::core::r#move::RawMove;
+    use serial_test::serial;
 
     #[test]
     fn test_parse_uci_option() {
@@ -404,5 +826,411 @@ mod tests {
             Engine::parse_uci_option("setoption name Debug Log File value /users/me/logfile.log"),
             Some(("Debug Log File".to_string(), "/users/me/logfile.log".to_string()))
         );
+        assert_eq!(
+            Engine::parse_uci_option("setoption name Clear Hash"),
+            Some(("Clear Hash".to_string(), "".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_uci_option_is_tolerant_of_case_and_whitespace() {
+        assert_eq!(
+            Engine::parse_uci_option("  SetOption  Name   Debug   Log File  Value  /tmp/x.log "),
+            Some(("Debug Log File".to_string(), "/tmp/x.log".to_string()))
+        );
+        assert_eq!(
+            Engine::parse_uci_option("setoption name usebook value"),
+            Some(("usebook".to_string(), "".to_string()))
+        );
+        assert_eq!(Engine::parse_uci_option("setoption value 1"), None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_set_option() {
+        let engine = Engine::new(None);
+        assert!(engine.set_option("UseBook", "false"));
+        assert!(!config::get_own_book());
+        assert!(engine.set_option("usebook", "True"));
+        assert!(config::get_own_book());
+
+        assert!(engine.set_option("Hash", &config::get_hash_size().to_string()));
+        assert!(engine.pending_transposition_table.borrow().is_some());
+
+        assert!(engine.set_option("RandomSeed", "42"));
+
+        assert!(!engine.set_option("NoSuchOption", "1"));
+    }
+
+    #[test]
+    fn test_quit_stops_search() {
+        let engine = Engine::new(None);
+        let mut search_handle: Option<JoinHandle<()>> = None;
+        let mut uci_position: Option<uci_util::UciPosition> = None;
+        for input in [
+            "position fen r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 40",
+            "go infinite",
+        ] {
+            let command = UciCommand::from_input(input);
+            engine.run_uci_command(
+                &mut search_handle,
+                &mut uci_position,
+                &input.to_string(),
+                command,
+            );
+        }
+        assert!(search_handle.is_some());
+
+        let start_time = std::time::Instant::now();
+        engine.run_uci_command(
+            &mut search_handle,
+            &mut uci_position,
+            &"quit".to_string(),
+            UciCommand::Quit,
+        );
+        assert!(start_time.elapsed().as_secs() < 5);
+        assert!(search_handle.is_none());
+        assert!(engine.main_loop_quit_flag.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    #[serial]
+    fn test_illegal_book_move_falls_back_to_search() {
+        struct IllegalMoveOpeningBook {}
+        impl OpeningBook for IllegalMoveOpeningBook {
+            fn get_opening_move(&self, _position: &Position) -> Result<RawMove, ErrorKind> {
+                Ok(utils::util::parse_move("e2e5".to_string()).unwrap())
+            }
+        }
+        let mut engine = Engine::new(None);
+        engine.opening_book = Box::new(IllegalMoveOpeningBook {});
+        assert!(config::get_own_book());
+        let mut search_handle: Option<JoinHandle<()>> = None;
+        let mut uci_position: Option<uci_util::UciPosition> = None;
+        for input in ["position startpos", "go depth 1"] {
+            let command = UciCommand::from_input(input);
+            engine.run_uci_command(
+                &mut search_handle,
+                &mut uci_position,
+                &input.to_string(),
+                command,
+            );
+        }
+        assert!(search_handle.take().unwrap().join().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test_go_ponder_requires_the_ponder_option() {
+        let engine = Engine::new(None);
+        let mut search_handle: Option<JoinHandle<()>> = None;
+        let mut uci_position: Option<uci_util::UciPosition> = None;
+        let mut run = |input: &str| {
+            let command = UciCommand::from_input(input);
+            engine.run_uci_command(
+                &mut search_handle,
+                &mut uci_position,
+                &input.to_string(),
+                command,
+            );
+            search_handle.is_some()
+        };
+        let gui_output = uci_util::capture_gui_output();
+        // the position is beyond the book depth so the book isn't used
+        run("position fen r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 30");
+        assert!(!config::get_ponder());
+        assert!(!run("go ponder wtime 1000 btime 1000"));
+        assert_eq!(
+            *gui_output.lock().unwrap(),
+            vec!["info string go ponder ignored because the Ponder option is off".to_string()]
+        );
+        gui_output.lock().unwrap().clear();
+
+        assert!(!run("setoption name Ponder value true"));
+        assert!(run("go ponder wtime 1000 btime 1000"));
+        assert!(matches!(UciCommand::from_input("ponderhit"), UciCommand::PonderHit));
+        assert!(!run("ponderhit"));
+        assert!(gui_output.lock().unwrap().iter().any(|line| line.starts_with("bestmove ")));
+        config::set_ponder(false);
+    }
+
+    #[test]
+    fn test_position_command_replaces_the_repetition_history() {
+        let engine = Engine::new(None);
+        let mut search_handle: Option<JoinHandle<()>> = None;
+        let mut uci_position: Option<uci_util::UciPosition> = None;
+        let mut set_position = |input: &str| {
+            let command = UciCommand::from_input(input);
+            engine.run_uci_command(
+                &mut search_handle,
+                &mut uci_position,
+                &input.to_string(),
+                command,
+            );
+            uci_position.clone().unwrap()
+        };
+        let repeated = set_position(
+            "position fen 4k3/8/8/8/8/8/8/R3K3 w - - 0 1 moves a1a2 e8d8 a2a1 d8e8 a1a2 e8d8 a2a1 d8e8",
+        );
+        assert_eq!(negamax::Search::position_occurrence_count_static(&repeated.repetition_keys), 3);
+
+        // a GUI may send another position without ucinewgame
+        let unrelated = set_position("position fen 4k3/8/8/8/8/8/8/R3K3 w - - 0 1 moves a1a3");
+        assert_eq!(unrelated.repetition_keys.len(), 2);
+        assert_eq!(
+            unrelated.repetition_keys.last().unwrap(),
+            &RepetitionKey::new(&unrelated.end_position)
+        );
+        assert_eq!(
+            negamax::Search::position_occurrence_count_static(&unrelated.repetition_keys),
+            1
+        );
+    }
+
+    #[test]
+    fn test_isready_after_set_hash() {
+        let gui_output = uci_util::capture_gui_output();
+        let engine = Engine::new(None);
+        let mut search_handle: Option<JoinHandle<()>> = None;
+        let mut uci_position: Option<uci_util::UciPosition> = None;
+        let hash_size = config::get_hash_size();
+        let set_hash = format!("setoption name Hash value {hash_size}");
+        engine.run_uci_command(
+            &mut search_handle,
+            &mut uci_position,
+            &set_hash,
+            UciCommand::from_input(&set_hash),
+        );
+        assert!(engine.pending_transposition_table.borrow().is_some());
+
+        engine.run_uci_command(
+            &mut search_handle,
+            &mut uci_position,
+            &"isready".to_string(),
+            UciCommand::from_input("isready"),
+        );
+        assert!(engine.pending_transposition_table.borrow().is_some());
+        assert_eq!(*gui_output.lock().unwrap(), vec!["readyok".to_string()]);
+    }
+
+    #[test]
+    fn test_stats_command() {
+        assert!(matches!(UciCommand::from_input("stats"), UciCommand::Stats));
+        let gui_output = uci_util::capture_gui_output();
+        let engine = Engine::new(None);
+        *engine.cutoff_counts.lock().unwrap() = vec![3, 1];
+        let mut search_handle: Option<JoinHandle<()>> = None;
+        let mut uci_position: Option<uci_util::UciPosition> = None;
+        let stats = "stats".to_string();
+        engine.run_uci_command(&mut search_handle, &mut uci_position, &stats, UciCommand::Stats);
+        assert_eq!(
+            *gui_output.lock().unwrap(),
+            vec!["info string beta cutoffs 4 by move 1:3 (75%) 2:1 (25%)".to_string()]
+        );
+        assert_eq!(*engine.cutoff_counts.lock().unwrap(), vec![3, 1]);
+    }
+
+    #[test]
+    fn test_flip_command() {
+        let engine = Engine::new(None);
+        let mut search_handle: Option<JoinHandle<()>> = None;
+        let mut uci_position: Option<uci_util::UciPosition> = None;
+        let flip = "flip".to_string();
+        engine.run_uci_command(&mut search_handle, &mut uci_position, &flip, UciCommand::Flip);
+        assert!(uci_position.is_none());
+
+        let input = "position startpos moves e2e4".to_string();
+        engine.run_uci_command(
+            &mut search_handle,
+            &mut uci_position,
+            &input,
+            UciCommand::from_input(&input),
+        );
+        assert!(matches!(UciCommand::from_input(&flip), UciCommand::Flip));
+        engine.run_uci_command(&mut search_handle, &mut uci_position, &flip, UciCommand::Flip);
+        let end_position = uci_position.as_ref().unwrap().end_position;
+        assert_eq!(
+            fen::write(&end_position),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1"
+        );
+        assert_eq!(
+            end_position.hash_code(),
+            Position::from(fen::write(&end_position).as_str()).hash_code()
+        );
+    }
+
+    #[test]
+    fn test_register_command() {
+        assert!(matches!(UciCommand::from_input("register later"), UciCommand::Register));
+        assert!(matches!(
+            UciCommand::from_input("register name natto code 1234"),
+            UciCommand::Register
+        ));
+        let gui_output = uci_util::capture_gui_output();
+        Engine::new(None).uci_register();
+        assert_eq!(*gui_output.lock().unwrap(), vec!["register later".to_string()]);
+    }
+
+    #[test]
+    fn test_uci_option_lines() {
+        let lines = Engine::uci_option_lines();
+        assert!(lines.first().unwrap().starts_with("id name natto "));
+        assert_eq!(
+            lines.first().unwrap(),
+            &format!("id name natto {}-{}", config::VERSION, config::GIT_HASH)
+        );
+        assert!(lines[1].starts_with("id author "));
+        assert_eq!(lines.last().unwrap(), "uciok");
+
+        let option_lines: Vec<&String> =
+            lines.iter().filter(|line| line.starts_with("option name ")).collect();
+        assert_eq!(option_lines.len(), lines.len() - 3);
+        let option_names: Vec<String> = option_lines
+            .iter()
+            .map(|line| {
+                let name = line.trim_start_matches("option name ");
+                name[..name.find(" type ").unwrap()].to_lowercase()
+            })
+            .collect();
+        for name in [
+            "debug log file",
+            "clear hash",
+            "hash",
+            "ownbook",
+            "bookdepth",
+            "quiescencedepth",
+            "enablelog",
+            "uci_logfile",
+            "uci_loglevel",
+            "bishoppair",
+            "passedpawn",
+            "kingsafety",
+            "doubledpawn",
+            "isolatedpawn",
+            "pawnvalue",
+            "knightvalue",
+            "bishopvalue",
+            "rookvalue",
+            "queenvalue",
+            "aspirationwindow",
+            "futilitymargin",
+            "futilitydepth",
+            "seethreshold",
+            "standpatmargin",
+            "move overhead",
+            "losingcapturereduction",
+            "usequiescence",
+            "ponder",
+            "multipv",
+            "uci_analysemode",
+            "godepth",
+            "piecesquaretables",
+            "hashmovescore",
+            "capturescore",
+            "killerscore",
+            "historypercentage",
+        ] {
+            assert_eq!(option_names.iter().filter(|n| *n == name).count(), 1, "option {name}");
+        }
+        assert_eq!(option_names.len(), 36);
+
+        let option_line = |name: &str| {
+            option_lines.iter().find(|line| line.starts_with(&format!("option name {name} type ")))
+        };
+        assert_eq!(
+            option_line("bookdepth").unwrap().as_str(),
+            "option name bookdepth type spin default 10 min 1 max 50"
+        );
+        assert_eq!(
+            option_line("quiescencedepth").unwrap().as_str(),
+            format!(
+                "option name quiescencedepth type spin default {} min 1 max {}",
+                config::get_max_quiescence_depth(),
+                quiescence::MAXIMUM_QUIESCENCE_DEPTH
+            )
+        );
+        assert_eq!(
+            option_line("bishoppair").unwrap().as_str(),
+            format!(
+                "option name bishoppair type spin default {} min 0 max 1000",
+                EvaluationWeights::default().bishop_pair_bonus
+            )
+        );
+        assert_eq!(
+            option_line("Clear Hash").unwrap().as_str(),
+            "option name Clear Hash type button"
+        );
+        assert!(option_line("ownbook").unwrap().ends_with("type check default true"));
+        assert_eq!(
+            option_line("Ponder").unwrap().as_str(),
+            "option name Ponder type check default false"
+        );
+        assert_eq!(
+            option_line("MultiPV").unwrap().as_str(),
+            format!(
+                "option name MultiPV type spin default 1 min 1 max {}",
+                uci_util::MAXIMUM_MULTI_PV
+            )
+        );
+    }
+
+    #[test]
+    fn test_search_tuning_parameter() {
+        let mut search_tuning = SearchTuning::default();
+        *Engine::search_tuning_parameter(&mut search_tuning, "futilitymargin").unwrap().0 = 0;
+        *Engine::search_tuning_parameter(&mut search_tuning, "aspirationwindow").unwrap().0 = 25;
+        assert_eq!(search_tuning.reverse_futility_margin, 0);
+        assert_eq!(search_tuning.aspiration_window, 25);
+        assert_eq!(
+            search_tuning.reverse_futility_max_depth,
+            SearchTuning::default().reverse_futility_max_depth
+        );
+        assert!(Engine::search_tuning_parameter(&mut search_tuning, "nullmovereduction").is_none());
+    }
+
+    #[test]
+    fn test_evaluation_weight() {
+        let mut weights = EvaluationWeights::default();
+        *Engine::evaluation_weight(&mut weights, "bishoppair").unwrap().0 = 0;
+        *Engine::evaluation_weight(&mut weights, "kingsafety").unwrap().0 = 50;
+        assert_eq!(weights.bishop_pair_bonus, 0);
+        assert_eq!(weights.king_safety_percentage, 50);
+        assert_eq!(weights.passed_pawn_bonus, EvaluationWeights::default().passed_pawn_bonus);
+        *Engine::evaluation_weight(&mut weights, "bishopvalue").unwrap().0 = 325;
+        assert_eq!(weights.piece_values[PieceType::Bishop as usize], 325);
+        let (_, min, max) = Engine::evaluation_weight(&mut weights, "queenvalue").unwrap();
+        assert_eq!((min, max), (0, MAXIMUM_PIECE_VALUE));
+        assert!(Engine::evaluation_weight(&mut weights, "mobility").is_none());
+    }
+
+    #[test]
+    fn test_parse_spin_value() {
+        let gui_output = uci_util::capture_gui_output();
+        assert_eq!(
+            Engine::parse_spin_value("QueenValue", "1000000", 0, MAXIMUM_PIECE_VALUE),
+            Some(MAXIMUM_PIECE_VALUE)
+        );
+        assert_eq!(Engine::parse_spin_value("FutilityDepth", "-5", 0, 10), Some(0));
+        assert_eq!(Engine::parse_spin_value("BishopPair", "50", 0, 1000), Some(50));
+        assert!(gui_output.lock().unwrap().is_empty());
+
+        assert_eq!(Engine::parse_spin_value("BishopPair", "lots", 0, 1000), None);
+        assert_eq!(
+            *gui_output.lock().unwrap(),
+            vec!["info string invalid value lots for option BishopPair".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_move_ordering_weight() {
+        let mut weights = MoveOrderingWeights::default();
+        *Engine::move_ordering_weight(&mut weights, "killerscore").unwrap().0 = 12000;
+        *Engine::move_ordering_weight(&mut weights, "historypercentage").unwrap().0 = 0;
+        assert_eq!(weights.killer_score, 12000);
+        assert_eq!(weights.history_percentage, 0);
+        assert_eq!(weights.hash_move_score, MoveOrderingWeights::default().hash_move_score);
+        let (_, min, max) = Engine::move_ordering_weight(&mut weights, "hashmovescore").unwrap();
+        assert_eq!(Engine::parse_spin_value("HashMoveScore", "2147483647", min, max), Some(100000));
+        assert!(Engine::move_ordering_weight(&mut weights, "countermovescore").is_none());
     }
 }
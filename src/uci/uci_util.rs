@@ -1,12 +1,15 @@
+use crate::core::move_gen;
 use crate::core::piece::PieceColor::{Black, White};
 use crate::core::position::Position;
 use crate::core::r#move::{Move, RawMove};
+use crate::eval::evaluation;
 use crate::search::move_ordering::MoveOrderer;
 use crate::search::negamax::{
     RepetitionKey, Search, SearchParams, SearchResults, MAXIMUM_SEARCH_DEPTH,
 };
 use crate::search::transposition_table::TranspositionTable;
-use crate::utils::util;
+use crate::uci::config;
+use crate::utils::{fen, util};
 use log::{error, info};
 use once_cell::sync::Lazy;
 use regex::{Captures, Regex};
@@ -17,10 +20,15 @@ use std::sync::Arc;
 include!("../utils/generated_macro.rs");
 
 const DEFAULT_NUMBER_OF_MOVES_TO_GO: usize = 30;
+pub const DEFAULT_MOVE_OVERHEAD_MILLIS: usize = 10;
+pub const MAXIMUM_MOVE_OVERHEAD_MILLIS: usize = 5000;
+pub const MAXIMUM_MULTI_PV: usize = 256;
 
 static UCI_POSITION_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"^position\s+(startpos|fen\s+([^\s]+(?:\s+[^\s]+){5}))(?:\s+moves\s+([\s\w]+))?$")
-        .unwrap()
+    Regex::new(
+        r"^position\s+(startpos|fen\s+([^\s]+(?:\s+[^\s]+){5}))(?:\s+moves(?:\s+([\s\w]*))?)?\s*$",
+    )
+    .unwrap()
 });
 
 #[derive(Clone, Debug)]
@@ -28,6 +36,8 @@ pub struct UciPosition {
     pub given_position: Position,
     pub end_position: Position,
     pub position_move_pairs: Option<Vec<(Position, Move)>>,
+    /// Built from the given position and the moves that follow it. When the GUI sends a FEN without
+    /// the moves that led to it any repetitions of earlier positions can't be detected.
     pub repetition_keys: Vec<RepetitionKey>,
 }
 
@@ -39,6 +49,10 @@ impl UciPosition {
         [vec![self.given_position].as_slice(), game_positions.as_slice()].concat()
     }
 
+    pub fn repetition_count(&self) -> usize {
+        evaluation::repetition_count(&self.repetition_keys)
+    }
+
     pub fn previous_move_from_position(&self) -> Option<Move> {
         let position_move_pairs = self.position_move_pairs.as_ref()?;
         let (last, rest) = position_move_pairs.as_slice().split_last()?;
@@ -60,6 +74,29 @@ pub struct UciGoOptions {
     pub search_moves: Option<Vec<RawMove>>,
 }
 
+impl UciGoOptions {
+    /// True for a go sent without any limit, which would otherwise search until stop
+    pub fn is_bare(&self) -> bool {
+        self.time.iter().chain(self.inc.iter()).all(Option::is_none)
+            && self.moves_to_go.is_none()
+            && self.depth.is_none()
+            && self.nodes.is_none()
+            && self.mate.is_none()
+            && self.move_time.is_none()
+            && !self.ponder
+            && !self.infinite
+    }
+
+    /// A bare go searches to the go depth, or until stop when the go depth is zero
+    pub fn with_go_depth(self, go_depth: u8) -> UciGoOptions {
+        if self.is_bare() && go_depth > 0 {
+            UciGoOptions { depth: Some(go_depth), ..self }
+        } else {
+            self
+        }
+    }
+}
+
 pub(crate) fn parse_uci_go_options(options_string: Option<String>) -> UciGoOptions {
     let mut uci_go_options = UciGoOptions::default();
     if let Some(options_string) = options_string {
@@ -76,9 +113,9 @@ pub(crate) fn parse_uci_go_options(options_string: Option<String>) -> UciGoOptio
         uci_go_options.inc[White as usize] = params.get("winc").copied();
         uci_go_options.inc[Black as usize] = params.get("binc").copied();
         uci_go_options.moves_to_go = params.get("movestogo").copied();
-        uci_go_options.depth = params.get("depth").map(|d| *d as u8);
+        uci_go_options.depth = params.get("depth").map(|d| (*d).min(u8::MAX.into()) as u8);
         uci_go_options.nodes = params.get("nodes").copied();
-        uci_go_options.mate = params.get("mate").map(|d| *d as u8);
+        uci_go_options.mate = params.get("mate").map(|d| (*d).min(u8::MAX.into()) as u8);
         uci_go_options.move_time = params.get("movetime").copied();
 
         let re_flag_options = Regex::new(r"(infinite|ponder)").unwrap();
@@ -100,19 +137,21 @@ pub(crate) fn parse_uci_go_options(options_string: Option<String>) -> UciGoOptio
 
 pub(crate) fn parse_position(input: &str) -> Option<UciPosition> {
     fn create_uci_position(position: &Position, captures: &Captures) -> Option<UciPosition> {
-        captures
-            .get(3)
-            .map_or(Some(vec![]), |m| util::replay_move_string(position, m.as_str().to_string()))
-            .map(|moves| UciPosition {
-                given_position: *position,
-                end_position: if !moves.is_empty() { moves.last().unwrap().0 } else { *position },
-                position_move_pairs: Some(moves),
-                repetition_keys: util::create_repetition_keys(
-                    position,
-                    captures.get(3).map_or("".to_string(), |m| m.as_str().to_string()),
-                )
-                .unwrap(),
-            })
+        let (moves, illegal_move) =
+            util::replay_legal_moves(position, captures.get(3).map_or("", |m| m.as_str()));
+        if let Some(illegal_move) = illegal_move {
+            error!("UCI position has illegal move {illegal_move}: {}", &captures[0]);
+            send_to_gui(&format!(
+                "info string illegal move {illegal_move} - using the position after move {}",
+                moves.len()
+            ));
+        }
+        Some(UciPosition {
+            given_position: *position,
+            end_position: moves.last().map_or(*position, |pm| pm.0),
+            repetition_keys: util::repetition_keys_from_history(position, &moves),
+            position_move_pairs: Some(moves),
+        })
     }
 
     if let Some(captures) = UCI_POSITION_REGEX.captures(input) {
@@ -120,8 +159,14 @@ pub(crate) fn parse_position(input: &str) -> Option<UciPosition> {
             let new_game_position = Position::new_game();
             create_uci_position(&new_game_position, &captures)
         } else if let Some(fen) = captures.get(2) {
-            let fen_position = Position::from(fen.as_str());
-            create_uci_position(&fen_position, &captures)
+            match fen::parse(fen.as_str().to_string()) {
+                Ok(fen_position) => create_uci_position(&fen_position, &captures),
+                Err(error) => {
+                    error!("UCI {error}");
+                    send_to_gui(&format!("info string {error}"));
+                    None
+                }
+            }
         } else {
             None
         }
@@ -131,9 +176,12 @@ pub(crate) fn parse_position(input: &str) -> Option<UciPosition> {
     }
 }
 
+/// The move overhead is the time in milliseconds lost between sending bestmove and the clock
+/// stopping, so it is taken off the time allocated to the move
 pub fn create_search_params(
     uci_go_options: &UciGoOptions,
     uci_position: &UciPosition,
+    move_overhead_millis: usize,
 ) -> SearchParams {
     let allocate_move_time_millis = || -> Option<usize> {
         if uci_go_options.move_time.is_some() {
@@ -159,25 +207,91 @@ pub fn create_search_params(
         }
     };
 
+    // a mate in n moves is found n * 2 - 1 plies deep and the search stops as soon as a mate is
+    // found, so go mate n either proves the mate or refutes it once that depth is complete
     let allocate_max_depth = || -> u8 {
-        let depth = uci_go_options.depth.max(uci_go_options.mate);
-        MAXIMUM_SEARCH_DEPTH.min(depth.map_or(u8::MAX.into(), |d| d.into())) as u8
+        let mate_depth =
+            uci_go_options.mate.map(|moves| (moves as usize * 2).saturating_sub(1).max(1));
+        let depth = uci_go_options.depth.map(usize::from).max(mate_depth);
+        if let Some(depth) = depth.filter(|&depth| depth > MAXIMUM_SEARCH_DEPTH) {
+            send_to_gui(&format!(
+                "info string depth {depth} exceeds the maximum search depth - searching to depth {MAXIMUM_SEARCH_DEPTH}"
+            ));
+        }
+        MAXIMUM_SEARCH_DEPTH.min(depth.unwrap_or(u8::MAX.into())) as u8
     };
 
     let allocate_max_nodes = || -> usize { uci_go_options.nodes.map_or(usize::MAX, |nodes| nodes) };
 
+    // a ponder search is searching the opponent's time so it runs until ponderhit or stop
+    let allocated_time_millis = allocate_move_time_millis()
+        .filter(|_| !uci_go_options.ponder)
+        .map_or(usize::MAX, |mtm| mtm.saturating_sub(move_overhead_millis));
+
     SearchParams {
-        allocated_time_millis: allocate_move_time_millis().map_or(usize::MAX, |mtm| mtm),
+        allocated_time_millis,
         max_depth: allocate_max_depth(),
         max_nodes: allocate_max_nodes(),
+        search_moves: search_moves(uci_go_options, &uci_position.end_position),
     }
 }
 
+/// The legal moves named by go searchmoves. A move that isn't legal is ignored.
+fn search_moves(uci_go_options: &UciGoOptions, position: &Position) -> Vec<Move> {
+    let legal_moves: Vec<Move> = move_gen::generate_moves(position)
+        .into_iter()
+        .filter(|mov| move_gen::is_legal(position, mov))
+        .collect();
+    uci_go_options
+        .search_moves
+        .iter()
+        .flatten()
+        .filter_map(|raw_move| {
+            let search_move = util::find_generated_move(legal_moves.clone(), raw_move);
+            if search_move.is_none() {
+                error!("Ignoring searchmoves move {raw_move} that isn't legal");
+            }
+            search_move
+        })
+        .collect()
+}
+
 pub fn send_to_gui(data: &str) {
+    #[cfg(test)]
+    GUI_OUTPUT.with_borrow(|gui_output| {
+        if let Some(gui_output) = gui_output {
+            gui_output.lock().unwrap().push(data.to_string());
+        }
+    });
     println!("{data}");
     info!("UCI Protocol: sending to GUI: {data}");
 }
 
+#[cfg(test)]
+thread_local! {
+    static GUI_OUTPUT: std::cell::RefCell<Option<Arc<std::sync::Mutex<Vec<String>>>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Records what is sent to the GUI by this thread, and by the search threads it starts, so that
+/// tests running in parallel don't see each other's output
+#[cfg(test)]
+pub fn capture_gui_output() -> Arc<std::sync::Mutex<Vec<String>>> {
+    let gui_output = Arc::new(std::sync::Mutex::new(Vec::new()));
+    set_gui_output_capture(Some(Arc::clone(&gui_output)));
+    gui_output
+}
+
+#[cfg(test)]
+pub fn gui_output_capture() -> Option<Arc<std::sync::Mutex<Vec<String>>>> {
+    GUI_OUTPUT.with_borrow(|gui_output| gui_output.clone())
+}
+
+#[cfg(test)]
+pub fn set_gui_output_capture(gui_output: Option<Arc<std::sync::Mutex<Vec<String>>>>) {
+    GUI_OUTPUT.set(gui_output);
+}
+
 pub fn run_uci_position(uci_position_str: &str, go_options_str: &str) -> SearchResults {
     run_uci_position_using_t_table(uci_position_str, go_options_str, &TranspositionTable::new(500))
 }
@@ -189,7 +303,8 @@ pub fn run_uci_position_using_t_table(
 ) -> SearchResults {
     let uci_position = parse_position(uci_position_str).unwrap();
     let uci_go_options = parse_uci_go_options(Some(go_options_str.to_string()));
-    let search_params = create_search_params(&uci_go_options, &uci_position);
+    let search_params =
+        create_search_params(&uci_go_options, &uci_position, config::get_move_overhead());
     let position = &mut uci_position.end_position.clone();
     let mut search = Search::new(
         position,
@@ -209,6 +324,7 @@ mod tests {
     use crate::core::piece::PieceColor;
     use crate::core::piece::PieceColor::{Black, White};
     use crate::core::r#move::BaseMove;
+    use crate::eval::evaluation::{get_game_status, GameStatus};
 
     fn create_uci_position(side_to_move: PieceColor) -> UciPosition {
         let mut position = Position::new_game();
@@ -233,8 +349,24 @@ mod tests {
         assert!(parse_position("position startpos moves e2e4 e7e5").is_some());
         assert!(parse_position("position fen 8/8/8/8/4k3/8/8/2BQKB2 w - - 0 1 moves f1c4 e4e5")
             .is_some());
-        assert!(parse_position("position startpos moves e2e4 e7e4").is_none());
-        assert!(parse_position("position startpos moves e2e3 e7e5 b1c3 d7d5 a2a4 f8a3 b2a3 b8c6 f1b5 d8h4 c3d5 h4f2 e1f2    c8g1").is_none());
+        assert!(parse_position("position fen 8/8/8/8/8/8/8/8 w - - 0 1").is_none());
+        assert!(parse_position("position fen 8/8/8/8/4k3/8/8/2BQ1B2 w - - 0 1").is_none());
+    }
+
+    #[test]
+    fn test_parse_position_stops_at_illegal_move() {
+        let uci_position = parse_position("position startpos moves e2e4 e7e9").unwrap();
+        assert_eq!(
+            uci_position.end_position,
+            Position::from("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")
+        );
+        assert_eq!(uci_position.repetition_keys.len(), 2);
+
+        let uci_position = parse_position("position startpos moves e2e4 e7e4").unwrap();
+        assert_eq!(uci_position.position_move_pairs.unwrap().len(), 1);
+
+        let uci_position = parse_position("position startpos moves e2e3 e7e5 b1c3 d7d5 a2a4 f8a3 b2a3 b8c6 f1b5 d8h4 c3d5 h4f2 e1f2    c8g1").unwrap();
+        assert_eq!(uci_position.position_move_pairs.unwrap().len(), 13);
     }
 
     #[test]
@@ -255,6 +387,95 @@ mod tests {
         );
     }
 
+    fn assert_repetition_keys(uci_position: &UciPosition) {
+        let expected: Vec<RepetitionKey> =
+            uci_position.all_game_positions().iter().map(RepetitionKey::new).collect();
+        assert_eq!(uci_position.repetition_keys, expected);
+        assert_eq!(
+            uci_position.repetition_keys[0],
+            RepetitionKey::new(&uci_position.given_position)
+        );
+    }
+
+    #[test]
+    fn test_parse_position_startpos() {
+        for input in ["position startpos", "position startpos ", "position startpos moves"] {
+            let uci_position = parse_position(input).unwrap();
+            assert_eq!(uci_position.given_position, Position::new_game());
+            assert_eq!(uci_position.end_position, Position::new_game());
+            assert_eq!(uci_position.repetition_keys.len(), 1);
+            assert_repetition_keys(&uci_position);
+        }
+    }
+
+    #[test]
+    fn test_parse_position_startpos_with_moves() {
+        let uci_position = parse_position("position startpos moves e2e4 e7e5").unwrap();
+        assert_eq!(uci_position.given_position, Position::new_game());
+        assert_eq!(
+            uci_position.end_position,
+            Position::from("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2")
+        );
+        assert_eq!(uci_position.repetition_keys.len(), 3);
+        assert_repetition_keys(&uci_position);
+    }
+
+    #[test]
+    fn test_parse_position_fen() {
+        let fen = "8/8/8/8/4k3/8/8/2BQKB2 w - - 0 1";
+        let uci_position = parse_position(&format!("position fen {fen}")).unwrap();
+        assert_eq!(uci_position.given_position, Position::from(fen));
+        assert_eq!(uci_position.end_position, Position::from(fen));
+        assert_eq!(uci_position.repetition_keys.len(), 1);
+        assert_repetition_keys(&uci_position);
+    }
+
+    #[test]
+    fn test_parse_position_fen_with_moves() {
+        let fen = "8/8/8/8/4k3/8/8/2BQKB2 w - - 0 1";
+        let uci_position = parse_position(&format!("position fen {fen} moves f1c4 e4e5")).unwrap();
+        assert_eq!(uci_position.given_position, Position::from(fen));
+        assert_eq!(uci_position.end_position, Position::from("8/8/8/4k3/2B5/8/8/2BQK3 w - - 2 2"));
+        assert_eq!(uci_position.repetition_keys.len(), 3);
+        assert_repetition_keys(&uci_position);
+    }
+
+    #[test]
+    fn test_uci_position_repetition_count() {
+        let uci_position = parse_position("position startpos").unwrap();
+        assert_eq!(uci_position.repetition_count(), 1);
+        let uci_position = parse_position("position startpos moves g1f3 g8f6 f3g1 f6g8").unwrap();
+        assert_eq!(uci_position.repetition_count(), 2);
+        let uci_position =
+            parse_position("position startpos moves g1f3 g8f6 f3g1 f6g8 e2e4 e7e5").unwrap();
+        assert_eq!(uci_position.repetition_count(), 1);
+    }
+
+    #[test]
+    fn test_repetition_in_move_list_is_a_draw() {
+        let uci_position =
+            parse_position("position startpos moves g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8")
+                .unwrap();
+        assert_eq!(uci_position.repetition_keys.len(), 9);
+        assert_eq!(
+            get_game_status(&uci_position.end_position, &uci_position.repetition_keys),
+            GameStatus::DrawnByThreefoldRepetition
+        );
+    }
+
+    #[test]
+    fn test_repetition_before_fen_is_not_detected() {
+        let uci_position = parse_position(
+            "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 8 5 moves g1f3",
+        )
+        .unwrap();
+        assert_eq!(uci_position.repetition_keys.len(), 2);
+        assert_eq!(
+            get_game_status(&uci_position.end_position, &uci_position.repetition_keys),
+            GameStatus::InProgress
+        );
+    }
+
     #[test]
     fn test_parse_uci_go_options() {
         let command = "go wtime 10 btime 11 winc 2 binc 4 movestogo 23 depth 30 nodes 1001 mate 3 movetime 1234 ponder infinite searchmoves e2e4 e7e5".to_string();
@@ -268,8 +489,8 @@ mod tests {
         assert_eq!(uci_go_options.nodes, Some(1001));
         assert_eq!(uci_go_options.mate, Some(3));
         assert_eq!(uci_go_options.move_time, Some(1234));
-        assert_eq!(uci_go_options.ponder, true);
-        assert_eq!(uci_go_options.infinite, true);
+        assert!(uci_go_options.ponder);
+        assert!(uci_go_options.infinite);
         assert_eq!(
             uci_go_options.search_moves,
             Some(vec!(
@@ -283,26 +504,92 @@ mod tests {
     fn test_create_search_params_with_no_go_params() {
         let command = "go".to_string();
         let uci_go_options = parse_uci_go_options(Some(command));
-        let search_params = create_search_params(&uci_go_options, &create_uci_position(White));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
         assert_eq!(search_params.allocated_time_millis, usize::MAX);
         assert_eq!(search_params.max_depth, MAXIMUM_SEARCH_DEPTH as u8);
         assert_eq!(search_params.max_nodes, usize::MAX);
     }
 
+    #[test]
+    fn test_bare_go() {
+        assert!(parse_uci_go_options(Some("go".to_string())).is_bare());
+        assert!(parse_uci_go_options(Some("go searchmoves e2e4".to_string())).is_bare());
+        for command in
+            ["go infinite", "go depth 3", "go nodes 100", "go movetime 10", "go wtime 10"]
+        {
+            assert!(!parse_uci_go_options(Some(command.to_string())).is_bare(), "{command}");
+        }
+
+        // a go depth of zero leaves a bare go searching until stop
+        let uci_go_options = parse_uci_go_options(Some("go".to_string())).with_go_depth(0);
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
+        assert_eq!(search_params.max_depth, MAXIMUM_SEARCH_DEPTH as u8);
+
+        let uci_go_options = parse_uci_go_options(Some("go".to_string())).with_go_depth(5);
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
+        assert_eq!(search_params.max_depth, 5);
+        assert!(!search_params.is_time_limited());
+
+        let uci_go_options = parse_uci_go_options(Some("go infinite".to_string())).with_go_depth(5);
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
+        assert_eq!(search_params.max_depth, MAXIMUM_SEARCH_DEPTH as u8);
+    }
+
+    #[test]
+    fn test_bare_go_searches_to_the_go_depth() {
+        let uci_position = parse_position("position startpos moves e2e4 e7e5").unwrap();
+        let uci_go_options = parse_uci_go_options(Some("go".to_string())).with_go_depth(5);
+        let search_params = create_search_params(&uci_go_options, &uci_position, 0);
+        let mut position = uci_position.end_position;
+        let search_results = Search::new(
+            &mut position,
+            &TranspositionTable::new(500),
+            search_params,
+            Arc::new(AtomicBool::new(false)),
+            uci_position.repetition_keys,
+            MoveOrderer::new(),
+            0,
+        )
+        .go();
+        assert_eq!(search_results.depth, 5);
+        assert!(search_results.pv.len() > 1);
+    }
+
     #[test]
     fn test_create_search_params_time_white() {
         let command = "go wtime 1000 btime 1100 winc 200 binc 400".to_string();
         let uci_go_options = parse_uci_go_options(Some(command));
-        let search_params = create_search_params(&uci_go_options, &create_uci_position(White));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
         assert_eq!(search_params.allocated_time_millis, 133);
         assert_eq!(search_params.max_depth, MAXIMUM_SEARCH_DEPTH as u8);
         assert_eq!(search_params.max_nodes, usize::MAX);
     }
     #[test]
+    fn test_create_search_params_ponder() {
+        let command = "go ponder wtime 1000 btime 1100 winc 200 binc 400".to_string();
+        let uci_go_options = parse_uci_go_options(Some(command));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
+        assert!(!search_params.is_time_limited());
+    }
+    #[test]
+    fn test_create_search_params_search_moves() {
+        let command = "go depth 3 searchmoves e2e4 e2e5 d2d4".to_string();
+        let uci_go_options = parse_uci_go_options(Some(command));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
+        // the illegal move is ignored
+        assert_eq!(
+            search_params.search_moves.iter().map(|mv| mv.to_string()).collect::<Vec<_>>(),
+            vec!["e2-e4", "d2-d4"]
+        );
+        let uci_go_options = parse_uci_go_options(Some("go depth 3".to_string()));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
+        assert!(search_params.search_moves.is_empty());
+    }
+    #[test]
     fn test_create_search_params_time_black() {
         let command = "go wtime 1000 btime 1100 winc 200 binc 400".to_string();
         let uci_go_options = parse_uci_go_options(Some(command));
-        let search_params = create_search_params(&uci_go_options, &create_uci_position(Black));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(Black), 0);
         assert_eq!(search_params.allocated_time_millis, 236);
         assert_eq!(search_params.max_depth, MAXIMUM_SEARCH_DEPTH as u8);
         assert_eq!(search_params.max_nodes, usize::MAX);
@@ -311,7 +598,7 @@ mod tests {
     fn test_create_search_params_time_white_with_moves_to_go() {
         let command = "go wtime 10000 btime 1100 winc 200 binc 400 movestogo 10".to_string();
         let uci_go_options = parse_uci_go_options(Some(command));
-        let search_params = create_search_params(&uci_go_options, &create_uci_position(White));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
         assert_eq!(search_params.allocated_time_millis, 1100);
         assert_eq!(search_params.max_depth, MAXIMUM_SEARCH_DEPTH as u8);
         assert_eq!(search_params.max_nodes, usize::MAX);
@@ -320,7 +607,7 @@ mod tests {
     fn test_create_search_params_time_white_with_move_time() {
         let command = "go wtime 10000 btime 1100 winc 200 binc 400 movetime 1234".to_string();
         let uci_go_options = parse_uci_go_options(Some(command));
-        let search_params = create_search_params(&uci_go_options, &create_uci_position(White));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
         assert_eq!(search_params.allocated_time_millis, 1234);
         assert_eq!(search_params.max_depth, MAXIMUM_SEARCH_DEPTH as u8);
         assert_eq!(search_params.max_nodes, usize::MAX);
@@ -329,7 +616,7 @@ mod tests {
     fn test_create_search_params_depth() {
         let command = "go depth 3".to_string();
         let uci_go_options = parse_uci_go_options(Some(command));
-        let search_params = create_search_params(&uci_go_options, &create_uci_position(White));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
         assert_eq!(search_params.allocated_time_millis, usize::MAX);
         assert_eq!(search_params.max_depth, 3);
         assert_eq!(search_params.max_nodes, usize::MAX);
@@ -338,25 +625,80 @@ mod tests {
     fn test_create_search_params_depth_with_mate() {
         let command = "go depth 3 mate 5".to_string();
         let uci_go_options = parse_uci_go_options(Some(command));
-        let search_params = create_search_params(&uci_go_options, &create_uci_position(White));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
         assert_eq!(search_params.allocated_time_millis, usize::MAX);
-        assert_eq!(search_params.max_depth, 5);
+        assert_eq!(search_params.max_depth, 9);
         assert_eq!(search_params.max_nodes, usize::MAX);
 
         let command = "go depth 10 mate 5".to_string();
         let uci_go_options = parse_uci_go_options(Some(command));
-        let search_params = create_search_params(&uci_go_options, &create_uci_position(White));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
         assert_eq!(search_params.allocated_time_millis, usize::MAX);
         assert_eq!(search_params.max_depth, 10);
         assert_eq!(search_params.max_nodes, usize::MAX);
     }
+
+    #[test]
+    fn test_create_search_params_depth_beyond_maximum_search_depth() {
+        for command in ["go depth 64", "go depth 300", "go mate 40"] {
+            let uci_go_options = parse_uci_go_options(Some(command.to_string()));
+            let search_params =
+                create_search_params(&uci_go_options, &create_uci_position(White), 0);
+            assert_eq!(search_params.max_depth, MAXIMUM_SEARCH_DEPTH as u8, "{command}");
+        }
+    }
+
+    #[test]
+    fn test_go_depth_beyond_maximum_search_depth() {
+        let search_results = run_uci_position("position startpos", "go depth 64 nodes 5000");
+        assert!(search_results.depth < MAXIMUM_SEARCH_DEPTH as u8);
+        assert!(!search_results.pv.is_empty());
+
+        // the only moves are king moves so every iteration is quick
+        let search_results =
+            run_uci_position("position fen 8/8/3k4/8/8/4K3/8/8 w - - 0 1", "go depth 64");
+        assert_eq!(search_results.depth, MAXIMUM_SEARCH_DEPTH as u8);
+    }
+
+    #[test]
+    fn test_go_mate() {
+        let position = "position fen r2qk2r/pb4pp/1n2Pb2/2B2Q2/p1p5/2P5/2B2PPP/RN2R1K1 w - - 1 1";
+        let search_results = run_uci_position(position, "go mate 2");
+        assert_eq!(Search::mate_in_moves(search_results.score), Some(2));
+        assert_eq!(search_results.depth, 3);
+
+        let search_results = run_uci_position(position, "go mate 1");
+        assert_eq!(Search::mate_in_moves(search_results.score), None);
+        assert_eq!(search_results.depth, 1);
+    }
     #[test]
     fn test_create_search_params_nodes() {
         let command = "go nodes 1001".to_string();
         let uci_go_options = parse_uci_go_options(Some(command));
-        let search_params = create_search_params(&uci_go_options, &create_uci_position(White));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 0);
         assert_eq!(search_params.allocated_time_millis, usize::MAX);
         assert_eq!(search_params.max_depth, MAXIMUM_SEARCH_DEPTH as u8);
         assert_eq!(search_params.max_nodes, 1001);
     }
+
+    #[test]
+    fn test_create_search_params_with_move_overhead() {
+        let command = "go wtime 1000 btime 1100 winc 200 binc 400".to_string();
+        let uci_go_options = parse_uci_go_options(Some(command));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 50);
+        assert_eq!(search_params.allocated_time_millis, 133 - 50);
+
+        let command = "go movetime 1234".to_string();
+        let uci_go_options = parse_uci_go_options(Some(command));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 50);
+        assert_eq!(search_params.allocated_time_millis, 1234 - 50);
+        let search_params =
+            create_search_params(&uci_go_options, &create_uci_position(White), 2000);
+        assert_eq!(search_params.allocated_time_millis, 0);
+
+        let command = "go depth 3".to_string();
+        let uci_go_options = parse_uci_go_options(Some(command));
+        let search_params = create_search_params(&uci_go_options, &create_uci_position(White), 50);
+        assert_eq!(search_params.allocated_time_millis, usize::MAX);
+    }
 }
@@ -6,6 +6,8 @@ pub mod fen;
 
 pub mod perf_t;
 
+pub mod bench;
+
 pub mod move_formatter;
 pub mod node_counter;
 mod sq_macro_generator;
@@ -0,0 +1,59 @@
+use crate::core::position::Position;
+use crate::search::move_ordering::MoveOrderer;
+use crate::search::negamax::{Search, SearchParams};
+use crate::search::transposition_table::TranspositionTable;
+use crate::utils::node_counter::{NodeCountStats, NodeCounter};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+// the positions, depth and hash size are fixed so that node counts are comparable across builds
+pub const BENCH_DEPTH: u8 = 6;
+pub const BENCH_HASH_SIZE_MB: usize = 16;
+pub const BENCH_POSITIONS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "8/8/4k3/3p4/3P4/4K3/8/8 w - - 0 1",
+];
+
+pub fn bench() -> NodeCountStats {
+    bench_to_depth(BENCH_DEPTH)
+}
+
+fn bench_to_depth(depth: u8) -> NodeCountStats {
+    let mut node_counter = NodeCounter::new();
+    node_counter.reset();
+    for fen in BENCH_POSITIONS {
+        let mut position = Position::from(fen);
+        let transposition_table = TranspositionTable::new(BENCH_HASH_SIZE_MB);
+        let mut search = Search::new(
+            &mut position,
+            &transposition_table,
+            SearchParams::new_by_depth(depth as isize),
+            Arc::new(AtomicBool::new(false)),
+            vec![],
+            MoveOrderer::new(),
+            0,
+        );
+        search.go();
+        node_counter.add(search.node_counter.node_count());
+    }
+    node_counter.stats()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    // the search reads its settings from the runtime config, which some tests change
+    #[test]
+    #[serial]
+    fn test_bench_node_count_is_deterministic() {
+        assert_eq!(bench_to_depth(3).node_count, 77621);
+    }
+}
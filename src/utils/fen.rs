@@ -1,6 +1,6 @@
 use crate::core::board;
 use crate::core::board::Board;
-use crate::core::piece::Piece;
+use crate::core::piece::{Piece, PieceColor, PieceType};
 use crate::core::position::Position;
 use crate::utils::util;
 use itertools::Itertools;
@@ -8,6 +8,10 @@ use once_cell::sync::Lazy;
 use regex::{Captures, Regex};
 use thiserror::Error;
 
+// the 75 move rule ends a game long before either counter gets this high
+pub const MAXIMUM_HALF_MOVE_CLOCK: usize = 1000;
+pub const MAXIMUM_FULL_MOVE_NUMBER: usize = 10000;
+
 static FEN_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(
     r"^(?<board>((?<RankItem>[pnbrqkPNBRQK1-8]{1,8})/?){8})\s+(?<side_to_move>[bw])\s+(?<castling_rights>-|K?Q?k?q?)\s+(?<en_passant_target_square>-|[a-h][3-6])\s+(?<halfmove_clock>\d+)\s+(?<fullmove_number>\d+)\s*$"
@@ -18,6 +22,10 @@ static FEN_REGEX: Lazy<Regex> = Lazy::new(|| {
 pub enum ErrorKind {
     #[error("Failed to parse fen: {0}")]
     InvalidFen(String),
+    #[error("Invalid move counter in fen: {0}")]
+    InvalidMoveCounter(String),
+    #[error("Fen must have one king of each color: {0}")]
+    InvalidKingCount(String),
 }
 
 pub struct Fen {
@@ -42,24 +50,29 @@ impl From<&Position> for Fen {
 impl<'a> TryFrom<Captures<'a>> for FenParts<'a> {
     type Error = ErrorKind;
     fn try_from(captures: Captures<'a>) -> Result<Self, Self::Error> {
+        let parse_counter = |name: &str, maximum: usize| {
+            captures[name]
+                .parse::<usize>()
+                .ok()
+                .filter(|&counter| counter <= maximum)
+                .ok_or_else(|| ErrorKind::InvalidMoveCounter(captures[0].to_string()))
+        };
         Ok(FenParts {
             board: expand_board(captures.name("board").unwrap().as_str()),
             side_to_move: captures.name("side_to_move").unwrap().as_str(),
             castling_rights: captures.name("castling_rights").unwrap().as_str(),
             en_passant_target_square: captures.name("en_passant_target_square").unwrap().as_str(),
-            halfmove_clock: captures.name("halfmove_clock").unwrap().as_str().parse().unwrap(),
-            fullmove_number: captures.name("fullmove_number").unwrap().as_str().parse().unwrap(),
-        })
-        .map_err(|_: std::num::ParseIntError| {
-            ErrorKind::InvalidFen(captures.name("fen").unwrap().as_str().to_string())
+            halfmove_clock: parse_counter("halfmove_clock", MAXIMUM_HALF_MOVE_CLOCK)?,
+            // puzzle fens often give the full move number as 0
+            fullmove_number: parse_counter("fullmove_number", MAXIMUM_FULL_MOVE_NUMBER)?.max(1),
         })
     }
 }
 pub fn parse(fen: String) -> Result<Position, ErrorKind> {
     let fen_parts = FEN_REGEX
         .captures(&fen)
-        .and_then(|caps| FenParts::try_from(caps).ok())
-        .ok_or_else(|| ErrorKind::InvalidFen(fen.clone()))?;
+        .ok_or_else(|| ErrorKind::InvalidFen(fen.clone()))
+        .and_then(FenParts::try_from)?;
 
     if fen_parts.board.chars().count() != board::NUMBER_OF_SQUARES {
         return Err(ErrorKind::InvalidFen(fen.clone()));
@@ -71,6 +84,13 @@ pub fn parse(fen: String) -> Result<Position, ErrorKind> {
             board.put_piece(i, Piece::from_char(ch).unwrap());
         }
     }
+    // the rest of the engine assumes that both kings are on the board
+    if [PieceColor::White, PieceColor::Black]
+        .iter()
+        .any(|&piece_color| board.get_piece_count(piece_color, PieceType::King) != 1)
+    {
+        return Err(ErrorKind::InvalidKingCount(fen.clone()));
+    }
 
     Ok(Position::new(
         board,
@@ -193,6 +213,38 @@ mod tests {
             "Failed to parse fen: rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 A"
         );
     }
+    #[test]
+    fn test_parse_move_counters() {
+        let parse_counters = |counters: &str| {
+            parse(format!("4k3/8/8/8/8/8/8/4K3 w - - {counters}"))
+                .map(|position| (position.half_move_clock(), position.full_move_number()))
+        };
+        assert_eq!(parse_counters("0 1"), Ok((0, 1)));
+        assert_eq!(parse_counters("1000 10000"), Ok((1000, 10000)));
+        assert_eq!(parse_counters("1 0"), Ok((1, 1)));
+        for counters in ["1001 1", "0 10001", "99999999999999999999999 1", "0 18446744073709551616"]
+        {
+            assert_eq!(
+                parse_counters(counters),
+                Err(ErrorKind::InvalidMoveCounter(format!("4k3/8/8/8/8/8/8/4K3 w - - {counters}")))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_king_count() {
+        assert!(parse("4k3/8/8/8/8/8/8/4K3 w - - 0 1".to_string()).is_ok());
+        for fen in [
+            "8/8/8/8/8/8/8/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/8 w - - 0 1",
+            "8/8/8/8/8/8/4P3/4K3 b - - 0 1",
+            "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+            "3kk3/8/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            assert_eq!(parse(fen.to_string()), Err(ErrorKind::InvalidKingCount(fen.to_string())));
+        }
+    }
+
     #[test]
     fn test_write_1() {
         let fen = "r6r/1b2k1bq/8/8/7B/8/8/R3K2R b Kq h3 9 22";
@@ -3,11 +3,11 @@ use crate::core::board::BoardSide::KingSide;
 use crate::core::move_gen::generate_moves;
 use crate::core::piece::PieceType::Pawn;
 use crate::core::position::Position;
-use crate::core::{piece::Piece, piece::PieceType, r#move::Move};
+use crate::core::{piece::Piece, piece::PieceColor, piece::PieceType, r#move::Move};
 use crate::eval::evaluation;
 use crate::eval::evaluation::GameStatus;
 use crate::search::negamax::SearchResults;
-use crate::utils::move_formatter::MoveFormat::{LongAlgebraic, ShortAlgebraic};
+use crate::utils::move_formatter::MoveFormat::{LongAlgebraic, ShortAlgebraic, StandardAlgebraic};
 use crate::utils::util;
 use phf::phf_map;
 
@@ -15,6 +15,8 @@ include!("generated_macro.rs");
 
 pub const SHORT_FORMATTER: MoveFormatter = MoveFormatter::new(ShortAlgebraic);
 pub const LONG_FORMATTER: MoveFormatter = MoveFormatter::new(LongAlgebraic);
+/// Plain ASCII standard algebraic notation as used in PGN
+pub const PGN_FORMATTER: MoveFormatter = MoveFormatter::new(StandardAlgebraic);
 
 pub trait FormatMove {
     fn format_move_list(&self, position: &Position, moves: &[Move]) -> Option<Vec<String>>;
@@ -24,6 +26,7 @@ pub trait FormatMove {
 pub enum MoveFormat {
     ShortAlgebraic,
     LongAlgebraic,
+    StandardAlgebraic,
 }
 
 const PIECE_CHAR_TO_UNICODE: phf::Map<char, char> = phf_map! {
@@ -76,10 +79,11 @@ impl MoveFormatter {
     ) -> String {
         match mov {
             Move::Castling { base_move: _, board_side } => {
-                if *board_side == KingSide {
-                    "0-0".to_string()
+                let castling = if *board_side == KingSide { "0-0" } else { "0-0-0" };
+                if self.move_format == StandardAlgebraic {
+                    format!("{}{}", castling.replace('0', "O"), self.get_result(next_position))
                 } else {
-                    "0-0-0".to_string()
+                    castling.to_string()
                 }
             }
             _ => self.basic_format(position, mov, next_position),
@@ -100,14 +104,16 @@ impl MoveFormatter {
     }
     fn get_piece(&self, position: &Position, mov: &Move) -> String {
         let piece = self.get_moved_piece(position, mov);
-        if piece.piece_type != PieceType::Pawn {
-            PIECE_CHAR_TO_UNICODE[&piece.to_char()].to_string()
-        } else {
+        if piece.piece_type == PieceType::Pawn {
             "".to_string()
+        } else if self.move_format == StandardAlgebraic {
+            piece.to_char().to_ascii_uppercase().to_string()
+        } else {
+            PIECE_CHAR_TO_UNICODE[&piece.to_char()].to_string()
         }
     }
     fn get_from_square(&self, position: &Position, mov: &Move) -> String {
-        if self.move_format == ShortAlgebraic {
+        if self.move_format != LongAlgebraic {
             let piece = self.get_moved_piece(position, mov);
             if piece.piece_type == Pawn {
                 if mov.get_base_move().capture {
@@ -129,7 +135,7 @@ impl MoveFormatter {
     fn get_from_to_separator(&self, mov: &Move) -> String {
         if mov.get_base_move().capture {
             'x'.to_string()
-        } else if self.move_format == ShortAlgebraic {
+        } else if self.move_format != LongAlgebraic {
             "".to_string()
         } else {
             "-".to_string()
@@ -142,6 +148,14 @@ impl MoveFormatter {
 
     fn get_promotion_piece(&self, position: &Position, mov: &Move) -> String {
         match mov {
+            Move::Promotion { base_move: _, promote_to }
+                if self.move_format == StandardAlgebraic =>
+            {
+                format!(
+                    "={}",
+                    Piece { piece_color: PieceColor::White, piece_type: *promote_to }.to_char()
+                )
+            }
             Move::Promotion { base_move: _, promote_to } => PIECE_CHAR_TO_UNICODE[&Piece {
                 piece_color: position.side_to_move(),
                 piece_type: *promote_to,
@@ -154,7 +168,11 @@ impl MoveFormatter {
 
     fn get_en_passant_indicator(&self, mov: &Move) -> String {
         match mov {
-            Move::EnPassant { base_move: _, capture_square: _ } => "ep".to_string(),
+            Move::EnPassant { base_move: _, capture_square: _ }
+                if self.move_format != StandardAlgebraic =>
+            {
+                "ep".to_string()
+            }
             _ => String::new(),
         }
     }
@@ -162,6 +180,9 @@ impl MoveFormatter {
     fn get_result(&self, next_position: &Position) -> String {
         match evaluation::get_game_status(next_position, &[]) {
             GameStatus::Checkmate => "#".to_string(),
+            _ if self.move_format == StandardAlgebraic => {
+                if evaluation::is_check(next_position) { "+" } else { "" }.to_string()
+            }
             _ => "+".repeat(evaluation::check_count(next_position)).to_string(),
         }
     }
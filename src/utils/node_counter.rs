@@ -1,26 +1,49 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
+// nps is calculated over at least this period so that tiny elapsed times at the start of a search
+// don't produce absurdly large values
+const MINIMUM_NPS_ELAPSED_MICROS: u128 = 10_000;
+
 #[derive(Debug)]
 pub struct NodeCountStats {
+    /// All the nodes, including the quiescence nodes
     pub node_count: usize,
+    pub quiescence_node_count: usize,
     pub start_time: Instant,
     pub nodes_per_second: usize,
     pub elapsed_time: Duration,
 }
+impl NodeCountStats {
+    pub fn quiescence_node_percentage(&self) -> usize {
+        self.quiescence_node_count * 100 / self.node_count.max(1)
+    }
+}
+
 pub struct NodeCounter {
     node_counter: AtomicUsize,
+    quiescence_node_counter: AtomicUsize,
     start_time: Instant,
 }
 
 impl NodeCounter {
     pub(crate) fn new() -> Self {
-        NodeCounter { node_counter: AtomicUsize::new(0), start_time: Instant::now() }
+        NodeCounter {
+            node_counter: AtomicUsize::new(0),
+            quiescence_node_counter: AtomicUsize::new(0),
+            start_time: Instant::now(),
+        }
     }
     pub(crate) fn increment(&self) -> usize {
         self.node_counter.fetch_add(1, Ordering::Relaxed)
     }
 
+    /// Counts a quiescence node, which is also counted in the total
+    pub(crate) fn increment_quiescence(&self) -> usize {
+        self.quiescence_node_counter.fetch_add(1, Ordering::Relaxed);
+        self.increment()
+    }
+
     pub(crate) fn add(&self, count: usize) {
         self.node_counter.fetch_add(count, Ordering::Relaxed);
     }
@@ -29,24 +52,70 @@ impl NodeCounter {
         self.node_counter.load(Ordering::SeqCst)
     }
 
+    pub fn quiescence_node_count(&self) -> usize {
+        self.quiescence_node_counter.load(Ordering::SeqCst)
+    }
+
     pub(crate) fn reset(&mut self) {
         self.node_counter.store(0, Ordering::Relaxed);
+        self.quiescence_node_counter.store(0, Ordering::Relaxed);
         self.start_time = Instant::now();
     }
 
     pub(crate) fn stats(&self) -> NodeCountStats {
         let elapsed = self.start_time.elapsed();
-        let elapsed_micros = elapsed.as_micros();
-        let node_count_stats: NodeCountStats = NodeCountStats {
-            node_count: self.node_count(),
+        let node_count = self.node_count();
+        NodeCountStats {
+            node_count,
+            quiescence_node_count: self.quiescence_node_count(),
             start_time: self.start_time,
-            nodes_per_second: if elapsed_micros != 0 {
-                (self.node_count() * 1000000) / elapsed_micros as usize
-            } else {
-                0
-            },
+            nodes_per_second: nodes_per_second(node_count, elapsed),
             elapsed_time: elapsed,
-        };
-        node_count_stats
+        }
+    }
+}
+
+fn nodes_per_second(node_count: usize, elapsed: Duration) -> usize {
+    let elapsed_micros = elapsed.as_micros().max(MINIMUM_NPS_ELAPSED_MICROS);
+    (node_count as u128 * 1_000_000 / elapsed_micros) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nodes_per_second() {
+        assert_eq!(nodes_per_second(1_000_000, Duration::from_secs(2)), 500_000);
+        assert_eq!(nodes_per_second(0, Duration::from_secs(1)), 0);
+    }
+
+    #[test]
+    fn test_nodes_per_second_with_tiny_elapsed_time() {
+        assert_eq!(nodes_per_second(100, Duration::ZERO), 10_000);
+        assert_eq!(nodes_per_second(100, Duration::from_micros(1)), 10_000);
+    }
+
+    #[test]
+    fn test_stats() {
+        let counter = NodeCounter::new();
+        counter.add(50);
+        let stats = counter.stats();
+        assert_eq!(stats.node_count, 50);
+        assert!(stats.nodes_per_second <= 5_000);
+    }
+
+    #[test]
+    fn test_quiescence_nodes_are_included_in_the_total() {
+        let counter = NodeCounter::new();
+        counter.increment();
+        counter.increment_quiescence();
+        counter.increment_quiescence();
+        counter.increment_quiescence();
+        let stats = counter.stats();
+        assert_eq!(stats.node_count, 4);
+        assert_eq!(stats.quiescence_node_count, 3);
+        assert_eq!(stats.quiescence_node_percentage(), 75);
+        assert_eq!(NodeCounter::new().stats().quiescence_node_percentage(), 0);
     }
 }
@@ -12,7 +12,7 @@ use std::ops::Add;
 include!("generated_macro.rs");
 
 static RAW_MOVE_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(^(?P<from>[a-h][0-8])(?P<to>[a-h][0-8])(?P<promote_to>[nbrq])?$)").unwrap()
+    Regex::new(r"(^(?P<from>[a-h][1-8])(?P<to>[a-h][1-8])(?P<promote_to>[nbrq])?$)").unwrap()
 });
 
 pub fn create_color(initial: &str) -> Option<PieceColor> {
@@ -149,6 +149,27 @@ pub fn replay_move_string(
     replay_raw_moves(position, &moves_string_to_raw_moves(raw_moves_string)?)
 }
 
+/// Replays moves until one is malformed or illegal, returning the moves replayed and the move that
+/// could not be played, if any
+pub fn replay_legal_moves(
+    position: &Position,
+    raw_moves_string: &str,
+) -> (Vec<(Position, Move)>, Option<String>) {
+    let mut position_move_pairs: Vec<(Position, Move)> = Vec::new();
+    for raw_move_string in raw_moves_string.split_whitespace() {
+        let mut current_position = position_move_pairs.last().map_or(*position, |pm| pm.0);
+        match parse_move(raw_move_string.to_string())
+            .and_then(|rm| current_position.make_raw_move(&rm))
+        {
+            Some(undo_move_info) => {
+                position_move_pairs.push((current_position, undo_move_info.mov))
+            }
+            None => return (position_move_pairs, Some(raw_move_string.to_string())),
+        }
+    }
+    (position_move_pairs, None)
+}
+
 pub fn replay_moves(position: &Position, moves: &[Move]) -> Option<Vec<(Position, Move)>> {
     replay_raw_moves(position, &r#move::convert_moves_to_raw(moves))
 }
@@ -183,14 +204,18 @@ pub fn create_repetition_keys(
     position: &Position,
     raw_moves_string: String,
 ) -> Option<Vec<RepetitionKey>> {
+    Some(repetition_keys_from_history(position, &replay_move_string(position, raw_moves_string)?))
+}
+
+/// Creates the repetition keys for a position followed by the positions reached by the moves played
+/// from it. Anything played before the given position is unknown so can't count towards a repetition.
+pub fn repetition_keys_from_history(
+    position: &Position,
+    position_move_pairs: &[(Position, Move)],
+) -> Vec<RepetitionKey> {
     std::iter::once(RepetitionKey::new(position))
-        .chain(
-            replay_move_string(position, raw_moves_string)?
-                .into_iter()
-                .map(|(pos, _)| RepetitionKey::new(&pos)),
-        )
-        .collect::<Vec<RepetitionKey>>()
-        .into()
+        .chain(position_move_pairs.iter().map(|(pos, _)| RepetitionKey::new(pos)))
+        .collect()
 }
 
 pub fn parse_initial_moves(raw_move_strings: Vec<String>) -> Option<Vec<RawMove>> {
@@ -369,10 +394,10 @@ mod tests {
     }
     #[test]
     fn test_print_bitboard() {
-        let board: u64 = 1 as u64;
+        let board: u64 = 1_u64;
         print_bitboard(board);
 
-        let board: u64 = (1 as u64) << 63;
+        let board: u64 = 1_u64 << 63;
         print_bitboard(board);
     }
 
@@ -506,6 +531,22 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_replay_legal_moves() {
+        let position = Position::new_game();
+        let (moves, illegal_move) = replay_legal_moves(&position, "e2e4 e7e5");
+        assert_eq!(moves.len(), 2);
+        assert_eq!(illegal_move, None);
+
+        let (moves, illegal_move) = replay_legal_moves(&position, "e2e4 e7e9 g1f3");
+        assert_eq!(moves.len(), 1);
+        assert_eq!(illegal_move, Some("e7e9".to_string()));
+
+        let (moves, illegal_move) = replay_legal_moves(&position, "e2e5");
+        assert!(moves.is_empty());
+        assert_eq!(illegal_move, Some("e2e5".to_string()));
+    }
+
     #[test]
     fn test_create_repetition_keys() {
         let mut position = Position::new_game();
@@ -564,65 +605,65 @@ mod tests {
         fn test_is_piece_pinned() {
             let fen: &str = "R1n1k3/8/8/8/8/8/8/4K3 w - - 0 1";
             let position: Position = Position::from(fen);
-            assert_eq!(is_piece_pinned(&position, sq!("c8"), PieceColor::White), true);
+            assert!(is_piece_pinned(&position, sq!("c8"), PieceColor::White));
         }
 
         #[test]
         fn test_is_piece_pinned2() {
             let fen: &str = "Q1n1k3/8/8/8/8/8/8/4K3 w - - 0 1";
             let position: Position = Position::from(fen);
-            assert_eq!(is_piece_pinned(&position, sq!("c8"), Black), false);
+            assert!(!is_piece_pinned(&position, sq!("c8"), Black));
         }
 
         #[test]
         fn test_is_piece_pinned3() {
             let fen: &str = "B1n1k3/8/8/8/8/8/8/4K3 w - - 0 1";
             let position: Position = Position::from(fen);
-            assert_eq!(is_piece_pinned(&position, sq!("c8"), PieceColor::White), false);
+            assert!(!is_piece_pinned(&position, sq!("c8"), PieceColor::White));
         }
 
         #[test]
         fn test_is_piece_pinned4() {
             let fen: &str = "RBn1k3/8/8/8/8/8/8/4K3 w - - 0 1";
             let position: Position = Position::from(fen);
-            assert_eq!(is_piece_pinned(&position, sq!("c8"), PieceColor::White), false);
+            assert!(!is_piece_pinned(&position, sq!("c8"), PieceColor::White));
         }
 
         #[test]
         fn test_is_piece_pinned5() {
             let fen: &str = "Q2nk3/8/8/8/8/8/8/4K3 w - - 0 1";
             let position: Position = Position::from(fen);
-            assert_eq!(is_piece_pinned(&position, sq!("d8"), PieceColor::White), true);
+            assert!(is_piece_pinned(&position, sq!("d8"), PieceColor::White));
         }
 
         #[test]
         fn test_is_piece_pinned6() {
             let fen: &str = "3nk3/8/8/1q6/8/3N4/8/5K2 w - - 0 1";
             let position: Position = Position::from(fen);
-            assert_eq!(is_piece_pinned(&position, sq!("d3"), PieceColor::Black), true);
+            assert!(is_piece_pinned(&position, sq!("d3"), PieceColor::Black));
         }
 
         #[test]
         fn test_is_piece_pinned7() {
             let fen: &str = "3nk3/8/5r2/1b6/8/3N1R2/8/5K2 w - - 0 1";
             let position: Position = Position::from(fen);
-            assert_eq!(is_piece_pinned(&position, sq!("d3"), PieceColor::Black), true);
-            assert_eq!(is_piece_pinned(&position, sq!("e3"), PieceColor::Black), false);
-            assert_eq!(is_piece_pinned(&position, sq!("f3"), PieceColor::Black), true);
+            assert!(is_piece_pinned(&position, sq!("d3"), PieceColor::Black));
+            assert!(!is_piece_pinned(&position, sq!("e3"), PieceColor::Black));
+            assert!(is_piece_pinned(&position, sq!("f3"), PieceColor::Black));
         }
 
         #[test]
         fn test_is_piece_pinned8() {
             let fen: &str = "4k2K/8/8/8/8/8/1R6/b7 b - - 1 1";
             let position: Position = Position::from(fen);
-            assert_eq!(is_piece_pinned(&position, sq!("b2"), PieceColor::Black), true);
+            assert!(is_piece_pinned(&position, sq!("b2"), PieceColor::Black));
         }
 
         #[test]
         fn test_is_piece_pinned9() {
             let fen: &str = "4k2K/8/8/8/8/8/1R6/r7 b - - 1 1";
             let position: Position = Position::from(fen);
-            assert_eq!(is_piece_pinned(&position, sq!("b2"), PieceColor::Black), false);
+            assert!(!is_piece_pinned(&position, sq!("b2"), PieceColor::Black));
         }
     }
 }
@@ -27,12 +27,10 @@ fn test_fen_new_game_position() {
 #[serial]
 fn test_fens() {
     let test_cases = load_fens().unwrap();
-    let mut test_number = 0;
-    for test in test_cases {
+    for (test_number, test) in test_cases.into_iter().enumerate() {
         let position = fen::parse(test.fen).expect("Failed to parse FEN");
         let node_count_stats = perf_t::count_nodes(&position, test.depth);
         assert_eq!(node_count_stats.node_count, test.nodes, "Test {}", test_number);
-        test_number += 1;
         println!("{:?}", node_count_stats);
     }
 }